@@ -0,0 +1,6 @@
+-- =====================================
+-- زمان آخرین تغییر رمز عبور (PostgreSQL)
+-- =====================================
+-- توکن‌هایی که قبل از این زمان صادر شدن دیگه معتبر نیستن
+
+ALTER TABLE users ADD COLUMN password_changed_at TIMESTAMPTZ;
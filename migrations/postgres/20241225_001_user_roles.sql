@@ -0,0 +1,7 @@
+-- =====================================
+-- نقش کاربران (PostgreSQL)
+-- =====================================
+-- مقادیر مجاز: 'user' و 'admin'
+
+ALTER TABLE users ADD COLUMN role TEXT NOT NULL DEFAULT 'user'
+    CHECK (role IN ('user', 'admin'));
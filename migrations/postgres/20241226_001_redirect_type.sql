@@ -0,0 +1,7 @@
+-- =====================================
+-- نوع redirect هر لینک (PostgreSQL)
+-- =====================================
+-- status code HTTP: 301، 302، 307 یا 308
+
+ALTER TABLE urls ADD COLUMN redirect_type INTEGER NOT NULL DEFAULT 307
+    CHECK (redirect_type IN (301, 302, 307, 308));
@@ -0,0 +1,7 @@
+-- =====================================
+-- سقف کلیک هر لینک (PostgreSQL)
+-- =====================================
+-- NULL یعنی بدون سقف؛ وقتی clicks به max_clicks برسه لینک دیگه باز نمیشه
+
+ALTER TABLE urls ADD COLUMN max_clicks BIGINT
+    CHECK (max_clicks > 0);
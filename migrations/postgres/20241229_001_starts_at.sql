@@ -0,0 +1,6 @@
+-- =====================================
+-- زمان شروع لینک (not-before) (PostgreSQL)
+-- =====================================
+-- NULL یعنی لینک از لحظه ساخت فعاله؛ قبل از starts_at لینک باز نمیشه
+
+ALTER TABLE urls ADD COLUMN starts_at TIMESTAMPTZ;
@@ -0,0 +1,7 @@
+-- =====================================
+-- زمان آخرین تغییر رمز عبور
+-- =====================================
+-- توکن‌هایی که قبل از این زمان صادر شدن دیگه معتبر نیستن
+-- برای کاربرهای قدیمی NULL میمونه (هیچ توکنی باطل نمیشه)
+
+ALTER TABLE users ADD COLUMN password_changed_at DATETIME;
@@ -0,0 +1,25 @@
+-- =====================================
+-- Refresh Token‌ها
+-- =====================================
+-- فقط هش SHA-256 توکن ذخیره میشه، نه خود توکن
+-- هر ورود یه family جدید میسازه؛ rotation داخل همون family میمونه
+-- تا اگه توکن قدیمی دوباره استفاده شد، کل family باطل بشه
+
+CREATE TABLE IF NOT EXISTS refresh_tokens (
+    id TEXT PRIMARY KEY NOT NULL,
+    user_id TEXT NOT NULL,
+    token_hash TEXT UNIQUE NOT NULL,
+    family_id TEXT NOT NULL,
+    expires_at DATETIME NOT NULL,
+    revoked_at DATETIME,
+    
+    -- شناسه توکنی که جایگزین این یکی شده (بعد از rotation)
+    replaced_by TEXT,
+    
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_refresh_tokens_user_id ON refresh_tokens(user_id);
+CREATE INDEX IF NOT EXISTS idx_refresh_tokens_family_id ON refresh_tokens(family_id);
@@ -0,0 +1,24 @@
+-- =====================================
+-- API Key‌ها
+-- =====================================
+-- برای دسترسی برنامه‌ای (CI، سرویس‌های داخلی) بدون ورود
+-- فقط هش SHA-256 کلید ذخیره میشه؛ key_prefix برای شناسایی در UI هست
+
+CREATE TABLE IF NOT EXISTS api_keys (
+    id TEXT PRIMARY KEY NOT NULL,
+    user_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    key_prefix TEXT NOT NULL,
+    key_hash TEXT UNIQUE NOT NULL,
+    
+    -- scope‌ها با کاما جدا میشن (مثلا "urls:read,urls:write")
+    scopes TEXT NOT NULL,
+    
+    last_used_at DATETIME,
+    revoked_at DATETIME,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_keys_user_id ON api_keys(user_id);
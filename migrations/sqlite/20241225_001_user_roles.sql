@@ -0,0 +1,8 @@
+-- =====================================
+-- نقش کاربران (RBAC)
+-- =====================================
+-- مقادیر مجاز: 'user' و 'admin'
+-- برای ساخت اولین ادمین:
+--   UPDATE users SET role = 'admin' WHERE email = 'you@example.com';
+
+ALTER TABLE users ADD COLUMN role TEXT NOT NULL DEFAULT 'user';
@@ -0,0 +1,7 @@
+-- =====================================
+-- نوع redirect هر لینک
+-- =====================================
+-- status code HTTP: 301، 302، 307 یا 308
+-- لینک‌های قبلی 307 میمونن (رفتار قبل از این migration)
+
+ALTER TABLE urls ADD COLUMN redirect_type INTEGER NOT NULL DEFAULT 307;
@@ -0,0 +1,6 @@
+-- =====================================
+-- رمز لینک
+-- =====================================
+-- هش Argon2 (مثل users.password_hash)؛ NULL یعنی لینک رمز نداره
+
+ALTER TABLE urls ADD COLUMN password_hash TEXT;
@@ -0,0 +1,6 @@
+-- =====================================
+-- سقف کلیک هر لینک
+-- =====================================
+-- NULL یعنی بدون سقف؛ وقتی clicks به max_clicks برسه لینک دیگه باز نمیشه
+
+ALTER TABLE urls ADD COLUMN max_clicks INTEGER;
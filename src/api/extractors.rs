@@ -12,14 +12,18 @@
 //! وقتی یه extractor به عنوان پارامتر handler تعریف میشه،
 //! axum قبل از اجرای handler، extractor رو اجرا میکنه.
 
+use std::net::{IpAddr, SocketAddr};
+
 use axum::{
     async_trait,
-    extract::FromRequestParts,
+    extract::{ConnectInfo, FromRequestParts},
     http::{header, request::Parts, HeaderMap},
 };
 
 use crate::{
+    config::TrustedProxies,
     error::AppError,
+    models::{ApiScope, API_KEY_PREFIX},
     services::AppState,
 };
 
@@ -78,24 +82,94 @@ impl FromRequestParts<AppState> for BearerToken {
     }
 }
 
+// =====================================
+// Credential
+// =====================================
+/// نوع اعتبارنامه‌ای که درخواست باهاش احراز هویت شده
+///
+/// # مفاهیم:
+/// - `Session`: JWT از `login` - دسترسی کامل به حساب
+/// - `ApiKey`: فقط به scope‌های خود کلید دسترسی داره
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Credential {
+    Session,
+    ApiKey {
+        key_id: String,
+        scopes: Vec<ApiScope>,
+    },
+}
+
+impl Credential {
+    /// بررسی دسترسی به یک scope
+    ///
+    /// نشست‌های JWT همه scope‌ها رو دارن
+    ///
+    /// # Errors
+    /// `Forbidden` اگه API key این scope رو نداشته باشه
+    pub fn require(&self, scope: ApiScope) -> Result<(), AppError> {
+        match self {
+            Self::Session => Ok(()),
+            Self::ApiKey { scopes, .. } if scopes.contains(&scope) => Ok(()),
+            Self::ApiKey { .. } => Err(AppError::Forbidden(format!(
+                "API key is missing the '{}' scope",
+                scope
+            ))),
+        }
+    }
+    
+    /// فقط نشست JWT قبوله (مثلا برای مدیریت حساب و خود API key‌ها)
+    ///
+    /// # Errors
+    /// `Forbidden` اگه درخواست با API key اومده باشه
+    pub fn require_session(&self) -> Result<(), AppError> {
+        match self {
+            Self::Session => Ok(()),
+            Self::ApiKey { .. } => Err(AppError::Forbidden(
+                "This endpoint requires a user session, not an API key".to_string()
+            )),
+        }
+    }
+}
+
+/// پیدا کردن API key در header‌ها
+///
+/// `X-API-Key: <key>` یا `Authorization: Bearer usk_...`
+pub(crate) fn api_key_from_headers(headers: &HeaderMap) -> Option<String> {
+    if let Some(key) = headers.get(API_KEY_HEADER).and_then(|v| v.to_str().ok()) {
+        return Some(key.trim().to_string());
+    }
+    
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .filter(|token| token.starts_with(API_KEY_PREFIX))
+        .map(ToString::to_string)
+}
+
+/// نام header برای API key
+pub const API_KEY_HEADER: &str = "X-API-Key";
+
 // =====================================
 // Auth User Extractor
 // =====================================
 /// استخراج کاربر احراز هویت شده
 ///
 /// # مفاهیم:
-/// - این extractor توکن رو verify میکنه
-/// - user_id رو برمیگردونه
+/// - هم JWT (`Authorization: Bearer <jwt>`) و هم API key قبوله
+/// - توکن‌های قبل از تغییر رمز عبور رد میشن (یه query به دیتابیس)
+/// - user_id و نوع اعتبارنامه رو برمیگردونه
 /// - اگه توکن نامعتبر باشه، 401 برمیگرده
 ///
 /// # استفاده:
 /// ```rust,ignore
-/// async fn handler(AuthUser(user_id): AuthUser) -> ... {
+/// async fn handler(AuthUser(user_id, credential): AuthUser) -> ... {
+///     credential.require(ApiScope::UrlsWrite)?;
 ///     // user_id شناسه کاربر هست
 /// }
 /// ```
 #[derive(Debug, Clone)]
-pub struct AuthUser(pub String);
+pub struct AuthUser(pub String, pub Credential);
 
 #[async_trait]
 impl FromRequestParts<AppState> for AuthUser {
@@ -105,13 +179,63 @@ impl FromRequestParts<AppState> for AuthUser {
         parts: &mut Parts,
         state: &AppState,
     ) -> Result<Self, Self::Rejection> {
-        // اول توکن رو بگیر
+        // اول API key
+        if let Some(key) = api_key_from_headers(&parts.headers) {
+            let api_key = state.api_key_service.authenticate(&key).await?;
+            let credential = Credential::ApiKey {
+                scopes: api_key.scopes(),
+                key_id: api_key.id,
+            };
+            
+            return Ok(AuthUser(api_key.user_id, credential));
+        }
+        
+        // بعد توکن JWT
         let BearerToken(token) = BearerToken::from_request_parts(parts, state).await?;
         
-        // Verify کردن توکن
-        let claims = state.auth_service.verify_token(&token)?;
+        // Verify کردن توکن و وضعیت کاربر
+        let claims = state.auth_service.authenticate(&token).await?;
+        
+        Ok(AuthUser(claims.sub, Credential::Session))
+    }
+}
+
+// =====================================
+// Admin User Extractor
+// =====================================
+/// استخراج کاربر ادمین
+///
+/// # مفاهیم:
+/// - اول مثل `AuthUser` احراز هویت میکنه
+/// - فقط نشست JWT قبوله (API key دسترسی ادمین نمیده)
+/// - نقش کاربر از دیتابیس خونده میشه، پس تغییر نقش فوری اعمال میشه
+/// - کاربر غیر ادمین 403 میگیره
+///
+/// # استفاده:
+/// ```rust,ignore
+/// async fn handler(AdminUser(admin_id): AdminUser) -> ... {
+///     // فقط ادمین‌ها به اینجا میرسن
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AdminUser(pub String);
+
+#[async_trait]
+impl FromRequestParts<AppState> for AdminUser {
+    type Rejection = AppError;
+    
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let AuthUser(user_id, credential) = AuthUser::from_request_parts(parts, state).await?;
+        credential.require_session()?;
+        
+        if !state.admin_service.is_admin(&user_id).await? {
+            return Err(AppError::Forbidden("Admin access required".to_string()));
+        }
         
-        Ok(AuthUser(claims.sub))
+        Ok(AdminUser(user_id))
     }
 }
 
@@ -121,7 +245,8 @@ impl FromRequestParts<AppState> for AuthUser {
 /// استخراج کاربر اختیاری
 ///
 /// # مفاهیم:
-/// - Option<T>: اگه توکن نداشته باشه، None برمیگرده
+/// - Option<T>: اگه هیچ اعتبارنامه‌ای نفرسته، None برمیگرده
+/// - اعتبارنامه نامعتبر، باطل شده یا منقضی 401 میده (ناشناس حساب نمیشه)
 /// - این برای endpoint‌هایی که هم با و هم بدون لاگین کار میکنن
 ///
 /// # استفاده:
@@ -134,39 +259,54 @@ impl FromRequestParts<AppState> for AuthUser {
 /// ```
 #[derive(Debug, Clone, Default)]
 pub struct OptionalAuth {
-    user_id: Option<String>,
+    user: Option<AuthUser>,
 }
 
 impl OptionalAuth {
     /// گرفتن user_id
     #[must_use]
     pub fn user_id(&self) -> Option<String> {
-        self.user_id.clone()
+        self.user.as_ref().map(|AuthUser(id, _)| id.clone())
     }
     
     /// آیا لاگین کرده؟
     #[must_use]
     pub fn is_authenticated(&self) -> bool {
-        self.user_id.is_some()
+        self.user.is_some()
+    }
+    
+    /// بررسی scope برای درخواست‌های احراز هویت شده
+    ///
+    /// درخواست‌های ناشناس همیشه رد نمیشن
+    ///
+    /// # Errors
+    /// `Forbidden` اگه API key این scope رو نداشته باشه
+    pub fn require(&self, scope: ApiScope) -> Result<(), AppError> {
+        match &self.user {
+            Some(AuthUser(_, credential)) => credential.require(scope),
+            None => Ok(()),
+        }
     }
 }
 
 #[async_trait]
 impl FromRequestParts<AppState> for OptionalAuth {
-    type Rejection = AppError;  // هیچوقت reject نمیکنه
+    type Rejection = AppError;
     
     async fn from_request_parts(
         parts: &mut Parts,
         state: &AppState,
     ) -> Result<Self, Self::Rejection> {
-        // تلاش برای گرفتن کاربر
-        // اگه fail شد، None برگردون
-        let user_id = match AuthUser::from_request_parts(parts, state).await {
-            Ok(AuthUser(id)) => Some(id),
-            Err(_) => None,
-        };
+        // فقط نبودن header یعنی ناشناس؛ اعتبارنامه خراب همون خطای AuthUser رو میده
+        let has_credential = parts.headers.contains_key(header::AUTHORIZATION)
+            || parts.headers.contains_key(API_KEY_HEADER);
+        if !has_credential {
+            return Ok(OptionalAuth::default());
+        }
+        
+        let user = AuthUser::from_request_parts(parts, state).await?;
         
-        Ok(OptionalAuth { user_id })
+        Ok(OptionalAuth { user: Some(user) })
     }
 }
 
@@ -212,39 +352,115 @@ impl<S: Send + Sync> FromRequestParts<S> for RequestId {
 /// استخراج IP کلاینت
 ///
 /// # مفاهیم:
-/// - بررسی header‌های proxy
-/// - X-Forwarded-For, X-Real-IP
+/// - آدرس اتصال (`ConnectInfo`) پایه‌ست؛ header‌ها رو خود کلاینت میتونه بسازه
+/// - `X-Forwarded-For` و `X-Real-IP` فقط وقتی اتصال از پروکسی مورد اعتماد باشه
+///   (`TRUSTED_PROXIES`) خونده میشن
+/// - `None` فقط وقتی هیچ آدرسی در دسترس نیست (مثلاً تست بدون ConnectInfo)
 #[derive(Debug, Clone)]
 pub struct ClientIp(pub Option<String>);
 
 #[async_trait]
-impl<S: Send + Sync> FromRequestParts<S> for ClientIp {
+impl FromRequestParts<AppState> for ClientIp {
     type Rejection = std::convert::Infallible;
     
     async fn from_request_parts(
         parts: &mut Parts,
-        _state: &S,
+        state: &AppState,
     ) -> Result<Self, Self::Rejection> {
-        // اول X-Forwarded-For رو چک کن (برای پشت proxy)
-        let ip = parts
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|info| info.0.ip());
+        
+        let ip = client_ip(&parts.headers, peer, &state.trusted_proxies);
+        
+        Ok(ClientIp(ip.map(|ip| ip.to_canonical().to_string())))
+    }
+}
+
+/// پیدا کردن IP کلاینت از آدرس اتصال و header‌های پروکسی
+///
+/// در `X-Forwarded-For` هر پروکسی آدرس قبلی رو به انتها اضافه میکنه، پس از
+/// راست به چپ اولین آدرسی که خودش پروکسی مورد اعتماد نیست کلاینت واقعیه.
+/// آدرس‌های سمت چپ‌تر رو خود کلاینت نوشته و قابل اعتماد نیستن.
+fn client_ip(headers: &HeaderMap, peer: Option<IpAddr>, trusted: &TrustedProxies) -> Option<IpAddr> {
+    if !trusted.is_trusted(peer) {
+        return peer;
+    }
+    
+    let forwarded: Vec<IpAddr> = headers
+        .get_all("X-Forwarded-For")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|s| s.trim().parse().ok())
+        .collect();
+    
+    forwarded
+        .iter()
+        .rev()
+        .find(|ip| !trusted.contains(**ip))
+        .or_else(|| forwarded.first())
+        .copied()
+        .or_else(|| {
+            headers
+                .get("X-Real-IP")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse().ok())
+        })
+        .or(peer)
+}
+
+// =====================================
+// Client Country Extractor
+// =====================================
+/// استخراج کشور کلاینت از header CDN (`COUNTRY_HEADER`)
+///
+/// # مفاهیم:
+/// - مثل `ClientIp`: header فقط از پروکسی مورد اعتماد قبوله
+/// - فقط کد دو حرفی ISO 3166-1 نگه داشته میشه؛ `XX` (ناشناخته) و `T1` (Tor) نه
+#[derive(Debug, Clone)]
+pub struct ClientCountry(pub Option<String>);
+
+#[async_trait]
+impl FromRequestParts<AppState> for ClientCountry {
+    type Rejection = std::convert::Infallible;
+    
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let Some(name) = state.config().country_header.as_deref() else {
+            return Ok(ClientCountry(None));
+        };
+        
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|info| info.0.ip());
+        
+        if !state.trusted_proxies.is_trusted(peer) {
+            return Ok(ClientCountry(None));
+        }
+        
+        let country = parts
             .headers
-            .get("X-Forwarded-For")
+            .get(name)
             .and_then(|v| v.to_str().ok())
-            .and_then(|s| s.split(',').next())
-            .map(|s| s.trim().to_string())
-            // بعد X-Real-IP
-            .or_else(|| {
-                parts
-                    .headers
-                    .get("X-Real-IP")
-                    .and_then(|v| v.to_str().ok())
-                    .map(ToString::to_string)
-            });
+            .and_then(country_code);
         
-        Ok(ClientIp(ip))
+        Ok(ClientCountry(country))
     }
 }
 
+/// نرمال کردن مقدار header کشور به کد دو حرفی بزرگ
+fn country_code(value: &str) -> Option<String> {
+    let code = value.trim().to_ascii_uppercase();
+    
+    let valid = code.len() == 2 && code.bytes().all(|b| b.is_ascii_uppercase());
+    (valid && code != "XX").then_some(code)
+}
+
 // =====================================
 // User Agent Extractor
 // =====================================
@@ -270,6 +486,33 @@ impl<S: Send + Sync> FromRequestParts<S> for UserAgent {
     }
 }
 
+// =====================================
+// Referer Extractor
+// =====================================
+/// استخراج Referer
+///
+/// صفحه‌ای که کاربر از اون روی لینک کلیک کرده (برای analytics)
+#[derive(Debug, Clone)]
+pub struct Referer(pub Option<String>);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Referer {
+    type Rejection = std::convert::Infallible;
+    
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let referer = parts
+            .headers
+            .get(header::REFERER)
+            .and_then(|v| v.to_str().ok())
+            .map(ToString::to_string);
+        
+        Ok(Referer(referer))
+    }
+}
+
 // =====================================
 // JSON with Validation
 // =====================================
@@ -292,26 +535,7 @@ use validator::Validate;
 #[derive(Debug, Clone)]
 pub struct ValidatedJson<T>(pub T);
 
-#[async_trait]
-impl<S, T> FromRequestParts<S> for ValidatedJson<T>
-where
-    S: Send + Sync,
-    T: DeserializeOwned + Validate,
-{
-    type Rejection = AppError;
-    
-    async fn from_request_parts(
-        _parts: &mut Parts,
-        _state: &S,
-    ) -> Result<Self, Self::Rejection> {
-        // این باید با FromRequest پیاده‌سازی بشه، نه FromRequestParts
-        // چون body داره
-        // این فقط یه placeholder هست
-        Err(AppError::Internal("Use axum::Json instead".to_string()))
-    }
-}
-
-// نسخه صحیح با FromRequest
+// body داره، پس فقط FromRequest (نه FromRequestParts)
 use axum::{
     extract::FromRequest,
     body::Body,
@@ -342,3 +566,59 @@ where
     }
 }
 
+
+// =====================================
+// Tests
+// =====================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigBuilder;
+    
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, value.parse().unwrap());
+        }
+        headers
+    }
+    
+    fn ip(s: &str) -> Option<IpAddr> {
+        Some(s.parse().unwrap())
+    }
+    
+    #[test]
+    fn test_forwarded_headers_ignored_from_untrusted_peer() {
+        let trusted = TrustedProxies::default();
+        let spoofed = headers(&[("X-Forwarded-For", "1.1.1.1"), ("X-Real-IP", "2.2.2.2")]);
+        
+        assert_eq!(client_ip(&spoofed, ip("203.0.113.9"), &trusted), ip("203.0.113.9"));
+        assert_eq!(client_ip(&spoofed, None, &trusted), None);
+    }
+    
+    #[test]
+    fn test_forwarded_for_from_trusted_proxy() {
+        let config = ConfigBuilder::new().trusted_proxy("10.0.0.0/8").build();
+        let trusted = TrustedProxies::from_config(&config).unwrap();
+        let proxy = ip("10.0.0.2");
+        
+        // آدرس اول رو کلاینت نوشته؛ اولین آدرس غیر پروکسی از راست کلاینت واقعیه
+        let chain = headers(&[("X-Forwarded-For", "1.1.1.1, 198.51.100.7, 10.0.0.3")]);
+        assert_eq!(client_ip(&chain, proxy, &trusted), ip("198.51.100.7"));
+        
+        let real_ip = headers(&[("X-Real-IP", "198.51.100.8")]);
+        assert_eq!(client_ip(&real_ip, proxy, &trusted), ip("198.51.100.8"));
+        
+        assert_eq!(client_ip(&HeaderMap::new(), proxy, &trusted), proxy);
+    }
+    
+    #[test]
+    fn test_country_code() {
+        assert_eq!(country_code("de"), Some("DE".to_string()));
+        assert_eq!(country_code(" US "), Some("US".to_string()));
+        assert_eq!(country_code("XX"), None);
+        assert_eq!(country_code("T1"), None);
+        assert_eq!(country_code("DEU"), None);
+    }
+}
+
@@ -0,0 +1,111 @@
+//! # Admin Handlers
+//!
+//! Handler‌های پنل ادمین (`/api/admin`)
+//!
+//! همه handler‌ها `AdminUser` میگیرن، پس کاربر عادی 403 میگیره.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+
+use crate::{
+    error::Result,
+    models::{
+        AdminUpdateUserRequest, ApiResponse, PaginatedResult, Pagination, SearchParams,
+        UrlResponse, UserResponse,
+    },
+    services::AppState,
+    api::extractors::AdminUser,
+};
+
+// =====================================
+// List Users
+// =====================================
+/// جستجو و لیست کاربران
+///
+/// # Endpoint
+/// `GET /api/admin/users?query=alice&status=inactive&page=1&per_page=50`
+///
+/// # Query Parameters
+/// - `query`: جستجو در ایمیل یا نام
+/// - `status`: `active`، `inactive` یا `all`
+/// - `page`, `per_page`: صفحه‌بندی
+pub async fn list_users(
+    State(state): State<AppState>,
+    AdminUser(_): AdminUser,
+    Query(pagination): Query<Pagination>,
+    Query(search): Query<SearchParams>,
+) -> Result<Json<ApiResponse<PaginatedResult<UserResponse>>>> {
+    let users = state.admin_service.list_users(&search, &pagination).await?;
+    
+    Ok(Json(ApiResponse::success(users)))
+}
+
+// =====================================
+// Update User
+// =====================================
+/// غیرفعال/فعال کردن کاربر یا تغییر نقش
+///
+/// # Endpoint
+/// `PATCH /api/admin/users/:id`
+///
+/// # Request Body
+/// ```json
+/// {
+///   "is_active": false,   // optional
+///   "role": "admin"       // optional: user | admin
+/// }
+/// ```
+pub async fn update_user(
+    State(state): State<AppState>,
+    AdminUser(admin_id): AdminUser,
+    Path(user_id): Path<String>,
+    Json(request): Json<AdminUpdateUserRequest>,
+) -> Result<Json<ApiResponse<UserResponse>>> {
+    let user = state.admin_service
+        .update_user(&admin_id, &user_id, request)
+        .await?;
+    
+    Ok(Json(ApiResponse::success(user)))
+}
+
+// =====================================
+// List URLs
+// =====================================
+/// جستجو و لیست همه URL‌ها
+///
+/// # Endpoint
+/// `GET /api/admin/urls?query=spam&status=active&sort_by=clicks&order=desc`
+///
+/// پارامترها مثل `GET /api/me/urls` هستن
+pub async fn list_urls(
+    State(state): State<AppState>,
+    AdminUser(_): AdminUser,
+    Query(pagination): Query<Pagination>,
+    Query(search): Query<SearchParams>,
+) -> Result<Json<ApiResponse<PaginatedResult<UrlResponse>>>> {
+    let urls = state.admin_service.list_urls(&search, &pagination).await?;
+    
+    Ok(Json(ApiResponse::success(urls)))
+}
+
+// =====================================
+// Delete URL
+// =====================================
+/// حذف هر URL (بدون بررسی مالکیت)
+///
+/// # Endpoint
+/// `DELETE /api/admin/urls/:code`
+pub async fn delete_url(
+    State(state): State<AppState>,
+    AdminUser(admin_id): AdminUser,
+    Path(code): Path<String>,
+) -> Result<impl IntoResponse> {
+    state.admin_service.delete_url(&admin_id, &code).await?;
+    
+    // 204 No Content
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,106 @@
+//! # API Key Handlers
+//!
+//! Handler‌های مدیریت API key‌های کاربر
+//!
+//! همه این endpoint‌ها فقط با نشست JWT کار میکنن؛
+//! یه API key نمیتونه کلید جدید بسازه یا کلیدها رو ببینه.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+
+use crate::{
+    error::Result,
+    models::{ApiKeyResponse, ApiResponse, CreateApiKeyRequest},
+    services::AppState,
+    api::extractors::AuthUser,
+};
+
+// =====================================
+// List API Keys
+// =====================================
+/// لیست API key‌های فعال کاربر
+///
+/// # Endpoint
+/// `GET /api/me/api-keys`
+///
+/// # Headers
+/// `Authorization: Bearer <token>`
+pub async fn list_api_keys(
+    State(state): State<AppState>,
+    AuthUser(user_id, credential): AuthUser,
+) -> Result<Json<ApiResponse<Vec<ApiKeyResponse>>>> {
+    credential.require_session()?;
+    
+    let keys = state.api_key_service.list_keys(&user_id).await?;
+    
+    Ok(Json(ApiResponse::success(keys)))
+}
+
+// =====================================
+// Create API Key
+// =====================================
+/// ساخت API key جدید
+///
+/// # Endpoint
+/// `POST /api/me/api-keys`
+///
+/// # Request Body
+/// ```json
+/// {
+///   "name": "ci-deploy",
+///   "scopes": ["urls:read", "urls:write", "analytics:read"]
+/// }
+/// ```
+///
+/// # Response
+/// `key` فقط همین یک بار برمیگرده:
+/// ```json
+/// {
+///   "success": true,
+///   "data": {
+///     "id": "...",
+///     "name": "ci-deploy",
+///     "prefix": "usk_AbC12345",
+///     "scopes": ["urls:read", "urls:write"],
+///     "key": "usk_AbC12345..."
+///   }
+/// }
+/// ```
+pub async fn create_api_key(
+    State(state): State<AppState>,
+    AuthUser(user_id, credential): AuthUser,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> Result<impl IntoResponse> {
+    credential.require_session()?;
+    
+    let created = state.api_key_service.create_key(&user_id, request).await?;
+    
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success(created))
+    ))
+}
+
+// =====================================
+// Revoke API Key
+// =====================================
+/// ابطال API key
+///
+/// # Endpoint
+/// `DELETE /api/me/api-keys/:id`
+pub async fn revoke_api_key(
+    State(state): State<AppState>,
+    AuthUser(user_id, credential): AuthUser,
+    Path(key_id): Path<String>,
+) -> Result<impl IntoResponse> {
+    credential.require_session()?;
+    
+    state.api_key_service.revoke_key(&user_id, &key_id).await?;
+    
+    // 204 No Content
+    Ok(StatusCode::NO_CONTENT)
+}
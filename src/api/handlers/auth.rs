@@ -12,11 +12,10 @@ use axum::{
 use crate::{
     error::Result,
     models::{
-        LoginRequest, LoginResponse, RegisterRequest, RegisterResponse,
+        LoginRequest, LoginResponse, RefreshTokenRequest, RegisterRequest,
         ApiResponse,
     },
     services::AppState,
-    api::extractors::BearerToken,
 };
 
 // =====================================
@@ -74,7 +73,9 @@ pub async fn register(
 ///   "data": {
 ///     "user": { ... },
 ///     "token": "eyJ...",
-///     "expires_at": "2024-..."
+///     "expires_at": "2024-...",
+///     "refresh_token": "V1StGXR8_Z5jdHi6B-myT...",
+///     "refresh_expires_at": "2024-..."
 ///   }
 /// }
 /// ```
@@ -93,20 +94,47 @@ pub async fn login(
 /// Refresh کردن توکن
 ///
 /// # مفاهیم:
-/// - `BearerToken`: استخراج توکن از header
-/// - این endpoint توکن جدید صادر میکنه
+/// - refresh token در body میاد، نه access token در header
+/// - هر refresh token فقط یک بار قابل استفاده‌ست؛ پاسخ یه refresh token جدید داره
+/// - استفاده دوباره از توکن قدیمی کل نشست رو باطل میکنه
 ///
 /// # Endpoint
 /// `POST /api/auth/refresh`
 ///
-/// # Headers
-/// `Authorization: Bearer <token>`
+/// # Request Body
+/// ```json
+/// { "refresh_token": "V1StGXR8_Z5jdHi6B-myT..." }
+/// ```
 pub async fn refresh_token(
     State(state): State<AppState>,
-    BearerToken(token): BearerToken,
+    Json(request): Json<RefreshTokenRequest>,
 ) -> Result<Json<ApiResponse<LoginResponse>>> {
-    let response = state.auth_service.refresh_token(&token).await?;
+    let response = state.auth_service.refresh_token(request).await?;
     
     Ok(Json(ApiResponse::success(response)))
 }
 
+// =====================================
+// Logout
+// =====================================
+/// خروج از نشست
+///
+/// refresh token (و هر توکنی که از rotation اون ساخته شده) باطل میشه.
+/// access token تا انقضای کوتاهش معتبر میمونه.
+///
+/// # Endpoint
+/// `POST /api/auth/logout`
+///
+/// # Request Body
+/// ```json
+/// { "refresh_token": "V1StGXR8_Z5jdHi6B-myT..." }
+/// ```
+pub async fn logout(
+    State(state): State<AppState>,
+    Json(request): Json<RefreshTokenRequest>,
+) -> Result<impl IntoResponse> {
+    state.auth_service.logout(request).await?;
+    
+    // 204 No Content
+    Ok(StatusCode::NO_CONTENT)
+}
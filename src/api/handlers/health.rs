@@ -1,18 +1,30 @@
 //! # Health Check Handler
 //!
 //! برای بررسی سلامت سرویس
+//!
+//! ## Probe‌ها:
+//! - `/health/live`: پروسه زنده‌ست؟ (بدون وابستگی خارجی - برای restart)
+//! - `/health/ready`: آماده دریافت ترافیکه؟ (دیتابیس و migration‌ها)
+//! - `/health`: خلاصه برای انسان‌ها و مانیتورینگ (503 وقتی دیتابیس جواب نده)
+
+use std::time::{Duration, Instant};
 
 use axum::{
     extract::State,
+    http::StatusCode,
+    response::IntoResponse,
     Json,
 };
 
 use crate::{
-    error::Result,
-    models::HealthResponse,
+    database::Database,
+    models::{DatabaseCheck, HealthResponse, ReadinessResponse},
     services::AppState,
 };
 
+/// حداکثر زمان انتظار برای پاسخ دیتابیس در health check
+const DATABASE_PING_TIMEOUT: Duration = Duration::from_secs(2);
+
 // =====================================
 // Health Check
 // =====================================
@@ -21,6 +33,7 @@ use crate::{
 /// # مفاهیم:
 /// - Health check برای Kubernetes/Docker
 /// - بررسی اتصال دیتابیس
+/// - 503 با status `degraded` وقتی ping دیتابیس شکست بخوره
 ///
 /// # Endpoint
 /// `GET /health`
@@ -30,16 +43,118 @@ use crate::{
 /// {
 ///   "status": "healthy",
 ///   "version": "0.1.0",
-///   "database": true
+///   "database": true,
+///   "uptime_seconds": 3600
 /// }
 /// ```
 pub async fn health_check(
-    State(_state): State<AppState>,
-) -> Result<Json<HealthResponse>> {
-    // TODO: بررسی واقعی دیتابیس
-    // let db_ok = state.database.health_check().await.is_ok();
-    let db_ok = true;
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let db_ok = match &state.db {
+        Some(db) => db.ping(DATABASE_PING_TIMEOUT).await.is_ok(),
+        None => true,
+    };
     
-    Ok(Json(HealthResponse::healthy(db_ok)))
+    let status = if db_ok {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    
+    (status, Json(HealthResponse::healthy(db_ok).with_uptime(state.uptime_seconds())))
 }
 
+// =====================================
+// Liveness
+// =====================================
+/// Liveness probe
+///
+/// # مفاهیم:
+/// - فقط نشون میده پروسه جواب میده
+/// - به دیتابیس وابسته نیست، تا قطعی دیتابیس باعث restart نشه
+///
+/// # Endpoint
+/// `GET /health/live`
+pub async fn liveness(
+    State(state): State<AppState>,
+) -> Json<HealthResponse> {
+    let mut response = HealthResponse::healthy(true).with_uptime(state.uptime_seconds());
+    response.status = "alive".to_string();
+    
+    Json(response)
+}
+
+// =====================================
+// Readiness
+// =====================================
+/// Readiness probe
+///
+/// # مفاهیم:
+/// - ping دیتابیس با timeout (فایل قفل شده => not ready)
+/// - migration‌های اجرا نشده => not ready
+/// - گزارش استفاده از connection pool
+/// - با ذخیره‌سازی در حافظه همیشه ready
+/// - 503 وقتی آماده نیست تا orchestrator ترافیک نفرسته
+///
+/// # Endpoint
+/// `GET /health/ready`
+///
+/// # Response
+/// ```json
+/// {
+///   "status": "ready",
+///   "version": "0.1.0",
+///   "uptime_seconds": 3600,
+///   "database": {
+///     "reachable": true,
+///     "latency_ms": 1,
+///     "pending_migrations": 0,
+///     "pool": { "size": 2, "idle": 1, "max_connections": 10 },
+///     "pool_utilization": 10.0
+///   }
+/// }
+/// ```
+pub async fn readiness(
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let database = match &state.db {
+        Some(db) => check_database(db).await,
+        None => DatabaseCheck::in_memory(),
+    };
+    let response = ReadinessResponse::new(database, state.uptime_seconds());
+    
+    let status = if response.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    
+    (status, Json(response))
+}
+
+/// ping، migration‌ها و وضعیت pool دیتابیس
+async fn check_database(db: &Database) -> DatabaseCheck {
+    let started = Instant::now();
+    let ping = db.ping(DATABASE_PING_TIMEOUT).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    
+    // خطای query migration یعنی not ready، ولی دیتابیس هنوز در دسترسه
+    let (pending_migrations, migration_error) = match ping {
+        Ok(()) => match db.pending_migrations(DATABASE_PING_TIMEOUT).await {
+            Ok(pending) => (Some(pending), None),
+            Err(e) => (None, Some(e.to_string())),
+        },
+        Err(_) => (None, None),
+    };
+    
+    let pool = db.pool_status();
+    DatabaseCheck {
+        reachable: ping.is_ok(),
+        latency_ms,
+        pending_migrations,
+        pool,
+        pool_utilization: pool.utilization(),
+        error: ping.err().map(|e| e.to_string()),
+        migration_error,
+    }
+}
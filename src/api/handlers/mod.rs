@@ -14,6 +14,8 @@
 pub mod url;
 pub mod auth;
 pub mod user;
+pub mod api_key;
+pub mod admin;
 pub mod health;
 pub mod stats;
 
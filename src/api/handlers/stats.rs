@@ -9,22 +9,27 @@ use axum::{
 
 use crate::{
     error::Result,
-    models::ApiResponse,
+    models::{ApiResponse, SystemStats},
     services::AppState,
-    database::UrlStats,
+    api::extractors::AdminUser,
 };
 
 // =====================================
 // Get Stats
 // =====================================
-/// گرفتن آمار کلی
+/// گرفتن آمار کلی (فقط ادمین)
+///
+/// # مفاهیم:
+/// - تعداد لینک‌ها و کاربران، فعال در برابر منقضی/غیرفعال
+/// - لینک‌های ساخته شده در 30 روز اخیر و 10 لینک پرکلیک
 ///
 /// # Endpoint
-/// `GET /api/stats`
+/// `GET /api/stats` یا `GET /api/admin/stats`
 pub async fn get_stats(
     State(state): State<AppState>,
-) -> Result<Json<ApiResponse<UrlStats>>> {
-    let stats = state.url_service.get_stats().await?;
+    AdminUser(_): AdminUser,
+) -> Result<Json<ApiResponse<SystemStats>>> {
+    let stats = state.stats_service.get_system_stats().await?;
     
     Ok(Json(ApiResponse::success(stats)))
 }
@@ -3,18 +3,21 @@
 //! Handler‌های مربوط به URL shortening
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::{StatusCode, header},
-    response::{IntoResponse, Redirect, Response},
-    Json,
+    response::{Html, IntoResponse, Redirect, Response},
+    Form, Json,
 };
 use tracing::info;
 
 use crate::{
     error::{AppError, Result},
-    models::{CreateUrlRequest, UrlResponse, ApiResponse},
-    services::AppState,
-    api::extractors::OptionalAuth,
+    models::{
+        AnalyticsQuery, ApiResponse, ApiScope, BatchDeleteRequest, BatchOperationResponse, ClickInfo, CreateUrlRequest, UnlockUrlRequest,
+        UpdateUrlRequest, UrlAnalytics, UrlResponse,
+    },
+    services::{AppState, RedirectOutcome},
+    api::extractors::{AuthUser, ClientCountry, ClientIp, OptionalAuth, Referer, UserAgent, ValidatedJson},
 };
 
 // =====================================
@@ -37,7 +40,11 @@ use crate::{
 ///   "url": "https://example.com/long-url",
 ///   "custom_code": "mylink",  // optional
 ///   "title": "My Link",        // optional
-///   "expires_in_hours": 24     // optional
+///   "starts_at": "2025-01-01T09:00:00Z", // optional: قبلش لینک باز نمیشه
+///   "expires_in_hours": 24,    // optional
+///   "redirect_type": 301,      // optional: 301 | 302 | 307 | 308
+///   "password": "s3cret",      // optional: 4-128 کاراکتر
+///   "max_clicks": 1            // optional: 1 = یک‌بار مصرف
 /// }
 /// ```
 ///
@@ -59,6 +66,9 @@ pub async fn create_url(
     auth: OptionalAuth,
     Json(request): Json<CreateUrlRequest>,
 ) -> Result<impl IntoResponse> {
+    // API key باید اجازه نوشتن داشته باشه
+    auth.require(ApiScope::UrlsWrite)?;
+    
     // گرفتن user_id اگه لاگین باشه
     let user_id = auth.user_id();
     
@@ -80,27 +90,155 @@ pub async fn create_url(
 /// # مفاهیم:
 /// - `Path<String>`: استخراج پارامتر از URL
 /// - `Redirect`: نوع خاص axum برای redirect
+/// - `ClientIp`, `UserAgent`, `Referer`, `ClientCountry`: اطلاعات کلاینت برای ثبت کلیک
 /// - این handler اصلی‌ترین عملکرد URL shortener هست
 ///
 /// # Endpoint
 /// `GET /:code`
 ///
 /// # Response
-/// - 302 Redirect به URL اصلی
+/// - 301، 302، 307 یا 308 (بسته به `redirect_type` لینک) به URL اصلی
+/// - 200 با فرم رمز اگه لینک رمزدار باشه (کلیکی ثبت نمیشه)
 /// - 404 اگه پیدا نشه
+/// - 410 اگه سقف کلیک (`max_clicks`) پر شده باشه
+/// - قبل از `starts_at`: redirect به `NOT_YET_AVAILABLE_URL` یا 403
 pub async fn redirect_handler(
     State(state): State<AppState>,
     Path(code): Path<String>,
+    ClientIp(ip_address): ClientIp,
+    UserAgent(user_agent): UserAgent,
+    Referer(referer): Referer,
+    ClientCountry(country): ClientCountry,
 ) -> Result<Response> {
+    let click = ClickInfo {
+        ip_address,
+        user_agent,
+        referer,
+        country,
+    };
+    
     // گرفتن URL اصلی
-    let original_url = state.url_service.get_original_url(&code).await?;
+    let outcome = match state.url_service.get_original_url(&code, click).await {
+        Err(e @ AppError::NotYetAvailable(_)) => return not_yet_available(&state, e),
+        outcome => outcome?,
+    };
+    
+    let target = match outcome {
+        RedirectOutcome::Redirect(target) => target,
+        RedirectOutcome::PasswordRequired => return Ok(unlock_page(StatusCode::OK, None)),
+    };
+    
+    info!(short_code = %code, status = target.redirect_type.as_u16(), "Redirecting");
+    
+    // ساخت redirect response با status code خود لینک
+    // `Redirect` در axum فقط 303/307/308 داره، پس header رو دستی میسازیم
+    let status = StatusCode::from_u16(target.redirect_type.as_u16())
+        .unwrap_or(StatusCode::TEMPORARY_REDIRECT);
+    
+    Ok((status, [(header::LOCATION, target.original_url)]).into_response())
+}
+
+/// پاسخ لینکی که زمان شروعش نرسیده
+///
+/// اگه `NOT_YET_AVAILABLE_URL` تنظیم شده باشه redirect موقت (302) به اون صفحه،
+/// وگرنه همون خطا (403 با زمان شروع)
+fn not_yet_available(state: &AppState, error: AppError) -> Result<Response> {
+    match &state.config().not_yet_available_url {
+        Some(url) => Ok((StatusCode::FOUND, [(header::LOCATION, url.clone())]).into_response()),
+        None => Err(error),
+    }
+}
+
+// =====================================
+// Unlock Password-Protected URL
+// =====================================
+/// فرم HTML رمز لینک
+///
+/// فرم بدون `action` به همون آدرس `/:code` فرستاده میشه،
+/// پس کد کوتاه لازم نیست داخل HTML بیاد (و escape نمیخواد)
+const UNLOCK_PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<meta name="robots" content="noindex">
+<title>Password required</title>
+</head>
+<body>
+<h1>This link is password protected</h1>
+{error}<form method="post">
+<input type="password" name="password" placeholder="Password" required autofocus>
+<button type="submit">Continue</button>
+</form>
+</body>
+</html>
+"#;
+
+/// ساخت پاسخ فرم رمز
+///
+/// `no-store`: فرم (و پیام خطاش) نباید توسط مرورگر یا proxy cache بشه
+fn unlock_page(status: StatusCode, error: Option<&str>) -> Response {
+    let error = error
+        .map(|message| format!("<p role=\"alert\">{}</p>\n", message))
+        .unwrap_or_default();
+    
+    (
+        status,
+        [(header::CACHE_CONTROL, "no-store")],
+        Html(UNLOCK_PAGE.replace("{error}", &error)),
+    )
+        .into_response()
+}
+
+/// باز کردن لینک رمزدار با رمز فرم
+///
+/// # مفاهیم:
+/// - `Form<T>`: بدنه `application/x-www-form-urlencoded`
+/// - فقط رمز اشتباه از bucket همون لینک کم میکنه تا brute force نشه؛
+///   تلاش درست هزینه‌ای نداره و کاربرهای دیگه رو قفل نمیکنه
+/// - 303 See Other: مرورگر مقصد رو با GET باز میکنه و فرم دوباره ارسال نمیشه
+///
+/// # Endpoint
+/// `POST /:code`
+///
+/// # Response
+/// - 303 به URL اصلی (کلیک ثبت میشه)
+/// - 401 با فرم و پیام خطا اگه رمز اشتباه باشه
+/// - 429 اگه رمز‌های اشتباه برای این لینک از سقف گذشته باشه
+/// - 404 اگه پیدا نشه یا منقضی شده باشه
+/// - 410 اگه سقف کلیک پر شده باشه
+/// - قبل از `starts_at`: مثل `redirect_handler`
+pub async fn unlock_handler(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+    ClientIp(ip_address): ClientIp,
+    UserAgent(user_agent): UserAgent,
+    Referer(referer): Referer,
+    ClientCountry(country): ClientCountry,
+    Form(request): Form<UnlockUrlRequest>,
+) -> Result<Response> {
+    let limiter_key = format!("link:{}", code);
+    state.unlock_limiter.check(&limiter_key)?;
     
-    info!(short_code = %code, "Redirecting");
+    let click = ClickInfo {
+        ip_address,
+        user_agent,
+        referer,
+        country,
+    };
     
-    // ساخت redirect response
-    // 302 Found برای temporary redirect
-    // میتونید 301 Permanent هم استفاده کنید
-    Ok(Redirect::temporary(&original_url).into_response())
+    match state.url_service.unlock_url(&code, &request.password, click).await {
+        Ok(target) => {
+            info!(short_code = %code, "Unlocked password-protected URL");
+            Ok(Redirect::to(&target.original_url).into_response())
+        }
+        Err(AppError::Unauthorized(_)) => {
+            state.unlock_limiter.record_failure(&limiter_key)?;
+            Ok(unlock_page(StatusCode::UNAUTHORIZED, Some("Incorrect password.")))
+        }
+        Err(e @ AppError::NotYetAvailable(_)) => not_yet_available(&state, e),
+        Err(e) => Err(e),
+    }
 }
 
 // =====================================
@@ -108,6 +246,10 @@ pub async fn redirect_handler(
 // =====================================
 /// گرفتن اطلاعات URL
 ///
+/// مقصد لینک رمزدار فقط به صاحبش نشون داده میشه (بقیه 403 میگیرن)
+///
+/// API key باید scope `urls:read` داشته باشه
+///
 /// # Endpoint
 /// `GET /api/urls/:code`
 ///
@@ -126,33 +268,144 @@ pub async fn redirect_handler(
 pub async fn get_url_info(
     State(state): State<AppState>,
     Path(code): Path<String>,
+    auth: OptionalAuth,
 ) -> Result<Json<ApiResponse<UrlResponse>>> {
-    let url = state.url_service.get_url_info(&code).await?;
+    auth.require(ApiScope::UrlsRead)?;
+    
+    let url = state.url_service.get_url_info(&code, auth.user_id().as_deref()).await?;
     
     Ok(Json(ApiResponse::success(url)))
 }
 
+// =====================================
+// Update URL
+// =====================================
+/// ویرایش URL
+///
+/// # مفاهیم:
+/// - Authorization: فقط مالک میتونه ویرایش کنه
+/// - Partial update: فیلدهای ارسال نشده تغییر نمیکنن
+///
+/// # Endpoint
+/// `PATCH /api/urls/:code`
+///
+/// # Request Body
+/// ```json
+/// {
+///   "url": "https://example.com/fixed-url",  // optional
+///   "title": "New title",                     // optional
+///   "expires_in_hours": 48,                   // optional
+///   "redirect_type": 308,                     // optional
+///   "max_clicks": 10,                         // optional, null = بدون سقف
+///   "starts_at": "2025-01-01T09:00:00Z"       // optional, null = از همین حالا
+/// }
+/// ```
+pub async fn update_url(
+    State(state): State<AppState>,
+    AuthUser(user_id, credential): AuthUser,
+    Path(code): Path<String>,
+    ValidatedJson(request): ValidatedJson<UpdateUrlRequest>,
+) -> Result<Json<ApiResponse<UrlResponse>>> {
+    credential.require(ApiScope::UrlsWrite)?;
+    
+    let url = state.url_service.update_url(&code, &user_id, request).await?;
+    
+    Ok(Json(ApiResponse::success(url)))
+}
+
+// =====================================
+// URL Analytics
+// =====================================
+/// گرفتن analytics یک URL
+///
+/// # مفاهیم:
+/// - `Query<T>`: استخراج پارامترهای query string
+/// - Authorization: فقط مالک لینک
+///
+/// # Endpoint
+/// `GET /api/urls/:code/analytics?interval=day&from=...&to=...`
+///
+/// # Query Parameters
+/// - `interval`: `hour`، `day` (پیش‌فرض) یا `week`
+/// - `from`, `to`: زمان RFC 3339 (اختیاری)
+///
+/// # Headers
+/// `Authorization: Bearer <token>`
+pub async fn get_url_analytics(
+    State(state): State<AppState>,
+    AuthUser(user_id, credential): AuthUser,
+    Path(code): Path<String>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<ApiResponse<UrlAnalytics>>> {
+    credential.require(ApiScope::AnalyticsRead)?;
+    
+    let analytics = state.url_service
+        .get_url_analytics(&code, &user_id, query)
+        .await?;
+    
+    Ok(Json(ApiResponse::success(analytics)))
+}
+
 // =====================================
 // Delete URL
 // =====================================
 /// حذف URL
 ///
 /// # مفاهیم:
-/// - Authorization: فقط مالک میتونه حذف کنه
+/// - Authorization: فقط مالک میتونه حذف کنه (API key با scope `urls:write`)
 /// - 204 No Content: پاسخ بدون بدنه
 ///
 /// # Endpoint
 /// `DELETE /api/urls/:code`
+///
+/// # Headers
+/// `Authorization: Bearer <token>`
 pub async fn delete_url(
     State(state): State<AppState>,
-    auth: OptionalAuth,
+    AuthUser(user_id, credential): AuthUser,
     Path(code): Path<String>,
 ) -> Result<impl IntoResponse> {
-    let user_id = auth.user_id();
+    credential.require(ApiScope::UrlsWrite)?;
     
-    state.url_service.delete_url(&code, user_id.as_deref()).await?;
+    state.url_service.delete_url(&code, &user_id).await?;
     
     // 204 No Content
     Ok(StatusCode::NO_CONTENT)
 }
 
+// =====================================
+// Batch Delete
+// =====================================
+/// حذف دسته‌ای URL‌ها
+///
+/// # مفاهیم:
+/// - یک تراکنش برای همه حذف‌ها
+/// - فقط URL‌های متعلق به کاربر حذف میشن
+///
+/// # Endpoint
+/// `POST /api/urls/batch-delete`
+///
+/// # Request Body
+/// ```json
+/// { "ids": ["V1StGXR8_Z5jdHi6B-myT", "..."] }
+/// ```
+///
+/// # Response
+/// ```json
+/// {
+///   "success": true,
+///   "data": { "success_count": 1, "failed_count": 1, "failed_ids": ["..."] }
+/// }
+/// ```
+pub async fn batch_delete(
+    State(state): State<AppState>,
+    AuthUser(user_id, credential): AuthUser,
+    Json(request): Json<BatchDeleteRequest>,
+) -> Result<Json<ApiResponse<BatchOperationResponse>>> {
+    credential.require(ApiScope::UrlsWrite)?;
+    
+    let response = state.url_service.batch_delete(&user_id, request).await?;
+    
+    Ok(Json(ApiResponse::success(response)))
+}
+
@@ -3,13 +3,16 @@
 //! Handler‌های مربوط به کاربر
 
 use axum::{
-    extract::State,
+    extract::{Query, State},
     Json,
 };
 
 use crate::{
     error::Result,
-    models::{ApiResponse, UrlResponse, UserResponse},
+    models::{
+        ApiResponse, ApiScope, ChangePasswordRequest, LoginResponse, PaginatedResult, Pagination,
+        SearchParams, UpdateProfileRequest, UrlResponse, UserResponse,
+    },
     services::AppState,
     api::extractors::AuthUser,
 };
@@ -30,29 +33,108 @@ use crate::{
 /// `Authorization: Bearer <token>`
 pub async fn get_profile(
     State(state): State<AppState>,
-    AuthUser(user_id): AuthUser,
+    AuthUser(user_id, _): AuthUser,
 ) -> Result<Json<ApiResponse<UserResponse>>> {
     let user = state.auth_service.get_user(&user_id).await?;
     
     Ok(Json(ApiResponse::success(user)))
 }
 
+// =====================================
+// Update Profile
+// =====================================
+/// بروزرسانی پروفایل کاربر فعلی
+///
+/// # Endpoint
+/// `PATCH /api/me`
+///
+/// # Request Body
+/// ```json
+/// {
+///   "name": "Jane Doe",           // optional
+///   "email": "jane@example.com"   // optional
+/// }
+/// ```
+///
+/// # Headers
+/// `Authorization: Bearer <token>`
+pub async fn update_profile(
+    State(state): State<AppState>,
+    AuthUser(user_id, credential): AuthUser,
+    Json(request): Json<UpdateProfileRequest>,
+) -> Result<Json<ApiResponse<UserResponse>>> {
+    credential.require_session()?;
+    
+    let user = state.auth_service.update_profile(&user_id, request).await?;
+    
+    Ok(Json(ApiResponse::success(user)))
+}
+
 // =====================================
 // Get My URLs
 // =====================================
 /// گرفتن URL‌های کاربر فعلی
 ///
+/// # مفاهیم:
+/// - دو `Query` extractor از یک query string: صفحه‌بندی و جستجو
+///
 /// # Endpoint
-/// `GET /api/me/urls`
+/// `GET /api/me/urls?query=docs&status=active&sort_by=clicks&order=desc&page=2&per_page=50`
+///
+/// # Query Parameters
+/// - `query`: جستجو در عنوان یا آدرس مقصد
+/// - `status`: `active`، `expired` یا `all`
+/// - `sort_by`: `created_at` (پیش‌فرض) یا `clicks`
+/// - `order`: `desc` (پیش‌فرض) یا `asc`
+/// - `page`, `per_page`: صفحه‌بندی (حداکثر 100 در هر صفحه)
 ///
 /// # Headers
 /// `Authorization: Bearer <token>`
 pub async fn get_my_urls(
     State(state): State<AppState>,
-    AuthUser(user_id): AuthUser,
-) -> Result<Json<ApiResponse<Vec<UrlResponse>>>> {
-    let urls = state.url_service.get_user_urls(&user_id).await?;
+    AuthUser(user_id, credential): AuthUser,
+    Query(pagination): Query<Pagination>,
+    Query(search): Query<SearchParams>,
+) -> Result<Json<ApiResponse<PaginatedResult<UrlResponse>>>> {
+    credential.require(ApiScope::UrlsRead)?;
+    
+    let urls = state.url_service
+        .get_user_urls(&user_id, &search, &pagination)
+        .await?;
     
     Ok(Json(ApiResponse::success(urls)))
 }
 
+// =====================================
+// Change Password
+// =====================================
+/// تغییر رمز عبور کاربر فعلی
+///
+/// # مفاهیم:
+/// - بعد از تغییر، توکن‌های قبلی دیگه کار نمیکنن
+/// - پاسخ شامل یه توکن جدید هست
+///
+/// # Endpoint
+/// `POST /api/me/password`
+///
+/// # Request Body
+/// ```json
+/// {
+///   "current_password": "oldpassword",
+///   "new_password": "newsecurepassword"
+/// }
+/// ```
+///
+/// # Headers
+/// `Authorization: Bearer <token>`
+pub async fn change_password(
+    State(state): State<AppState>,
+    AuthUser(user_id, credential): AuthUser,
+    Json(request): Json<ChangePasswordRequest>,
+) -> Result<Json<ApiResponse<LoginResponse>>> {
+    credential.require_session()?;
+    
+    let response = state.auth_service.change_password(&user_id, request).await?;
+    
+    Ok(Json(ApiResponse::success(response)))
+}
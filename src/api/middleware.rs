@@ -18,12 +18,12 @@
 
 use axum::{
     body::Body,
-    http::{Request, Response, StatusCode},
+    http::Request,
     middleware::Next,
     response::IntoResponse,
 };
 use std::time::Instant;
-use tracing::{info, warn, Span};
+use tracing::{info, warn};
 
 use crate::error::AppError;
 
@@ -140,7 +140,8 @@ pub async fn error_handler(
 // Auth Middleware (Alternative)
 // =====================================
 use crate::services::AppState;
-use axum::extract::State;
+use crate::api::extractors::AuthUser;
+use axum::extract::{FromRequestParts, State};
 
 /// Middleware احراز هویت
 ///
@@ -158,97 +159,282 @@ pub async fn require_auth(
     request: Request<Body>,
     next: Next,
 ) -> Result<impl IntoResponse, AppError> {
-    // گرفتن header
-    let auth_header = request
-        .headers()
-        .get(axum::http::header::AUTHORIZATION)
-        .and_then(|v| v.to_str().ok())
-        .ok_or_else(|| AppError::Unauthorized("Missing auth token".to_string()))?;
-    
-    // چک کردن prefix
-    if !auth_header.starts_with("Bearer ") {
-        return Err(AppError::Unauthorized("Invalid auth format".to_string()));
-    }
-    
-    let token = &auth_header[7..];
-    
-    // Verify token
-    state.auth_service.verify_token(token)?;
+    // JWT یا API key (همون منطق extractor)
+    let (mut parts, body) = request.into_parts();
+    AuthUser::from_request_parts(&mut parts, &state).await?;
+    let request = Request::from_parts(parts, body);
     
     // ادامه
     Ok(next.run(request).await)
 }
 
 // =====================================
-// Rate Limiting (Simple In-Memory)
+// Rate Limiting (Token Bucket)
 // =====================================
-use std::sync::Arc;
 use std::collections::HashMap;
-use tokio::sync::RwLock;
+use std::num::NonZeroU32;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use governor::{
+    clock::{Clock, DefaultClock},
+    middleware::StateInformationMiddleware,
+    state::keyed::DefaultKeyedStateStore,
+    Quota, RateLimiter,
+};
 
-/// State برای rate limiter ساده
+use crate::{
+    api::extractors::{api_key_from_headers, ClientIp},
+    config::Config,
+    error::RateLimitInfo,
+    services::extract_token_from_header,
+};
+
+/// Rate limiter کلید‌دار governor که بعد از هر درخواست مجاز، وضعیت bucket رو برمیگردونه
+type KeyedRateLimiter = RateLimiter<
+    String,
+    DefaultKeyedStateStore<String>,
+    DefaultClock,
+    StateInformationMiddleware,
+>;
+
+/// State برای rate limiter
 ///
 /// # مفاهیم:
-/// - `RwLock`: قفل خواندن/نوشتن async
-/// - `Arc`: اشتراک امن بین threads
-/// - `HashMap`: نگهداری counter برای هر IP
-#[derive(Debug, Clone, Default)]
+/// - Token bucket (الگوریتم GCRA در governor)
+/// - هر کلید (کاربر یا IP) bucket جداگانه داره
+/// - `Arc`: اشتراک امن بین threads؛ governor خودش lock-free هست
+#[derive(Debug, Clone)]
 pub struct RateLimiterState {
-    requests: Arc<RwLock<HashMap<String, (u32, Instant)>>>,
-    max_requests: u32,
-    window_seconds: u64,
+    limiter: Arc<KeyedRateLimiter>,
+    burst: u32,
+    /// زمان پر شدن یک token
+    period: Duration,
 }
 
 impl RateLimiterState {
     /// ساخت rate limiter جدید
+    ///
+    /// # Arguments
+    /// * `per_second` - نرخ پر شدن bucket
+    /// * `burst` - ظرفیت bucket (حداکثر درخواست پشت سر هم)
     #[must_use]
-    pub fn new(max_requests: u32, window_seconds: u64) -> Self {
+    pub fn new(per_second: u32, burst: u32) -> Self {
+        let per_second = NonZeroU32::new(per_second).unwrap_or(NonZeroU32::MIN);
+        let burst = NonZeroU32::new(burst).unwrap_or(per_second);
+        
+        Self::with_quota(Quota::per_second(per_second).allow_burst(burst))
+    }
+    
+    /// ساخت rate limiter با نرخ دقیقه‌ای (برای کارهای حساس مثل تلاش رمز)
+    ///
+    /// # Arguments
+    /// * `per_minute` - تعداد تلاش مجاز در دقیقه
+    /// * `burst` - ظرفیت bucket (حداکثر تلاش پشت سر هم)
+    #[must_use]
+    pub fn per_minute(per_minute: u32, burst: u32) -> Self {
+        let per_minute = NonZeroU32::new(per_minute).unwrap_or(NonZeroU32::MIN);
+        let burst = NonZeroU32::new(burst).unwrap_or(per_minute);
+        
+        Self::with_quota(Quota::per_minute(per_minute).allow_burst(burst))
+    }
+    
+    fn with_quota(quota: Quota) -> Self {
+        let limiter = RateLimiter::keyed(quota)
+            .with_middleware::<StateInformationMiddleware>();
+        
         Self {
-            requests: Arc::new(RwLock::new(HashMap::new())),
-            max_requests,
-            window_seconds,
+            limiter: Arc::new(limiter),
+            burst: quota.burst_size().get(),
+            period: quota.replenish_interval(),
         }
     }
     
+    /// ساخت از تنظیمات (`rate_limit_per_second` و `rate_limit_burst`)
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.rate_limit_per_second, config.rate_limit_burst)
+    }
+    
     /// چک کردن rate limit
     ///
     /// # Returns
-    /// - `Ok(())` اگه مجاز باشه
-    /// - `Err(AppError)` اگه محدود شده باشه
-    pub async fn check(&self, key: &str) -> Result<(), AppError> {
-        let now = Instant::now();
-        let mut requests = self.requests.write().await;
-        
-        // گرفتن یا ساختن entry
-        let entry = requests.entry(key.to_string()).or_insert((0, now));
+    /// - `Ok(RateLimitInfo)` اگه مجاز باشه (برای header‌های پاسخ)
+    /// - `Err(AppError::RateLimited)` اگه bucket خالی باشه
+    pub fn check(&self, key: &str) -> Result<RateLimitInfo, AppError> {
+        match self.limiter.check_key(&key.to_string()) {
+            Ok(snapshot) => Ok(RateLimitInfo {
+                limit: self.burst,
+                remaining: snapshot.remaining_burst_capacity(),
+                retry_after_secs: 0,
+            }),
+            Err(not_until) => {
+                let wait = not_until.wait_time_from(DefaultClock::default().now());
+                Err(self.rate_limited(wait))
+            }
+        }
+    }
+    
+    /// خطای 429 برای وقتی که bucket تا `wait` دیگه خالیه
+    fn rate_limited(&self, wait: Duration) -> AppError {
+        AppError::RateLimited(RateLimitInfo {
+            limit: self.burst,
+            remaining: 0,
+            // گرد کردن به بالا تا کلاینت زودتر از موعد تلاش نکنه
+            retry_after_secs: wait.as_secs() + u64::from(wait.subsec_nanos() > 0),
+        })
+    }
+    
+    /// پاکسازی bucket‌هایی که دوباره پر شدن
+    pub fn cleanup(&self) {
+        self.limiter.retain_recent();
+        self.limiter.shrink_to_fit();
+    }
+    
+    /// تعداد کلید‌هایی که الان ردیابی میشن
+    #[must_use]
+    pub fn tracked_keys(&self) -> usize {
+        self.limiter.len()
+    }
+}
+
+/// Rate limiter که فقط تلاش‌های ناموفق رو میشمره (مثلاً رمز اشتباه لینک)
+///
+/// # مفاهیم:
+/// - `check` قبل از بررسی: فقط قفل بودن کلید رو چک میکنه و token مصرف نمیکنه،
+///   پس تلاش درست هیچ هزینه‌ای نداره
+/// - `record_failure` بعد از شکست: از bucket کم میکنه؛ وقتی bucket خالی بشه
+///   کلید تا پر شدن یک token قفل میشه، حتی برای رمز درست (وگرنه حدس زدن ادامه پیدا میکنه)
+#[derive(Debug, Clone)]
+pub struct FailureLimiter {
+    failures: RateLimiterState,
+    locked_until: Arc<Mutex<HashMap<String, Instant>>>,
+}
+
+impl FailureLimiter {
+    /// ساخت limiter با تعداد شکست مجاز در دقیقه
+    #[must_use]
+    pub fn per_minute(per_minute: u32, burst: u32) -> Self {
+        Self {
+            failures: RateLimiterState::per_minute(per_minute, burst),
+            locked_until: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+    
+    /// چک کردن قفل بودن کلید، بدون مصرف token
+    ///
+    /// # Errors
+    /// `AppError::RateLimited` اگه شکست‌های این کلید از سقف گذشته باشه
+    pub fn check(&self, key: &str) -> Result<(), AppError> {
+        let mut locked_until = self.locks();
+        let Some(until) = locked_until.get(key) else {
+            return Ok(());
+        };
         
-        // چک کردن window
-        let window = std::time::Duration::from_secs(self.window_seconds);
-        if now.duration_since(entry.1) > window {
-            // Window جدید
-            *entry = (1, now);
+        let wait = until.saturating_duration_since(Instant::now());
+        if wait.is_zero() {
+            locked_until.remove(key);
             return Ok(());
         }
-        
-        // چک کردن تعداد
-        if entry.0 >= self.max_requests {
-            return Err(AppError::RateLimited);
+        Err(self.failures.rate_limited(wait))
+    }
+    
+    /// ثبت یک تلاش ناموفق
+    ///
+    /// # Errors
+    /// `AppError::RateLimited` اگه کلید همزمان با این تلاش قفل شده باشه
+    pub fn record_failure(&self, key: &str) -> Result<(), AppError> {
+        match self.failures.check(key) {
+            // آخرین شکست مجاز؛ تلاش بعدی تا پر شدن یک token رد میشه
+            Ok(info) if info.remaining == 0 => {
+                self.lock(key, self.failures.period);
+                Ok(())
+            }
+            Ok(_) => Ok(()),
+            Err(AppError::RateLimited(info)) => {
+                self.lock(key, Duration::from_secs(info.retry_after_secs));
+                Err(AppError::RateLimited(info))
+            }
+            Err(e) => Err(e),
         }
-        
-        // افزایش counter
-        entry.0 += 1;
-        
-        Ok(())
     }
     
-    /// پاکسازی entry‌های قدیمی
-    pub async fn cleanup(&self) {
+    /// پاکسازی bucket‌های پر شده و قفل‌های منقضی
+    pub fn cleanup(&self) {
+        self.failures.cleanup();
         let now = Instant::now();
-        let window = std::time::Duration::from_secs(self.window_seconds);
-        let mut requests = self.requests.write().await;
-        
-        requests.retain(|_, (_, time)| now.duration_since(*time) <= window);
+        self.locks().retain(|_, until| *until > now);
+    }
+    
+    /// تعداد کلید‌هایی که الان ردیابی میشن
+    #[must_use]
+    pub fn tracked_keys(&self) -> usize {
+        self.failures.tracked_keys()
     }
+    
+    fn lock(&self, key: &str, duration: Duration) {
+        self.locks().insert(key.to_string(), Instant::now() + duration);
+    }
+    
+    /// گرفتن lock - هر ورودی جداگانه معتبره، پس poison نادیده گرفته میشه
+    fn locks(&self) -> MutexGuard<'_, HashMap<String, Instant>> {
+        self.locked_until.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Middleware محدودیت نرخ
+///
+/// # مفاهیم:
+/// - JWT با امضای معتبر: کلید `user:<id>` (فقط امضا و انقضا، بدون lookup کاربر)
+/// - API key: کلید `user:<صاحب کلید>`؛ صاحب از cache سرویس API key میاد
+/// - API key که توی cache نیست: اول از bucket `ip:<ip>` کم میشه و بعد query میخوره،
+///   پس کلید ساختگی نه query رایگان میگیره و نه bucket تازه
+/// - بقیه (ناشناس، توکن نامعتبر): کلید `ip:<ip>` از `ClientIp`
+/// - header‌های `X-RateLimit-*` روی همه پاسخ‌ها
+///
+/// # استفاده:
+/// ```rust,ignore
+/// let api = Router::new()
+///     .layer(axum::middleware::from_fn_with_state(state.clone(), rate_limit));
+/// ```
+pub async fn rate_limit(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    request: Request<Body>,
+    next: Next,
+) -> Result<impl IntoResponse, AppError> {
+    let ip_key = format!("ip:{}", ip.as_deref().unwrap_or("unknown"));
+    
+    let info = if let Some(api_key) = api_key_from_headers(request.headers()) {
+        match state.api_key_service.cached_owner(&api_key) {
+            Some(user_id) => state.rate_limiter.check(&format!("user:{}", user_id))?,
+            None => {
+                let info = state.rate_limiter.check(&ip_key)?;
+                match state.api_key_service.resolve_owner(&api_key).await? {
+                    Some(user_id) => state.rate_limiter.check(&format!("user:{}", user_id))?,
+                    None => info,
+                }
+            }
+        }
+    } else {
+        let session = request
+            .headers()
+            .get(axum::http::header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(extract_token_from_header)
+            .and_then(|token| state.auth_service.verify_token(token).ok());
+        
+        let key = match session {
+            Some(claims) => format!("user:{}", claims.sub),
+            None => ip_key,
+        };
+        
+        state.rate_limiter.check(&key)?
+    };
+    
+    let mut response = next.run(request).await;
+    info.apply_headers(response.headers_mut());
+    
+    Ok(response)
 }
 
 // =====================================
@@ -297,3 +483,117 @@ pub async fn security_headers(
     response
 }
 
+// =====================================
+// Tests
+// =====================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_rate_limiter_burst_then_reject() {
+        let limiter = RateLimiterState::new(1, 2);
+        
+        let first = limiter.check("ip:1.2.3.4").unwrap();
+        assert_eq!(first.limit, 2);
+        assert_eq!(first.remaining, 1);
+        assert!(limiter.check("ip:1.2.3.4").is_ok());
+        
+        match limiter.check("ip:1.2.3.4") {
+            Err(AppError::RateLimited(info)) => {
+                assert_eq!(info.remaining, 0);
+                assert!(info.retry_after_secs >= 1);
+            }
+            other => panic!("expected RateLimited, got {:?}", other),
+        }
+        
+        // کلید‌های دیگه bucket جداگانه دارن
+        assert!(limiter.check("user:abc").is_ok());
+        assert_eq!(limiter.tracked_keys(), 2);
+    }
+    
+    #[test]
+    fn test_failure_limiter_only_counts_failures() {
+        let limiter = FailureLimiter::per_minute(1, 2);
+        
+        // چک کردن token مصرف نمیکنه
+        for _ in 0..10 {
+            assert!(limiter.check("link:abc").is_ok());
+        }
+        
+        assert!(limiter.record_failure("link:abc").is_ok());
+        assert!(limiter.check("link:abc").is_ok());
+        assert!(limiter.record_failure("link:abc").is_ok());
+        
+        // بعد از سقف شکست‌ها کلید قفله
+        match limiter.check("link:abc") {
+            Err(AppError::RateLimited(info)) => {
+                assert_eq!(info.limit, 2);
+                assert!(info.retry_after_secs >= 1);
+            }
+            other => panic!("expected RateLimited, got {:?}", other),
+        }
+        assert!(limiter.check("link:other").is_ok());
+    }
+    
+    #[tokio::test]
+    async fn test_rate_limit_keys_api_key_on_owner() {
+        use axum::{http::StatusCode, routing::get, Router};
+        use tower::ServiceExt;
+        use crate::{
+            api::extractors::API_KEY_HEADER,
+            models::{ApiScope, CreateApiKeyRequest, LoginRequest, RegisterRequest},
+        };
+        
+        let state = AppState::in_memory(Config {
+            rate_limit_per_second: 1,
+            rate_limit_burst: 3,
+            ..Config::default()
+        });
+        let user = state.auth_service.register(RegisterRequest {
+            email: "limit@example.com".to_string(),
+            password: "password123".to_string(),
+            name: None,
+        }).await.unwrap().user;
+        let api_key = state.api_key_service.create_key(&user.id, CreateApiKeyRequest {
+            name: "ci".to_string(),
+            scopes: vec![ApiScope::UrlsRead],
+        }).await.unwrap().key;
+        let token = state.auth_service.login(LoginRequest {
+            email: "limit@example.com".to_string(),
+            password: "password123".to_string(),
+        }).await.unwrap().token;
+        
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(state, rate_limit));
+        let send = |header: Option<(&'static str, String)>| {
+            let mut request = Request::get("/");
+            if let Some((name, value)) = header {
+                request = request.header(name, value);
+            }
+            app.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+        let with_key = |key: &str| Some((API_KEY_HEADER, key.to_string()));
+        
+        // درخواست اول با کلید: یک بار از bucket آی‌پی (برای lookup) و یک بار از bucket کاربر
+        let first = send(with_key(&api_key)).await.unwrap();
+        assert_eq!(first.headers()["X-RateLimit-Remaining"], "2");
+        assert_eq!(send(with_key(&api_key)).await.unwrap().status(), StatusCode::OK);
+        
+        // کلید و session همون کاربر یه bucket مشترک دارن
+        let session = Some(("Authorization", format!("Bearer {}", token)));
+        assert_eq!(send(session).await.unwrap().status(), StatusCode::OK);
+        assert_eq!(send(with_key(&api_key)).await.unwrap().status(), StatusCode::TOO_MANY_REQUESTS);
+        
+        // bucket آی‌پی فقط برای lookup اول خرج شده
+        let anonymous = send(None).await.unwrap();
+        assert_eq!(anonymous.status(), StatusCode::OK);
+        assert_eq!(anonymous.headers()["X-RateLimit-Remaining"], "1");
+        
+        // کلید ساختگی از bucket آی‌پی کم میکنه
+        assert_eq!(send(with_key("usk_fake")).await.unwrap().status(), StatusCode::OK);
+        assert_eq!(send(with_key("usk_fake")).await.unwrap().status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+}
+
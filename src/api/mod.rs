@@ -14,22 +14,37 @@
 //! - `POST /api/urls` - ساخت URL کوتاه
 //! - `GET /:code` - Redirect به URL اصلی
 //! - `GET /api/urls/:code` - اطلاعات URL
+//! - `PATCH /api/urls/:code` - ویرایش URL
 //! - `DELETE /api/urls/:code` - حذف URL
+//! - `GET /api/urls/:code/analytics` - آمار کلیک‌های URL
+//! - `POST /api/urls/batch-delete` - حذف دسته‌ای URL‌ها
 //! - `POST /api/auth/register` - ثبت‌نام
 //! - `POST /api/auth/login` - ورود
+//! - `POST /api/auth/refresh` - چرخش refresh token و گرفتن access token جدید
+//! - `POST /api/auth/logout` - ابطال refresh token
 //! - `GET /api/me` - پروفایل کاربر
+//! - `PATCH /api/me` - ویرایش پروفایل
+//! - `POST /api/me/password` - تغییر رمز عبور
+//! - `GET/POST /api/me/api-keys` - لیست و ساخت API key
+//! - `DELETE /api/me/api-keys/:id` - ابطال API key
+//! - `GET /api/stats` - آمار کلی (فقط ادمین)
+//! - `/api/admin/*` - مدیریت کاربران و لینک‌ها (فقط ادمین)
 //! - `GET /health` - Health check
+//! - `GET /health/live` - Liveness probe
+//! - `GET /health/ready` - Readiness probe (دیتابیس و migration‌ها)
 
 mod handlers;
 mod middleware;
 mod extractors;
+mod server;
 
 pub use handlers::*;
 pub use middleware::*;
 pub use extractors::*;
+pub use server::*;
 
 use axum::{
-    routing::{get, post, delete},
+    routing::{get, post, patch, delete},
     Router,
     middleware as axum_middleware,
 };
@@ -64,19 +79,35 @@ use crate::{
 /// * `db` - اتصال دیتابیس
 /// * `config` - تنظیمات برنامه
 pub fn create_router(db: Database, config: Config) -> Router {
-    // ساخت AppState
-    let state = AppState::new(db, config.clone());
-    
+    create_router_with_state(AppState::new(db, config))
+}
+
+/// ساخت Router از یک AppState موجود
+///
+/// وقتی کاربرد داره که state بیرون از router هم لازمه،
+/// مثلاً برای کارهای پس‌زمینه در `main.rs`.
+pub fn create_router_with_state(state: AppState) -> Router {
     // ساخت router با گروه‌بندی
     Router::new()
         // Route اصلی redirect
-        .route("/:code", get(handlers::url::redirect_handler))
+        .route(
+            "/:code",
+            get(handlers::url::redirect_handler).post(handlers::url::unlock_handler),
+        )
         
-        // API routes
-        .nest("/api", api_routes())
+        // API routes (با rate limiting برای هر کاربر یا IP)
+        .nest(
+            "/api",
+            api_routes().layer(axum_middleware::from_fn_with_state(
+                state.clone(),
+                middleware::rate_limit,
+            )),
+        )
         
         // Health check
         .route("/health", get(handlers::health::health_check))
+        .route("/health/live", get(handlers::health::liveness))
+        .route("/health/ready", get(handlers::health::readiness))
         
         // Middleware‌های عمومی
         .layer(
@@ -118,10 +149,20 @@ fn api_routes() -> Router<AppState> {
         
         // User endpoints (نیاز به احراز هویت)
         .route("/me", get(handlers::user::get_profile))
+        .route("/me", patch(handlers::user::update_profile))
         .route("/me/urls", get(handlers::user::get_my_urls))
+        .route("/me/password", post(handlers::user::change_password))
         
-        // Stats
+        // API key‌ها (فقط با نشست JWT)
+        .route("/me/api-keys", get(handlers::api_key::list_api_keys))
+        .route("/me/api-keys", post(handlers::api_key::create_api_key))
+        .route("/me/api-keys/:id", delete(handlers::api_key::revoke_api_key))
+        
+        // Stats (فقط ادمین)
         .route("/stats", get(handlers::stats::get_stats))
+        
+        // پنل ادمین
+        .nest("/admin", admin_routes())
 }
 
 /// Route‌های URL
@@ -130,11 +171,37 @@ fn url_routes() -> Router<AppState> {
         // ساخت URL کوتاه
         .route("/", post(handlers::url::create_url))
         
+        // حذف دسته‌ای
+        .route("/batch-delete", post(handlers::url::batch_delete))
+        
         // اطلاعات URL
         .route("/:code", get(handlers::url::get_url_info))
         
+        // ویرایش URL
+        .route("/:code", patch(handlers::url::update_url))
+        
         // حذف URL
         .route("/:code", delete(handlers::url::delete_url))
+        
+        // آمار کلیک‌ها (فقط مالک)
+        .route("/:code/analytics", get(handlers::url::get_url_analytics))
+}
+
+/// Route‌های ادمین
+///
+/// همه handler‌ها `AdminUser` extractor دارن
+fn admin_routes() -> Router<AppState> {
+    Router::new()
+        // کاربران
+        .route("/users", get(handlers::admin::list_users))
+        .route("/users/:id", patch(handlers::admin::update_user))
+        
+        // لینک‌ها
+        .route("/urls", get(handlers::admin::list_urls))
+        .route("/urls/:code", delete(handlers::admin::delete_url))
+        
+        // آمار
+        .route("/stats", get(handlers::stats::get_stats))
 }
 
 /// Route‌های احراز هویت
@@ -148,6 +215,9 @@ fn auth_routes() -> Router<AppState> {
         
         // Refresh token
         .route("/refresh", post(handlers::auth::refresh_token))
+        
+        // خروج
+        .route("/logout", post(handlers::auth::logout))
 }
 
 // =====================================
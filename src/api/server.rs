@@ -0,0 +1,315 @@
+//! # Listener سرور HTTP
+//!
+//! سرور میتونه روی TCP (هاست و پورت از config) یا روی Unix domain socket
+//! گوش بده. وقتی پشت reverse proxy محلی اجرا میشه، socket فایل باعث میشه
+//! سرویس روی هیچ interface شبکه‌ای در دسترس نباشه.
+//!
+//! ## مفاهیم:
+//! - **Enum به جای Trait Object**: دو نوع listener با یه API مشترک
+//! - **`#[cfg(unix)]`**: کامپایل شرطی برای Unix socket
+//! - **hyper-util**: axum 0.7 فقط `TcpListener` رو serve میکنه،
+//!   برای Unix socket اتصال‌ها رو مستقیم به hyper میدیم
+
+use std::{
+    fmt,
+    future::Future,
+    net::SocketAddr,
+};
+
+use axum::Router;
+use tokio::net::TcpListener;
+
+use crate::{
+    config::Config,
+    error::{AppError, Result},
+};
+
+#[cfg(unix)]
+use std::{os::unix::fs::FileTypeExt, path::PathBuf};
+
+#[cfg(unix)]
+use tokio::net::UnixListener;
+
+// =====================================
+// Listener
+// =====================================
+/// Listener سرور - TCP یا Unix socket
+#[derive(Debug)]
+pub enum Listener {
+    /// گوش دادن روی آدرس TCP
+    Tcp(TcpListener),
+    
+    /// گوش دادن روی فایل Unix domain socket
+    #[cfg(unix)]
+    Unix {
+        listener: UnixListener,
+        path: PathBuf,
+    },
+}
+
+impl Listener {
+    /// ساخت listener بر اساس config
+    ///
+    /// اگه `unix_socket` تنظیم شده باشه روی اون فایل گوش میده،
+    /// وگرنه روی `host:port`. هاست میتونه IPv4، IPv6 (با یا بدون `[]`)
+    /// یا اسم باشه.
+    ///
+    /// # Errors
+    /// - آدرس قابل bind نباشه (پورت اشغال، هاست نامعتبر)
+    /// - Unix socket روی پلتفرم غیر unix خواسته بشه
+    pub async fn bind(config: &Config) -> Result<Self> {
+        if let Some(path) = &config.unix_socket {
+            return Self::bind_unix(path);
+        }
+        
+        let listener = TcpListener::bind((config.bind_host(), config.port)).await?;
+        Ok(Self::Tcp(listener))
+    }
+    
+    #[cfg(unix)]
+    fn bind_unix(path: &str) -> Result<Self> {
+        let path = PathBuf::from(path);
+        
+        // فایل socket قدیمی (از اجرای قبلی) bind رو fail میکنه؛
+        // ولی هر چیز دیگه‌ای اون مسیر احتمالاً اشتباه تایپی هست و دست نمیخوره
+        match std::fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(&path)?,
+            Ok(_) => {
+                return Err(AppError::Config(format!(
+                    "UNIX_SOCKET path '{}' already exists and is not a socket",
+                    path.display()
+                )));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        
+        let listener = UnixListener::bind(&path)?;
+        Ok(Self::Unix { listener, path })
+    }
+    
+    #[cfg(not(unix))]
+    fn bind_unix(_path: &str) -> Result<Self> {
+        Err(AppError::Config(
+            "UNIX_SOCKET is only supported on unix platforms".to_string()
+        ))
+    }
+    
+    /// آدرس TCP واقعی (مثلاً وقتی پورت 0 بوده)
+    #[must_use]
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        match self {
+            Self::Tcp(listener) => listener.local_addr().ok(),
+            #[cfg(unix)]
+            Self::Unix { .. } => None,
+        }
+    }
+    
+    /// اجرای سرور تا وقتی `shutdown` تموم بشه
+    ///
+    /// بعد از shutdown اتصال جدید قبول نمیشه و منتظر تموم شدن
+    /// درخواست‌های باز میمونه.
+    ///
+    /// # Errors
+    /// خطای I/O سرور
+    pub async fn serve<F>(self, app: Router, shutdown: F) -> Result<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        match self {
+            Self::Tcp(listener) => {
+                // ConnectInfo آدرس کلاینت رو برای rate limiting در دسترس میذاره
+                axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+                    .with_graceful_shutdown(shutdown)
+                    .await
+                    .map_err(|e| AppError::Server(e.to_string()))
+            }
+            #[cfg(unix)]
+            Self::Unix { listener, path } => {
+                let result = unix::serve(listener, app, shutdown).await;
+                
+                // پاک کردن فایل socket بعد از بسته شدن
+                if let Err(e) = std::fs::remove_file(&path) {
+                    tracing::warn!(error = %e, path = %path.display(), "Failed to remove unix socket");
+                }
+                
+                result
+            }
+        }
+    }
+}
+
+impl fmt::Display for Listener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(listener) => match listener.local_addr() {
+                Ok(addr) => write!(f, "http://{addr}"),
+                Err(_) => write!(f, "tcp (unknown address)"),
+            },
+            #[cfg(unix)]
+            Self::Unix { path, .. } => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+// =====================================
+// Unix Socket Server
+// =====================================
+#[cfg(unix)]
+mod unix {
+    use std::{future::Future, time::Duration};
+    
+    use axum::Router;
+    use hyper_util::{
+        rt::{TokioExecutor, TokioIo},
+        server::{conn::auto, graceful::GracefulShutdown},
+        service::TowerToHyperService,
+    };
+    use tokio::net::UnixListener;
+    use tracing::{debug, warn};
+    
+    use crate::error::Result;
+    
+    /// حلقه accept برای Unix socket
+    ///
+    /// # مفاهیم:
+    /// - هر اتصال در task جداگانه serve میشه
+    /// - `GracefulShutdown` اتصال‌های باز رو ردیابی میکنه تا بعد از
+    ///   سیگنال، درخواست‌های در حال اجرا تموم بشن
+    /// - ConnectInfo وجود نداره؛ اتصال از پروکسی محلی حساب میشه و `X-Forwarded-For` اون قبوله
+    pub(super) async fn serve<F>(listener: UnixListener, app: Router, shutdown: F) -> Result<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let builder = auto::Builder::new(TokioExecutor::new());
+        let graceful = GracefulShutdown::new();
+        let mut shutdown = std::pin::pin!(shutdown);
+        
+        loop {
+            let stream = tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        // مثلاً EMFILE - کمی صبر میکنیم تا حلقه CPU رو نسوزونه
+                        warn!(error = %e, "Failed to accept unix socket connection");
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                        continue;
+                    }
+                },
+                () = &mut shutdown => break,
+            };
+            
+            let service = TowerToHyperService::new(app.clone());
+            let connection = builder
+                .serve_connection_with_upgrades(TokioIo::new(stream), service)
+                .into_owned();
+            let connection = graceful.watch(connection);
+            
+            tokio::spawn(async move {
+                if let Err(e) = connection.await {
+                    debug!(error = %e, "Unix socket connection closed with error");
+                }
+            });
+        }
+        
+        // قبول نکردن اتصال جدید و صبر برای اتصال‌های باز
+        drop(listener);
+        graceful.shutdown().await;
+        
+        Ok(())
+    }
+}
+
+// =====================================
+// Tests
+// =====================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigBuilder;
+    
+    #[tokio::test]
+    async fn test_bind_ipv6_loopback() {
+        let config = ConfigBuilder::new().host("[::1]").port(0).build();
+        
+        // بعضی محیط‌ها (مثلاً container بدون IPv6) اجازه bind نمیدن
+        let Ok(listener) = Listener::bind(&config).await else {
+            return;
+        };
+        
+        let addr = listener.local_addr().unwrap();
+        assert!(addr.ip().is_loopback());
+        assert!(addr.is_ipv6());
+    }
+    
+    #[tokio::test]
+    async fn test_bind_configured_host() {
+        let config = ConfigBuilder::new().host("127.0.0.1").port(0).build();
+        let listener = Listener::bind(&config).await.unwrap();
+        
+        let addr = listener.local_addr().unwrap();
+        assert_eq!(addr.ip().to_string(), "127.0.0.1");
+        assert!(listener.to_string().starts_with("http://127.0.0.1:"));
+    }
+    
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_serve_over_unix_socket() {
+        use axum::routing::get;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        
+        let path = std::env::temp_dir().join(format!("url-shortener-{}.sock", nanoid::nanoid!(8)));
+        let config = ConfigBuilder::new()
+            .unix_socket(path.to_string_lossy())
+            .build();
+        
+        let listener = Listener::bind(&config).await.unwrap();
+        assert_eq!(listener.to_string(), format!("unix:{}", path.display()));
+        
+        let app = Router::new().route("/ping", get(|| async { "pong" }));
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn(listener.serve(app, async move {
+            let _ = stopped.await;
+        }));
+        
+        let mut stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+        stream
+            .write_all(b"GET /ping HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.ends_with("pong"));
+        
+        stop.send(()).unwrap();
+        server.await.unwrap().unwrap();
+        
+        // فایل socket بعد از shutdown پاک میشه
+        assert!(!path.exists());
+    }
+    
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_bind_unix_only_replaces_stale_socket() {
+        let path = std::env::temp_dir().join(format!("url-shortener-{}.sock", nanoid::nanoid!(8)));
+        let config = ConfigBuilder::new()
+            .unix_socket(path.to_string_lossy())
+            .build();
+        
+        // socket جامونده از اجرای قبلی جایگزین میشه
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+        drop(Listener::bind(&config).await.unwrap());
+        std::fs::remove_file(&path).unwrap();
+        
+        // فایل معمولی پاک نمیشه
+        std::fs::write(&path, "data").unwrap();
+        let result = Listener::bind(&config).await;
+        assert!(matches!(result, Err(AppError::Config(_))));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "data");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
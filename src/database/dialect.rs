@@ -0,0 +1,78 @@
+//! # تفاوت‌های SQL بین backend‌ها
+//!
+//! بیشتر query‌ها بین SQLite و PostgreSQL مشترک هستن (placeholder‌های `$N`
+//! در هر دو کار میکنن). چیزهایی که فرق دارن اینجا جمع شدن تا repository‌ها
+//! پر از `#[cfg]` نشن.
+//!
+//! ## مفاهیم:
+//! - **Conditional Compilation**: فقط نسخه backend فعال کامپایل میشه
+//! - `format!` فقط با ستون‌های ثابت صدا زده میشه، نه مقادیر کاربر
+
+use crate::models::AnalyticsInterval;
+
+/// عملگر جستجوی بدون حساسیت به حروف بزرگ و کوچک
+///
+/// `LIKE` در SQLite برای ASCII case-insensitive هست، ولی PostgreSQL `ILIKE` لازم داره
+#[cfg(feature = "postgres")]
+pub(crate) const LIKE: &str = "ILIKE";
+
+#[cfg(not(feature = "postgres"))]
+pub(crate) const LIKE: &str = "LIKE";
+
+/// query سلامت که واقعا catalog دیتابیس رو میخونه (نه فقط `SELECT 1`)
+#[cfg(feature = "postgres")]
+pub(crate) const HEALTH_CHECK: &str = "SELECT COUNT(*) FROM pg_catalog.pg_tables";
+
+#[cfg(not(feature = "postgres"))]
+pub(crate) const HEALTH_CHECK: &str = "SELECT COUNT(*) FROM sqlite_master";
+
+/// آیا جدول `_sqlx_migrations` ساخته شده؟ (`bool`)
+///
+/// در PostgreSQL `to_regclass` از `search_path` پیروی میکنه، مثل خود migrator
+#[cfg(feature = "postgres")]
+pub(crate) const MIGRATIONS_TABLE_EXISTS: &str =
+    "SELECT to_regclass('_sqlx_migrations') IS NOT NULL";
+
+#[cfg(not(feature = "postgres"))]
+pub(crate) const MIGRATIONS_TABLE_EXISTS: &str =
+    "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations')";
+
+/// تاریخ روز (`YYYY-MM-DD`، UTC) یک ستون زمانی
+#[cfg(feature = "postgres")]
+pub(crate) fn day(column: &str) -> String {
+    format!("to_char({column} AT TIME ZONE 'UTC', 'YYYY-MM-DD')")
+}
+
+#[cfg(not(feature = "postgres"))]
+pub(crate) fn day(column: &str) -> String {
+    format!("strftime('%Y-%m-%d', {column})")
+}
+
+/// کلید bucket سری زمانی برای یک interval
+///
+/// # فرمت خروجی (در هر دو backend یکسان)
+/// - ساعت: `2024-01-15T13:00:00Z`
+/// - روز: `2024-01-15`
+/// - هفته: تاریخ دوشنبه همون هفته
+#[cfg(feature = "postgres")]
+pub(crate) fn interval_bucket(interval: AnalyticsInterval, column: &str) -> String {
+    match interval {
+        AnalyticsInterval::Hour => {
+            format!(r#"to_char({column} AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:00:00"Z"')"#)
+        }
+        AnalyticsInterval::Day => day(column),
+        AnalyticsInterval::Week => {
+            format!("to_char(date_trunc('week', {column} AT TIME ZONE 'UTC'), 'YYYY-MM-DD')")
+        }
+    }
+}
+
+#[cfg(not(feature = "postgres"))]
+pub(crate) fn interval_bucket(interval: AnalyticsInterval, column: &str) -> String {
+    match interval {
+        AnalyticsInterval::Hour => format!("strftime('%Y-%m-%dT%H:00:00Z', {column})"),
+        AnalyticsInterval::Day => day(column),
+        AnalyticsInterval::Week => format!("date({column}, '-6 days', 'weekday 1')"),
+    }
+}
+
@@ -0,0 +1,974 @@
+//! # ذخیره‌سازی در حافظه (In-Memory Store)
+//!
+//! پیاده‌سازی storage trait‌ها بدون دیتابیس. همه داده‌ها در یک
+//! `RwLock` نگه داشته میشن و با drop شدن آخرین clone از بین میرن.
+//!
+//! ## کاربردها:
+//! - تست سرویس‌ها بدون ساختن دیتابیس و اجرای migration
+//! - نسخه دمو که هیچ فایلی روی دیسک نمیسازه
+//!
+//! ## تفاوت با SQL:
+//! - foreign key چک نمیشه (مثلا `user_id` یه URL لازم نیست کاربر واقعی باشه)
+//! - تکراری بودن short_code یا email خطای `Conflict` میده
+//! - رفتار جستجو، مرتب‌سازی و analytics مثل query‌های SQL هست
+//!
+//! ## مفاهیم Rust:
+//! - **Interior Mutability**: تغییر داده پشت `&self` با `RwLock`
+//! - **Shared Ownership**: همه clone‌ها یک `Arc` مشترک دارن
+//! - lock هیچوقت روی `.await` نگه داشته نمیشه
+
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, Duration, Utc};
+
+use super::{ApiKeyStore, ClickStore, RefreshTokenStore, UrlStats, UrlStore, UserStore};
+use crate::{
+    error::{AppError, Result},
+    models::{
+        hash_password, AnalyticsInterval, ApiKey, ClickBatch, ClickBucket, ClickDimension,
+        ClickEvent, CreateApiKey, CreateClickEvent, CreateRefreshToken, CreateUrl, CreateUser,
+        DailyCount, Pagination, RefreshToken, SortOrder, TopValue, Url, UrlFilter, UrlSortField,
+        UrlStatusFilter, User, UserFilter, UserRole,
+    },
+};
+
+/// جدول‌های در حافظه
+#[derive(Debug, Default)]
+struct Tables {
+    /// URL‌ها با کلید short_code (مسیر پرتکرار redirect)
+    urls: HashMap<String, Url>,
+    clicks: Vec<ClickEvent>,
+    /// کاربران با کلید id
+    users: HashMap<String, User>,
+    /// refresh token‌ها با کلید id
+    refresh_tokens: HashMap<String, RefreshToken>,
+    /// API key‌ها با کلید id
+    api_keys: HashMap<String, ApiKey>,
+}
+
+impl Tables {
+    fn url_by_id(&self, id: &str) -> Option<&Url> {
+        self.urls.values().find(|url| url.id == id)
+    }
+    
+    /// حذف URL و کلیک‌هاش (مثل `ON DELETE CASCADE`)
+    fn remove_url(&mut self, short_code: &str) -> Option<Url> {
+        let url = self.urls.remove(short_code)?;
+        self.clicks.retain(|click| click.url_id != url.id);
+        Some(url)
+    }
+    
+    /// کلیک‌های یک URL در بازه `[from, to)`
+    fn clicks_in_range<'a>(
+        &'a self,
+        url_id: &'a str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> impl Iterator<Item = &'a ClickEvent> {
+        self.clicks
+            .iter()
+            .filter(move |c| c.url_id == url_id && c.clicked_at >= from && c.clicked_at < to)
+    }
+}
+
+// =====================================
+// Memory Store
+// =====================================
+/// ذخیره‌سازی کامل در حافظه
+///
+/// یک مقدار همه storage trait‌ها رو پیاده‌سازی میکنه، پس میشه همون رو
+/// (با `Arc::new(store.clone())`) به همه سرویس‌ها داد.
+///
+/// # مثال
+/// ```rust,no_run
+/// use std::sync::Arc;
+/// use url_shortener::{
+///     config::Config,
+///     database::MemoryStore,
+///     services::{ClickWriter, RedirectCache, UrlService},
+/// };
+///
+/// # async fn example() {
+/// let store = MemoryStore::new();
+/// let config = Config::default();
+/// let service = UrlService::new(
+///     Arc::new(store.clone()),
+///     Arc::new(store.clone()),
+///     Arc::new(RedirectCache::disabled()),
+///     Arc::new(ClickWriter::spawn(Arc::new(store), &config)),
+///     Arc::new(config),
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    tables: Arc<RwLock<Tables>>,
+}
+
+impl MemoryStore {
+    /// ساخت store خالی
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    // panic یک thread وسط نوشتن داده‌ها رو نیمه‌کاره نمیذاره
+    // (هر متد قبل از تغییر همه چیز رو چک میکنه)، پس poison رو نادیده میگیریم
+    fn read(&self) -> RwLockReadGuard<'_, Tables> {
+        self.tables.read().unwrap_or_else(PoisonError::into_inner)
+    }
+    
+    fn write(&self) -> RwLockWriteGuard<'_, Tables> {
+        self.tables.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// =====================================
+// URL Store
+// =====================================
+#[async_trait]
+impl UrlStore for MemoryStore {
+    async fn find_by_id(&self, id: &str) -> Result<Option<Url>> {
+        Ok(self.read().url_by_id(id).cloned())
+    }
+    
+    async fn find_by_short_code(&self, short_code: &str) -> Result<Option<Url>> {
+        Ok(self.read().urls.get(short_code).cloned())
+    }
+    
+    async fn exists(&self, short_code: &str) -> Result<bool> {
+        Ok(self.read().urls.contains_key(short_code))
+    }
+    
+    async fn create(&self, create_url: &CreateUrl) -> Result<Url> {
+        let mut tables = self.write();
+        
+        if tables.urls.contains_key(&create_url.short_code) {
+            return Err(AppError::Conflict(
+                format!("Short code '{}' already exists", create_url.short_code)
+            ));
+        }
+        
+        let now = Utc::now();
+        let url = Url {
+            id: create_url.id.clone(),
+            short_code: create_url.short_code.clone(),
+            original_url: create_url.original_url.clone(),
+            title: create_url.title.clone(),
+            clicks: 0,
+            user_id: create_url.user_id.clone(),
+            expires_at: create_url.expires_at,
+            redirect_type: create_url.redirect_type,
+            password_hash: create_url.password_hash.clone(),
+            max_clicks: create_url.max_clicks,
+            starts_at: create_url.starts_at,
+            created_at: now,
+            updated_at: now,
+        };
+        
+        tables.urls.insert(url.short_code.clone(), url.clone());
+        Ok(url)
+    }
+    
+    async fn update(&self, url: &Url) -> Result<Url> {
+        let mut tables = self.write();
+        
+        let stored = tables
+            .urls
+            .values_mut()
+            .find(|stored| stored.id == url.id)
+            .ok_or_else(|| AppError::url_not_found(&url.short_code))?;
+        
+        stored.original_url = url.original_url.clone();
+        stored.title = url.title.clone();
+        stored.expires_at = url.expires_at;
+        stored.redirect_type = url.redirect_type;
+        stored.password_hash = url.password_hash.clone();
+        stored.max_clicks = url.max_clicks;
+        stored.starts_at = url.starts_at;
+        stored.updated_at = Utc::now();
+        
+        Ok(stored.clone())
+    }
+    
+    async fn increment_clicks(&self, short_code: &str) -> Result<bool> {
+        let mut tables = self.write();
+        
+        let Some(url) = tables.urls.get_mut(short_code) else {
+            return Ok(false);
+        };
+        
+        if url.is_exhausted() {
+            return Ok(false);
+        }
+        
+        url.clicks += 1;
+        url.updated_at = Utc::now();
+        Ok(true)
+    }
+    
+    async fn delete(&self, id: &str) -> Result<bool> {
+        let mut tables = self.write();
+        
+        let Some(short_code) = tables.url_by_id(id).map(|url| url.short_code.clone()) else {
+            return Ok(false);
+        };
+        
+        Ok(tables.remove_url(&short_code).is_some())
+    }
+    
+    async fn delete_many_owned(&self, ids: &[String], user_id: &str) -> Result<Vec<String>> {
+        let mut tables = self.write();
+        let mut deleted = Vec::with_capacity(ids.len());
+        
+        for id in ids {
+            let owned = tables
+                .url_by_id(id)
+                .filter(|url| url.user_id.as_deref() == Some(user_id))
+                .map(|url| url.short_code.clone());
+            
+            if let Some(short_code) = owned {
+                tables.remove_url(&short_code);
+                deleted.push(id.clone());
+            }
+        }
+        
+        Ok(deleted)
+    }
+    
+    async fn search(
+        &self,
+        owner: Option<&str>,
+        filter: &UrlFilter,
+        pagination: &Pagination,
+    ) -> Result<(Vec<Url>, u64)> {
+        let now = Utc::now();
+        // LIKE در SQL هم برای ASCII به حروف بزرگ و کوچک حساس نیست
+        let query = filter.query.as_deref().map(str::to_lowercase);
+        
+        let mut urls: Vec<Url> = self
+            .read()
+            .urls
+            .values()
+            .filter(|url| owner.is_none_or(|owner| url.user_id.as_deref() == Some(owner)))
+            .filter(|url| match &query {
+                Some(query) => {
+                    url.original_url.to_lowercase().contains(query)
+                        || url.title.as_deref().is_some_and(|t| t.to_lowercase().contains(query))
+                }
+                None => true,
+            })
+            .filter(|url| match filter.status {
+                Some(UrlStatusFilter::Active) => url.expires_at.is_none_or(|at| at > now),
+                Some(UrlStatusFilter::Expired) => url.expires_at.is_some_and(|at| at <= now),
+                None => true,
+            })
+            .cloned()
+            .collect();
+        
+        urls.sort_by(|a, b| {
+            let ordering = match filter.sort_by {
+                UrlSortField::CreatedAt => a.created_at.cmp(&b.created_at),
+                UrlSortField::Clicks => a.clicks.cmp(&b.clicks),
+            };
+            let ordering = match filter.order {
+                SortOrder::Asc => ordering,
+                SortOrder::Desc => ordering.reverse(),
+            };
+            ordering.then_with(|| a.id.cmp(&b.id))
+        });
+        
+        let total = urls.len() as u64;
+        let page = urls
+            .into_iter()
+            .skip(pagination.offset() as usize)
+            .take(pagination.limit() as usize)
+            .collect();
+        
+        Ok((page, total))
+    }
+    
+    async fn delete_expired(&self) -> Result<u64> {
+        let mut tables = self.write();
+        let now = Utc::now();
+        
+        let expired: Vec<String> = tables
+            .urls
+            .values()
+            .filter(|url| url.expires_at.is_some_and(|at| at < now))
+            .map(|url| url.short_code.clone())
+            .collect();
+        
+        for short_code in &expired {
+            tables.remove_url(short_code);
+        }
+        
+        Ok(expired.len() as u64)
+    }
+    
+    async fn get_stats(&self) -> Result<UrlStats> {
+        let tables = self.read();
+        
+        let total_urls = tables.urls.len() as i64;
+        let total_clicks: i64 = tables.urls.values().map(|url| url.clicks).sum();
+        let avg_clicks = if total_urls == 0 {
+            0.0
+        } else {
+            total_clicks as f64 / total_urls as f64
+        };
+        
+        Ok(UrlStats { total_urls, total_clicks, avg_clicks })
+    }
+    
+    async fn count_by_status(&self) -> Result<(i64, i64)> {
+        let now = Utc::now();
+        let tables = self.read();
+        
+        let expired = tables
+            .urls
+            .values()
+            .filter(|url| url.expires_at.is_some_and(|at| at <= now))
+            .count() as i64;
+        
+        Ok((tables.urls.len() as i64 - expired, expired))
+    }
+    
+    async fn count_created_per_day(&self, since: DateTime<Utc>) -> Result<Vec<DailyCount>> {
+        let tables = self.read();
+        let days = tables
+            .urls
+            .values()
+            .filter(|url| url.created_at >= since)
+            .map(|url| url.created_at.format("%Y-%m-%d").to_string());
+        
+        Ok(count_by(days)
+            .into_iter()
+            .map(|(date, count)| DailyCount { date, count })
+            .collect())
+    }
+    
+    async fn find_top_by_clicks(&self, limit: u32) -> Result<Vec<Url>> {
+        let mut urls: Vec<Url> = self.read().urls.values().cloned().collect();
+        
+        // ORDER BY clicks DESC, created_at ASC
+        urls.sort_by(|a, b| b.clicks.cmp(&a.clicks).then_with(|| a.created_at.cmp(&b.created_at)));
+        urls.truncate(limit as usize);
+        
+        Ok(urls)
+    }
+}
+
+// =====================================
+// Click Store
+// =====================================
+/// کلید bucket با همون فرمت `dialect::interval_bucket`
+fn interval_bucket(interval: AnalyticsInterval, at: DateTime<Utc>) -> String {
+    match interval {
+        AnalyticsInterval::Hour => at.format("%Y-%m-%dT%H:00:00Z").to_string(),
+        AnalyticsInterval::Day => at.format("%Y-%m-%d").to_string(),
+        AnalyticsInterval::Week => {
+            let monday = at.date_naive()
+                - Duration::days(i64::from(at.weekday().num_days_from_monday()));
+            monday.format("%Y-%m-%d").to_string()
+        }
+    }
+}
+
+/// مقدار یک بعد از رویداد کلیک
+fn dimension_value(click: &ClickEvent, dimension: ClickDimension) -> Option<&str> {
+    match dimension {
+        ClickDimension::Referer => click.referer.as_deref(),
+        ClickDimension::UserAgent => click.user_agent.as_deref(),
+        ClickDimension::Country => click.country.as_deref(),
+    }
+}
+
+/// شمارش مقادیر و مرتب‌سازی بر اساس کلید
+fn count_by(values: impl Iterator<Item = String>) -> Vec<(String, i64)> {
+    let mut counts: HashMap<String, i64> = HashMap::new();
+    for value in values {
+        *counts.entry(value).or_default() += 1;
+    }
+    
+    let mut counts: Vec<(String, i64)> = counts.into_iter().collect();
+    counts.sort();
+    counts
+}
+
+/// تبدیل رویداد ورودی به ردیف ذخیره شده
+fn click_event(event: &CreateClickEvent) -> ClickEvent {
+    ClickEvent {
+        id: event.id.clone(),
+        url_id: event.url_id.clone(),
+        ip_address: event.ip_address.clone(),
+        user_agent: event.user_agent.clone(),
+        referer: event.referer.clone(),
+        country: event.country.clone(),
+        clicked_at: event.clicked_at,
+    }
+}
+
+#[async_trait]
+impl ClickStore for MemoryStore {
+    async fn create(&self, event: &CreateClickEvent) -> Result<()> {
+        self.write().clicks.push(click_event(event));
+        
+        Ok(())
+    }
+    
+    async fn record_batch(&self, batch: &ClickBatch) -> Result<u64> {
+        // یک write lock برای کل batch، مثل تراکنش SQL
+        let mut tables = self.write();
+        let now = Utc::now();
+        
+        for url in tables.urls.values_mut() {
+            if let Some(count) = batch.increments.get(&url.id) {
+                url.clicks += i64::try_from(*count).unwrap_or(i64::MAX);
+                url.updated_at = now;
+            }
+        }
+        
+        let mut recorded = 0;
+        for event in &batch.events {
+            if tables.url_by_id(&event.url_id).is_some() {
+                tables.clicks.push(click_event(event));
+                recorded += 1;
+            }
+        }
+        
+        Ok(recorded)
+    }
+    
+    async fn count_by_url(&self, url_id: &str) -> Result<i64> {
+        let tables = self.read();
+        Ok(tables.clicks.iter().filter(|c| c.url_id == url_id).count() as i64)
+    }
+    
+    async fn count_in_range(
+        &self,
+        url_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<i64> {
+        Ok(self.read().clicks_in_range(url_id, from, to).count() as i64)
+    }
+    
+    async fn timeline(
+        &self,
+        url_id: &str,
+        interval: AnalyticsInterval,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<ClickBucket>> {
+        let tables = self.read();
+        let buckets = tables
+            .clicks_in_range(url_id, from, to)
+            .map(|click| interval_bucket(interval, click.clicked_at));
+        
+        Ok(count_by(buckets)
+            .into_iter()
+            .map(|(bucket, clicks)| ClickBucket { bucket, clicks })
+            .collect())
+    }
+    
+    async fn top_values(
+        &self,
+        url_id: &str,
+        dimension: ClickDimension,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<TopValue>> {
+        let tables = self.read();
+        let values = tables
+            .clicks_in_range(url_id, from, to)
+            .filter_map(|click| dimension_value(click, dimension))
+            .filter(|value| !value.is_empty())
+            .map(ToString::to_string);
+        
+        let mut counts = count_by(values);
+        // ORDER BY clicks DESC, value ASC (ترتیب value از count_by حفظ میشه)
+        counts.sort_by_key(|(_, clicks)| Reverse(*clicks));
+        
+        Ok(counts
+            .into_iter()
+            .take(limit as usize)
+            .map(|(value, clicks)| TopValue { value, clicks })
+            .collect())
+    }
+}
+
+// =====================================
+// User Store
+// =====================================
+#[async_trait]
+impl UserStore for MemoryStore {
+    async fn find_by_id(&self, id: &str) -> Result<Option<User>> {
+        Ok(self.read().users.get(id).cloned())
+    }
+    
+    async fn find_by_email(&self, email: &str) -> Result<Option<User>> {
+        Ok(self.read().users.values().find(|user| user.email == email).cloned())
+    }
+    
+    async fn email_exists(&self, email: &str) -> Result<bool> {
+        Ok(self.read().users.values().any(|user| user.email == email))
+    }
+    
+    async fn create(&self, create_user: &CreateUser) -> Result<User> {
+        let mut tables = self.write();
+        
+        if tables.users.values().any(|user| user.email == create_user.email) {
+            return Err(AppError::Conflict("Email already registered".to_string()));
+        }
+        
+        let now = Utc::now();
+        let user = User {
+            id: create_user.id.clone(),
+            email: create_user.email.clone(),
+            password_hash: create_user.password_hash.clone(),
+            name: create_user.name.clone(),
+            is_active: true,
+            role: UserRole::default(),
+            password_changed_at: None,
+            created_at: now,
+            updated_at: now,
+        };
+        
+        tables.users.insert(user.id.clone(), user.clone());
+        Ok(user)
+    }
+    
+    async fn update(&self, user: &User) -> Result<User> {
+        let mut tables = self.write();
+        
+        if tables.users.values().any(|other| other.email == user.email && other.id != user.id) {
+            return Err(AppError::Conflict("Email already registered".to_string()));
+        }
+        
+        let stored = tables
+            .users
+            .get_mut(&user.id)
+            .ok_or_else(|| AppError::NotFound(format!("User '{}' not found", user.id)))?;
+        
+        stored.email = user.email.clone();
+        stored.name = user.name.clone();
+        stored.is_active = user.is_active;
+        stored.role = user.role;
+        stored.updated_at = Utc::now();
+        
+        Ok(stored.clone())
+    }
+    
+    async fn update_password(&self, id: &str, new_password: &str) -> Result<()> {
+        // هش کردن کنده، قبل از گرفتن lock انجام میشه
+        let password_hash = hash_password(new_password)?;
+        let mut tables = self.write();
+        
+        let user = tables
+            .users
+            .get_mut(id)
+            .ok_or_else(|| AppError::NotFound(format!("User '{}' not found", id)))?;
+        
+        let now = Utc::now();
+        user.password_hash = password_hash;
+        user.password_changed_at = Some(now);
+        user.updated_at = now;
+        
+        Ok(())
+    }
+    
+    async fn search(&self, filter: &UserFilter, pagination: &Pagination) -> Result<(Vec<User>, u64)> {
+        let query = filter.query.as_deref().map(str::to_lowercase);
+        
+        let mut users: Vec<User> = self
+            .read()
+            .users
+            .values()
+            .filter(|user| match &query {
+                Some(query) => {
+                    user.email.to_lowercase().contains(query)
+                        || user.name.as_deref().is_some_and(|n| n.to_lowercase().contains(query))
+                }
+                None => true,
+            })
+            .filter(|user| filter.is_active.is_none_or(|active| user.is_active == active))
+            .cloned()
+            .collect();
+        
+        // ORDER BY created_at DESC, id ASC
+        users.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| a.id.cmp(&b.id)));
+        
+        let total = users.len() as u64;
+        let page = users
+            .into_iter()
+            .skip(pagination.offset() as usize)
+            .take(pagination.limit() as usize)
+            .collect();
+        
+        Ok((page, total))
+    }
+    
+    async fn count(&self) -> Result<i64> {
+        Ok(self.read().users.len() as i64)
+    }
+    
+    async fn count_active(&self) -> Result<i64> {
+        Ok(self.read().users.values().filter(|user| user.is_active).count() as i64)
+    }
+}
+
+// =====================================
+// Refresh Token Store
+// =====================================
+/// ساخت ردیف refresh token از DTO
+fn refresh_token(token: &CreateRefreshToken, now: DateTime<Utc>) -> RefreshToken {
+    RefreshToken {
+        id: token.id.clone(),
+        user_id: token.user_id.clone(),
+        token_hash: token.token_hash.clone(),
+        family_id: token.family_id.clone(),
+        expires_at: token.expires_at,
+        revoked_at: None,
+        replaced_by: None,
+        created_at: now,
+    }
+}
+
+/// باطل کردن توکن‌های فعالی که شرط رو دارن
+fn revoke_where(tables: &mut Tables, predicate: impl Fn(&RefreshToken) -> bool) -> u64 {
+    let now = Utc::now();
+    let mut revoked = 0;
+    
+    for token in tables.refresh_tokens.values_mut() {
+        if token.revoked_at.is_none() && predicate(token) {
+            token.revoked_at = Some(now);
+            revoked += 1;
+        }
+    }
+    
+    revoked
+}
+
+#[async_trait]
+impl RefreshTokenStore for MemoryStore {
+    async fn create(&self, token: &CreateRefreshToken) -> Result<()> {
+        let mut tables = self.write();
+        tables.refresh_tokens.insert(token.id.clone(), refresh_token(token, Utc::now()));
+        Ok(())
+    }
+    
+    async fn find_by_hash(&self, token_hash: &str) -> Result<Option<RefreshToken>> {
+        Ok(self
+            .read()
+            .refresh_tokens
+            .values()
+            .find(|token| token.token_hash == token_hash)
+            .cloned())
+    }
+    
+    async fn rotate(&self, current_id: &str, next: &CreateRefreshToken) -> Result<bool> {
+        // یک write lock برای کل عملیات، مثل تراکنش در SQL
+        let mut tables = self.write();
+        let now = Utc::now();
+        
+        match tables.refresh_tokens.get_mut(current_id) {
+            Some(current) if current.revoked_at.is_none() => {
+                current.revoked_at = Some(now);
+                current.replaced_by = Some(next.id.clone());
+            }
+            _ => return Ok(false),
+        }
+        
+        tables.refresh_tokens.insert(next.id.clone(), refresh_token(next, now));
+        Ok(true)
+    }
+    
+    async fn revoke_family(&self, family_id: &str) -> Result<u64> {
+        Ok(revoke_where(&mut self.write(), |token| token.family_id == family_id))
+    }
+    
+    async fn revoke_all_for_user(&self, user_id: &str) -> Result<u64> {
+        Ok(revoke_where(&mut self.write(), |token| token.user_id == user_id))
+    }
+}
+
+// =====================================
+// API Key Store
+// =====================================
+#[async_trait]
+impl ApiKeyStore for MemoryStore {
+    async fn create(&self, key: &CreateApiKey) -> Result<ApiKey> {
+        let api_key = ApiKey {
+            id: key.id.clone(),
+            user_id: key.user_id.clone(),
+            name: key.name.clone(),
+            key_prefix: key.key_prefix.clone(),
+            key_hash: key.key_hash.clone(),
+            scopes: key.scopes.clone(),
+            last_used_at: None,
+            revoked_at: None,
+            created_at: Utc::now(),
+        };
+        
+        self.write().api_keys.insert(api_key.id.clone(), api_key.clone());
+        Ok(api_key)
+    }
+    
+    async fn find_active_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>> {
+        let tables = self.read();
+        
+        // مثل JOIN روی users: کلید کاربر حذف شده یا غیرفعال برنمیگرده
+        Ok(tables
+            .api_keys
+            .values()
+            .find(|key| key.key_hash == key_hash && key.revoked_at.is_none())
+            .filter(|key| tables.users.get(&key.user_id).is_some_and(|user| user.is_active))
+            .cloned())
+    }
+    
+    async fn find_active_by_user(&self, user_id: &str) -> Result<Vec<ApiKey>> {
+        let mut keys: Vec<ApiKey> = self
+            .read()
+            .api_keys
+            .values()
+            .filter(|key| key.user_id == user_id && key.revoked_at.is_none())
+            .cloned()
+            .collect();
+        
+        keys.sort_by_key(|key| Reverse(key.created_at));
+        Ok(keys)
+    }
+    
+    async fn revoke(&self, id: &str, user_id: &str) -> Result<bool> {
+        let mut tables = self.write();
+        
+        match tables.api_keys.get_mut(id) {
+            Some(key) if key.user_id == user_id && key.revoked_at.is_none() => {
+                key.revoked_at = Some(Utc::now());
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+    
+    async fn touch(&self, id: &str) -> Result<()> {
+        if let Some(key) = self.write().api_keys.get_mut(id) {
+            key.last_used_at = Some(Utc::now());
+        }
+        
+        Ok(())
+    }
+}
+
+// =====================================
+// Tests
+// =====================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::{ClickRepository, Database, UrlRepository},
+        models::{ClickInfo, UrlBuilder},
+    };
+    
+    fn create_url(original_url: &str, code: &str, owner: Option<&str>) -> CreateUrl {
+        let mut builder = UrlBuilder::new(original_url).custom_code(code);
+        if let Some(owner) = owner {
+            builder = builder.user_id(owner);
+        }
+        builder.build().unwrap()
+    }
+    
+    /// یک سناریوی جستجو روی هر دو پیاده‌سازی نتیجه یکسان میده
+    async fn search_scenario(store: &dyn UrlStore, owner: &str) -> Vec<(String, u64)> {
+        for (code, path, clicks) in [("aaa111", "Docs/a", 2), ("bbb222", "docs/b", 0), ("ccc333", "blog/c", 1)] {
+            store
+                .create(&create_url(&format!("https://example.com/{}", path), code, Some(owner)))
+                .await
+                .unwrap();
+            for _ in 0..clicks {
+                store.increment_clicks(code).await.unwrap();
+            }
+        }
+        
+        let filters = [
+            UrlFilter::default(),
+            UrlFilter { query: Some("DOCS".to_string()), ..Default::default() },
+            UrlFilter {
+                sort_by: UrlSortField::Clicks,
+                order: SortOrder::Desc,
+                ..Default::default()
+            },
+            UrlFilter {
+                sort_by: UrlSortField::Clicks,
+                order: SortOrder::Asc,
+                ..Default::default()
+            },
+            UrlFilter { status: Some(UrlStatusFilter::Expired), ..Default::default() },
+        ];
+        
+        let mut results = Vec::new();
+        for filter in &filters {
+            let (urls, total) = store
+                .search(Some(owner), filter, &Pagination { page: 1, per_page: 2 })
+                .await
+                .unwrap();
+            let codes: Vec<&str> = urls.iter().map(|url| url.short_code.as_str()).collect();
+            results.push((codes.join(","), total));
+        }
+        results
+    }
+    
+    #[tokio::test]
+    async fn test_search_matches_sql_repository() {
+        let db = Database::ephemeral().await.unwrap();
+        let owner = crate::database::UserRepository::new(db.clone())
+            .create(&CreateUser::new("owner@example.com", "password123", None).unwrap())
+            .await
+            .unwrap()
+            .id;
+        
+        let sql = search_scenario(&UrlRepository::new(db), &owner).await;
+        let memory = search_scenario(&MemoryStore::new(), &owner).await;
+        
+        assert_eq!(memory, sql);
+        assert_eq!(memory[1], ("aaa111,bbb222".to_string(), 2));
+        assert_eq!(memory[2].0, "aaa111,ccc333");
+    }
+    
+    #[tokio::test]
+    async fn test_analytics_match_sql_repository() {
+        let store = MemoryStore::new();
+        let db = Database::ephemeral().await.unwrap();
+        let sql_urls = UrlRepository::new(db.clone());
+        let sql_clicks = ClickRepository::new(db);
+        
+        let url = create_url("https://example.com", "stats1", None);
+        UrlStore::create(&store, &url).await.unwrap();
+        UrlStore::create(&sql_urls, &url).await.unwrap();
+        
+        for referer in ["https://b.example", "https://a.example", "https://b.example", ""] {
+            let click = ClickInfo {
+                referer: Some(referer.to_string()),
+                ..Default::default()
+            };
+            let event = CreateClickEvent::new(&url.id, click);
+            ClickStore::create(&store, &event).await.unwrap();
+            ClickStore::create(&sql_clicks, &event).await.unwrap();
+        }
+        
+        let to = Utc::now() + Duration::minutes(1);
+        let from = to - Duration::days(1);
+        
+        for interval in [AnalyticsInterval::Hour, AnalyticsInterval::Day, AnalyticsInterval::Week] {
+            let memory = store.timeline(&url.id, interval, from, to).await.unwrap();
+            let sql = sql_clicks.timeline(&url.id, interval, from, to).await.unwrap();
+            
+            let memory: Vec<_> = memory.into_iter().map(|b| (b.bucket, b.clicks)).collect();
+            let sql: Vec<_> = sql.into_iter().map(|b| (b.bucket, b.clicks)).collect();
+            assert_eq!(memory, sql);
+        }
+        
+        let memory = store
+            .top_values(&url.id, ClickDimension::Referer, from, to, 10)
+            .await
+            .unwrap();
+        let sql = sql_clicks
+            .top_values(&url.id, ClickDimension::Referer, from, to, 10)
+            .await
+            .unwrap();
+        
+        let memory: Vec<_> = memory.into_iter().map(|v| (v.value, v.clicks)).collect();
+        let sql: Vec<_> = sql.into_iter().map(|v| (v.value, v.clicks)).collect();
+        assert_eq!(memory, sql);
+        assert_eq!(memory[0], ("https://b.example".to_string(), 2));
+    }
+    
+    #[tokio::test]
+    async fn test_delete_cascades_clicks() {
+        let store = MemoryStore::new();
+        let url = UrlStore::create(&store, &create_url("https://example.com", "gone12", Some("u1")))
+            .await
+            .unwrap();
+        ClickStore::create(&store, &CreateClickEvent::new(&url.id, ClickInfo::default()))
+            .await
+            .unwrap();
+        
+        // فقط مالک میتونه حذف کنه
+        let deleted = store.delete_many_owned(std::slice::from_ref(&url.id), "u2").await.unwrap();
+        assert!(deleted.is_empty());
+        
+        let deleted = store.delete_many_owned(std::slice::from_ref(&url.id), "u1").await.unwrap();
+        assert_eq!(deleted, vec![url.id.clone()]);
+        assert!(!store.exists("gone12").await.unwrap());
+        assert_eq!(store.count_by_url(&url.id).await.unwrap(), 0);
+    }
+    
+    #[tokio::test]
+    async fn test_unique_constraints() {
+        let store = MemoryStore::new();
+        
+        UrlStore::create(&store, &create_url("https://example.com", "dup123", None)).await.unwrap();
+        let duplicate = UrlStore::create(&store, &create_url("https://example.org", "dup123", None)).await;
+        assert!(matches!(duplicate, Err(AppError::Conflict(_))));
+        
+        let user = CreateUser::new("dup@example.com", "password123", None).unwrap();
+        UserStore::create(&store, &user).await.unwrap();
+        let again = CreateUser::new("dup@example.com", "password123", None).unwrap();
+        assert!(matches!(UserStore::create(&store, &again).await, Err(AppError::Conflict(_))));
+    }
+    
+    #[tokio::test]
+    async fn test_rotate_only_once() {
+        let store = MemoryStore::new();
+        let ttl = chrono::Duration::days(1);
+        
+        let (first, _) = CreateRefreshToken::generate("u1", None, ttl);
+        RefreshTokenStore::create(&store, &first).await.unwrap();
+        
+        let (second, _) = CreateRefreshToken::generate("u1", Some(first.family_id.clone()), ttl);
+        assert!(store.rotate(&first.id, &second).await.unwrap());
+        
+        // توکن rotate شده دوباره rotate نمیشه
+        let (third, _) = CreateRefreshToken::generate("u1", Some(first.family_id.clone()), ttl);
+        assert!(!store.rotate(&first.id, &third).await.unwrap());
+        
+        let stored = store.find_by_hash(&first.token_hash).await.unwrap().unwrap();
+        assert_eq!(stored.replaced_by.as_deref(), Some(second.id.as_str()));
+        assert!(store.find_by_hash(&third.token_hash).await.unwrap().is_none());
+        
+        assert_eq!(store.revoke_family(&first.family_id).await.unwrap(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_api_key_follows_owner_and_revocation() {
+        let store = MemoryStore::new();
+        let user = UserStore::create(&store, &CreateUser::new("key@example.com", "password123", None).unwrap())
+            .await
+            .unwrap();
+        
+        let (key, _) = CreateApiKey::generate(&user.id, "ci", &[crate::models::ApiScope::UrlsRead]);
+        ApiKeyStore::create(&store, &key).await.unwrap();
+        assert!(store.find_active_by_hash(&key.key_hash).await.unwrap().is_some());
+        
+        // کلید کاربر غیرفعال پیدا نمیشه (مثل JOIN نسخه SQL)
+        let mut inactive = user.clone();
+        inactive.is_active = false;
+        UserStore::update(&store, &inactive).await.unwrap();
+        assert!(store.find_active_by_hash(&key.key_hash).await.unwrap().is_none());
+        UserStore::update(&store, &user).await.unwrap();
+        
+        // فقط صاحب کلید میتونه revoke کنه
+        assert!(!store.revoke(&key.id, "someone-else").await.unwrap());
+        assert!(store.revoke(&key.id, &user.id).await.unwrap());
+        assert!(store.find_active_by_hash(&key.key_hash).await.unwrap().is_none());
+        assert!(store.find_active_by_user(&user.id).await.unwrap().is_empty());
+    }
+}
+
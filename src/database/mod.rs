@@ -14,16 +14,52 @@
 //! - Repository Pattern: جداسازی لایه داده از منطق
 //! - Connection Pool: مدیریت اتصالات دیتابیس
 
+mod dialect;
+mod memory;
 mod repository;
+mod store;
 
+pub use memory::*;
 pub use repository::*;
+pub use store::*;
 
+use std::collections::HashSet;
+use std::future::Future;
 use std::sync::Arc;
-use sqlx::{sqlite::{SqlitePool, SqlitePoolOptions}, migrate::Migrator};
-use crate::error::Result;
+use std::time::Duration;
+use sqlx::{pool::PoolOptions, migrate::Migrator};
+use crate::{
+    error::{AppError, Result},
+    models::PoolStatus,
+};
 
-// مسیر migration‌ها
-static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+// =====================================
+// Backend Selection
+// =====================================
+// backend با cargo feature انتخاب میشه: `sqlite` (پیش‌فرض) یا `postgres`
+// اگه هر دو فعال باشن postgres برنده‌ست تا `--features postgres` بدون
+// `--no-default-features` هم کار کنه
+
+#[cfg(not(any(feature = "sqlite", feature = "postgres")))]
+compile_error!("enable a database backend feature: `sqlite` (default) or `postgres`");
+
+/// درایور دیتابیس فعال
+#[cfg(feature = "postgres")]
+pub type Db = sqlx::Postgres;
+
+/// درایور دیتابیس فعال
+#[cfg(all(feature = "sqlite", not(feature = "postgres")))]
+pub type Db = sqlx::Sqlite;
+
+/// Connection pool برای backend فعال
+pub type DbPool = sqlx::Pool<Db>;
+
+// مسیر migration‌ها - هر backend مجموعه جدای خودش رو داره
+#[cfg(feature = "postgres")]
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations/postgres");
+
+#[cfg(not(feature = "postgres"))]
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
 
 // =====================================
 // Database Connection
@@ -32,7 +68,7 @@ static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
 ///
 /// # مفاهیم:
 /// - `#[derive(Clone)]`: Clone implementation
-/// - `Arc<SqlitePool>`: Reference counting برای thread-safe sharing
+/// - `Arc<DbPool>`: Reference counting برای thread-safe sharing
 ///
 /// ## چرا Arc؟
 /// - `Arc` (Atomic Reference Counted) اجازه میده یک داده رو بین چند thread share کنیم
@@ -42,7 +78,11 @@ static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
 pub struct Database {
     /// Connection pool
     /// Arc برای share کردن بین handlers مختلف
-    pool: Arc<SqlitePool>,
+    pool: Arc<DbPool>,
+    
+    /// schema تست؛ فقط نگه داشته میشه تا با drop شدن آخرین clone پاک بشه
+    #[cfg(feature = "postgres")]
+    _schema: Option<Arc<EphemeralSchema>>,
 }
 
 impl Database {
@@ -54,7 +94,7 @@ impl Database {
     /// - `.await`: صبر کردن برای نتیجه async
     ///
     /// # Arguments
-    /// * `database_url` - آدرس دیتابیس (مثلا `sqlite://data/urls.db`)
+    /// * `database_url` - آدرس دیتابیس (مثلا `sqlite://data/urls.db` یا `postgres://...`)
     ///
     /// # Errors
     /// خطا برمیگردونه اگه اتصال موفق نباشه
@@ -76,7 +116,7 @@ impl Database {
         
         // ساخت connection pool
         // Builder pattern برای تنظیمات
-        let pool = SqlitePoolOptions::new()
+        let pool = PoolOptions::<Db>::new()
             .max_connections(10)           // حداکثر 10 اتصال همزمان
             .min_connections(1)            // حداقل 1 اتصال
             .acquire_timeout(std::time::Duration::from_secs(5))
@@ -86,6 +126,8 @@ impl Database {
         
         Ok(Self {
             pool: Arc::new(pool),
+            #[cfg(feature = "postgres")]
+            _schema: None,
         })
     }
     
@@ -93,7 +135,7 @@ impl Database {
     ///
     /// Migration‌ها تغییرات schema دیتابیس رو مدیریت میکنن
     pub async fn migrate(&self) -> Result<()> {
-        MIGRATOR.run(&*self.pool).await?;
+        MIGRATOR.run(&*self.pool).await.map_err(sqlx::Error::from)?;
         Ok(())
     }
     
@@ -101,19 +143,92 @@ impl Database {
     ///
     /// # مفاهیم:
     /// - `&self`: borrowing - فقط reference میگیره
-    /// - `&SqlitePool`: reference به pool برمیگردونه
+    /// - `&DbPool`: reference به pool برمیگردونه
     #[must_use]
-    pub fn pool(&self) -> &SqlitePool {
+    pub fn pool(&self) -> &DbPool {
         &self.pool
     }
     
     /// بررسی سلامت دیتابیس
+    ///
+    /// catalog دیتابیس خونده میشه (نه فقط `SELECT 1`) تا اگه فایل دیتابیس
+    /// قفل یا خراب باشه، خطا برگرده
     pub async fn health_check(&self) -> Result<()> {
-        sqlx::query("SELECT 1")
+        sqlx::query(dialect::HEALTH_CHECK)
             .execute(&*self.pool)
             .await?;
         Ok(())
     }
+    
+    /// بررسی سلامت دیتابیس با محدودیت زمانی
+    ///
+    /// # مفاهیم:
+    /// - `tokio::time::timeout`: اگه future در زمان مشخص تموم نشه، لغو میشه
+    /// - قفل بودن فایل باعث انتظار برای busy timeout میشه؛ این جلوش رو میگیره
+    ///
+    /// # Errors
+    /// `Server` اگه timeout بشه، یا خطای دیتابیس
+    pub async fn ping(&self, timeout: Duration) -> Result<()> {
+        within(timeout, self.health_check()).await
+    }
+    
+    /// تعداد migration‌هایی که هنوز اجرا نشدن
+    ///
+    /// # مفاهیم:
+    /// - فقط خوندنی: جدول `_sqlx_migrations` ساخته نمیشه و نبودنش یعنی همه pending
+    /// - مثل `ping` با محدودیت زمانی، تا readiness پشت یه قفل نمونه
+    ///
+    /// # Errors
+    /// `Server` اگه timeout بشه، یا خطای دیتابیس
+    pub async fn pending_migrations(&self, timeout: Duration) -> Result<usize> {
+        let applied = within(timeout, self.applied_migrations()).await?;
+        
+        Ok(MIGRATOR
+            .iter()
+            .filter(|m| !m.migration_type.is_down_migration())
+            .filter(|m| !applied.contains(&m.version))
+            .count())
+    }
+    
+    /// نسخه migration‌های اجرا شده
+    async fn applied_migrations(&self) -> Result<HashSet<i64>> {
+        let exists: bool = sqlx::query_scalar(dialect::MIGRATIONS_TABLE_EXISTS)
+            .fetch_one(&*self.pool)
+            .await?;
+        if !exists {
+            return Ok(HashSet::new());
+        }
+        
+        let versions: Vec<i64> = sqlx::query_scalar("SELECT version FROM _sqlx_migrations")
+            .fetch_all(&*self.pool)
+            .await?;
+        
+        Ok(versions.into_iter().collect())
+    }
+    
+    /// وضعیت connection pool
+    #[must_use]
+    pub fn pool_status(&self) -> PoolStatus {
+        PoolStatus {
+            size: self.pool.size(),
+            idle: self.pool.num_idle() as u32,
+            max_connections: self.pool.options().get_max_connections(),
+        }
+    }
+    
+    /// بستن تمیز pool
+    ///
+    /// منتظر میمونه تا اتصال‌های در حال استفاده برگردن و بعد همه رو میبنده.
+    /// برای SQLite یعنی WAL checkpoint و آزاد شدن lock فایل قبل از خروج.
+    pub async fn close(&self) {
+        self.pool.close().await;
+    }
+    
+    /// آیا pool بسته شده؟
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.pool.is_closed()
+    }
 }
 
 // =====================================
@@ -138,7 +253,7 @@ impl Database {
     /// // عملیات‌ها
     /// tx.commit().await?;
     /// ```
-    pub async fn begin(&self) -> Result<sqlx::Transaction<'_, sqlx::Sqlite>> {
+    pub async fn begin(&self) -> Result<sqlx::Transaction<'_, Db>> {
         Ok(self.pool.begin().await?)
     }
     
@@ -150,19 +265,23 @@ impl Database {
     /// - `async move`: منتقل کردن ownership به closure
     /// - `Fn` trait: نوع closure
     ///
+    /// تراکنش به closure منتقل میشه (`'static` چون از pool گرفته شده)،
+    /// پس closure باید خودش `commit` کنه؛ در غیر این صورت با drop شدن rollback میشه.
+    ///
     /// # مثال
     /// ```rust,ignore
-    /// db.transaction(|tx| async move {
-    ///     // عملیات‌ها
+    /// db.transaction(|mut tx| async move {
+    ///     // عملیات‌ها با `&mut *tx`
+    ///     tx.commit().await?;
     ///     Ok(())
     /// }).await?;
     /// ```
     pub async fn transaction<F, T, Fut>(&self, f: F) -> Result<T>
     where
-        F: FnOnce(sqlx::Transaction<'_, sqlx::Sqlite>) -> Fut,
+        F: FnOnce(sqlx::Transaction<'static, Db>) -> Fut,
         Fut: std::future::Future<Output = Result<T>>,
     {
-        let tx = self.begin().await?;
+        let tx = self.pool.begin().await?;
         f(tx).await
     }
 }
@@ -170,22 +289,180 @@ impl Database {
 // =====================================
 // Test Utilities
 // =====================================
-#[cfg(test)]
+/// دیتابیس موقت و ایزوله برای تست‌ها
+///
+/// # Backend‌ها
+/// - SQLite: دیتابیس `:memory:` که با بسته شدن pool از بین میره
+/// - PostgreSQL: یه schema تازه (`test_<uuid>`) داخل `TEST_DATABASE_URL` که با
+///   drop شدن آخرین clone پاک میشه
+///
+/// pool فقط یک اتصال داره تا رفتار هر دو backend یکسان باشه.
 impl Database {
-    /// ساخت دیتابیس in-memory برای تست
-    pub async fn in_memory() -> Result<Self> {
-        // :memory: یه دیتابیس موقت در RAM میسازه
-        let pool = SqlitePoolOptions::new()
+    /// ساخت دیتابیس موقت با همه migration‌ها
+    ///
+    /// # Errors
+    /// خطا اگه اتصال یا migration موفق نباشه (یا با postgres، `TEST_DATABASE_URL` تنظیم نشده باشه)
+    pub async fn ephemeral() -> Result<Self> {
+        let db = Self::ephemeral_unmigrated().await?;
+        db.migrate().await?;
+        Ok(db)
+    }
+    
+    /// دیتابیس موقت خالی، بدون اجرای migration‌ها
+    #[cfg(not(feature = "postgres"))]
+    async fn ephemeral_unmigrated() -> Result<Self> {
+        let pool = PoolOptions::<Db>::new()
             .max_connections(1)
             .connect(":memory:")
             .await?;
         
-        let db = Self {
+        Ok(Self { pool: Arc::new(pool) })
+    }
+    
+    /// دیتابیس موقت خالی، بدون اجرای migration‌ها
+    #[cfg(feature = "postgres")]
+    async fn ephemeral_unmigrated() -> Result<Self> {
+        use std::str::FromStr;
+        use sqlx::postgres::PgConnectOptions;
+        
+        let url = std::env::var("TEST_DATABASE_URL").map_err(|_| AppError::Config(
+            "TEST_DATABASE_URL must point to a PostgreSQL database to run tests".to_string()
+        ))?;
+        let schema = format!("test_{}", uuid::Uuid::new_v4().simple());
+        
+        // ساخت schema با یه اتصال موقت
+        let setup = PoolOptions::<Db>::new().max_connections(1).connect(&url).await?;
+        sqlx::query(&format!("CREATE SCHEMA {schema}")).execute(&setup).await?;
+        setup.close().await;
+        
+        // همه اتصال‌های pool فقط این schema رو میبینن
+        let options = PgConnectOptions::from_str(&url)?
+            .options([("search_path", schema.as_str())]);
+        let pool = PoolOptions::<Db>::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await?;
+        
+        Ok(Self {
             pool: Arc::new(pool),
+            _schema: Some(Arc::new(EphemeralSchema { url, name: schema })),
+        })
+    }
+}
+
+/// schema موقت یک تست postgres
+#[cfg(feature = "postgres")]
+#[derive(Debug)]
+struct EphemeralSchema {
+    url: String,
+    name: String,
+}
+
+#[cfg(feature = "postgres")]
+impl Drop for EphemeralSchema {
+    /// پاک کردن schema با همه جدول‌هاش
+    ///
+    /// `Drop` نمیتونه await کنه و runtime تست ممکنه در حال بسته شدن باشه،
+    /// پس پاکسازی روی یه thread جدا با runtime خودش اجرا میشه
+    fn drop(&mut self) {
+        let url = self.url.clone();
+        let statement = format!("DROP SCHEMA IF EXISTS {} CASCADE", self.name);
+        
+        let cleanup = std::thread::spawn(move || -> Result<()> {
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+            runtime.block_on(async {
+                let setup = PoolOptions::<Db>::new().max_connections(1).connect(&url).await?;
+                sqlx::query(&statement).execute(&setup).await?;
+                setup.close().await;
+                Ok(())
+            })
+        });
+        
+        if let Ok(Err(e)) = cleanup.join() {
+            tracing::warn!(schema = %self.name, error = %e, "Failed to drop test schema");
+        }
+    }
+}
+
+/// اجرای یک عملیات دیتابیس با محدودیت زمانی
+async fn within<T>(timeout: Duration, operation: impl Future<Output = Result<T>>) -> Result<T> {
+    tokio::time::timeout(timeout, operation)
+        .await
+        .map_err(|_| AppError::Server(format!(
+            "Database did not respond within {}ms",
+            timeout.as_millis()
+        )))?
+}
+
+// =====================================
+// Tests
+// =====================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn test_readiness_checks() {
+        let db = Database::ephemeral().await.unwrap();
+        
+        assert!(db.ping(Duration::from_secs(1)).await.is_ok());
+        assert_eq!(db.pending_migrations(Duration::from_secs(1)).await.unwrap(), 0);
+        
+        let pool = db.pool_status();
+        assert_eq!(pool.max_connections, 1);
+        assert!(pool.size <= pool.max_connections);
+    }
+    
+    #[tokio::test]
+    async fn test_pending_migrations_before_migrate() {
+        let db = Database::ephemeral_unmigrated().await.unwrap();
+        
+        assert_eq!(db.pending_migrations(Duration::from_secs(1)).await.unwrap(), MIGRATOR.iter().count());
+        
+        // بررسی read-only هست و جدول migration رو نمیسازه
+        let created: bool = sqlx::query_scalar(dialect::MIGRATIONS_TABLE_EXISTS)
+            .fetch_one(&*db.pool)
+            .await
+            .unwrap();
+        assert!(!created);
+        
+        db.migrate().await.unwrap();
+        assert_eq!(db.pending_migrations(Duration::from_secs(1)).await.unwrap(), 0);
+    }
+    
+    #[tokio::test]
+    async fn test_close_shuts_down_pool() {
+        let db = Database::ephemeral().await.unwrap();
+        let clone = db.clone();
+        
+        db.close().await;
+        
+        // clone‌ها همون pool رو share میکنن
+        assert!(clone.is_closed());
+        assert!(clone.health_check().await.is_err());
+    }
+    
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn test_ephemeral_schema_dropped_with_last_clone() {
+        let db = Database::ephemeral().await.unwrap();
+        let name = db._schema.as_ref().unwrap().name.clone();
+        let check = PoolOptions::<Db>::new()
+            .max_connections(1)
+            .connect(&std::env::var("TEST_DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let exists = || {
+            sqlx::query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM pg_namespace WHERE nspname = $1)")
+                .bind(name.clone())
+                .fetch_one(&check)
         };
         
-        db.migrate().await?;
-        Ok(db)
+        drop(db.clone());
+        assert!(exists().await.unwrap());
+        
+        drop(db);
+        assert!(!exists().await.unwrap());
     }
 }
 
@@ -59,9 +59,10 @@ pub trait Repository: Send + Sync {
 // =====================================
 // URL Repository
 // =====================================
-use super::Database;
-use crate::models::{Url, CreateUrl};
+use super::{dialect, Database, Db};
+use crate::models::{Url, CreateUrl, DailyCount, Pagination, UrlFilter, UrlStatusFilter};
 use chrono::Utc;
+use sqlx::QueryBuilder;
 
 /// Repository برای مدیریت URL‌ها
 ///
@@ -89,9 +90,9 @@ impl UrlRepository {
         let url = sqlx::query_as::<_, Url>(
             r#"
             SELECT id, short_code, original_url, title, clicks, 
-                   user_id, expires_at, created_at, updated_at
+                   user_id, starts_at, expires_at, redirect_type, password_hash, max_clicks, created_at, updated_at
             FROM urls 
-            WHERE short_code = ?
+            WHERE short_code = $1
             "#
         )
         .bind(short_code)
@@ -107,8 +108,8 @@ impl UrlRepository {
         
         sqlx::query(
             r#"
-            INSERT INTO urls (id, short_code, original_url, title, user_id, expires_at, created_at, updated_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO urls (id, short_code, original_url, title, user_id, starts_at, expires_at, redirect_type, password_hash, max_clicks, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
             "#
         )
         .bind(&create_url.id)
@@ -116,7 +117,11 @@ impl UrlRepository {
         .bind(&create_url.original_url)
         .bind(&create_url.title)
         .bind(&create_url.user_id)
-        .bind(&create_url.expires_at)
+        .bind(create_url.starts_at)
+        .bind(create_url.expires_at)
+        .bind(create_url.redirect_type)
+        .bind(&create_url.password_hash)
+        .bind(create_url.max_clicks)
         .bind(now)
         .bind(now)
         .execute(self.db.pool())
@@ -131,16 +136,19 @@ impl UrlRepository {
     /// افزایش شمارنده کلیک
     ///
     /// # مفاهیم:
-    /// - SQL UPDATE
-    /// - Returning the updated value
-    pub async fn increment_clicks(&self, short_code: &str) -> Result<Option<Url>> {
+    /// - SQL UPDATE بدون SELECT دوباره (مسیر redirect به ردیف جدید نیازی نداره)
+    /// - چک سقف کلیک داخل همون UPDATE: درخواست‌های همزمان نمیتونن از `max_clicks` رد بشن
+    ///
+    /// # Returns
+    /// `false` اگه کد وجود نداشته باشه یا سقف کلیک پر شده باشه
+    pub async fn increment_clicks(&self, short_code: &str) -> Result<bool> {
         let now = Utc::now();
         
-        sqlx::query(
+        let result = sqlx::query(
             r#"
             UPDATE urls 
-            SET clicks = clicks + 1, updated_at = ?
-            WHERE short_code = ?
+            SET clicks = clicks + 1, updated_at = $1
+            WHERE short_code = $2 AND (max_clicks IS NULL OR clicks < max_clicks)
             "#
         )
         .bind(now)
@@ -148,7 +156,38 @@ impl UrlRepository {
         .execute(self.db.pool())
         .await?;
         
-        self.find_by_short_code(short_code).await
+        Ok(result.rows_affected() > 0)
+    }
+    
+    /// بروزرسانی URL
+    ///
+    /// مقصد، عنوان، تاریخ انقضا، نوع redirect و رمز رو ذخیره میکنه؛ شمارنده کلیک دست نمیخوره
+    pub async fn update(&self, url: &Url) -> Result<Url> {
+        let now = Utc::now();
+        
+        sqlx::query(
+            r#"
+            UPDATE urls
+            SET original_url = $1, title = $2, expires_at = $3, redirect_type = $4,
+                password_hash = $5, max_clicks = $6, starts_at = $7, updated_at = $8
+            WHERE id = $9
+            "#
+        )
+        .bind(&url.original_url)
+        .bind(&url.title)
+        .bind(url.expires_at)
+        .bind(url.redirect_type)
+        .bind(&url.password_hash)
+        .bind(url.max_clicks)
+        .bind(url.starts_at)
+        .bind(now)
+        .bind(&url.id)
+        .execute(self.db.pool())
+        .await?;
+        
+        self.find_by_id(&url.id)
+            .await?
+            .ok_or_else(|| crate::error::AppError::url_not_found(&url.short_code))
     }
     
     /// پیدا کردن URL‌های یک کاربر
@@ -156,9 +195,9 @@ impl UrlRepository {
         let urls = sqlx::query_as::<_, Url>(
             r#"
             SELECT id, short_code, original_url, title, clicks,
-                   user_id, expires_at, created_at, updated_at
+                   user_id, starts_at, expires_at, redirect_type, password_hash, max_clicks, created_at, updated_at
             FROM urls 
-            WHERE user_id = ?
+            WHERE user_id = $1
             ORDER BY created_at DESC
             "#
         )
@@ -169,10 +208,105 @@ impl UrlRepository {
         Ok(urls)
     }
     
+    /// حذف چند URL متعلق به یک کاربر در یک تراکنش
+    ///
+    /// # مفاهیم:
+    /// - `Database::transaction`: همه حذف‌ها با هم commit میشن
+    /// - شرط `user_id` در خود query مالکیت رو تضمین میکنه
+    ///
+    /// # Returns
+    /// شناسه‌هایی که واقعا حذف شدن
+    pub async fn delete_many_owned(&self, ids: &[String], user_id: &str) -> Result<Vec<String>> {
+        let ids = ids.to_vec();
+        let user_id = user_id.to_string();
+        
+        self.db.transaction(|mut tx| async move {
+            let mut deleted = Vec::with_capacity(ids.len());
+            
+            for id in ids {
+                let result = sqlx::query("DELETE FROM urls WHERE id = $1 AND user_id = $2")
+                    .bind(&id)
+                    .bind(&user_id)
+                    .execute(&mut *tx)
+                    .await?;
+                
+                if result.rows_affected() > 0 {
+                    deleted.push(id);
+                }
+            }
+            
+            tx.commit().await?;
+            Ok(deleted)
+        }).await
+    }
+    
+    /// جستجو، فیلتر و صفحه‌بندی URL‌های یک کاربر
+    pub async fn search_by_user(
+        &self,
+        user_id: &str,
+        filter: &UrlFilter,
+        pagination: &Pagination,
+    ) -> Result<(Vec<Url>, u64)> {
+        self.search(Some(user_id), filter, pagination).await
+    }
+    
+    /// جستجو، فیلتر و صفحه‌بندی URL‌ها
+    ///
+    /// # مفاهیم:
+    /// - `QueryBuilder`: ساخت query پویا با bind امن مقادیر
+    /// - دو query: یکی برای تعداد کل، یکی برای صفحه جاری
+    ///
+    /// # Arguments
+    /// * `owner` - فقط URL‌های این کاربر؛ `None` یعنی همه URL‌ها (پنل ادمین)
+    ///
+    /// # Returns
+    /// URL‌های صفحه جاری و تعداد کل نتایج
+    pub async fn search(
+        &self,
+        owner: Option<&str>,
+        filter: &UrlFilter,
+        pagination: &Pagination,
+    ) -> Result<(Vec<Url>, u64)> {
+        let now = Utc::now();
+        
+        let mut count_query = QueryBuilder::<Db>::new("SELECT COUNT(*) FROM urls");
+        push_url_filter(&mut count_query, owner, filter, now);
+        let total = count_query
+            .build_query_scalar::<i64>()
+            .fetch_one(self.db.pool())
+            .await?;
+        
+        let mut query = QueryBuilder::<Db>::new(
+            r#"
+            SELECT id, short_code, original_url, title, clicks,
+                   user_id, starts_at, expires_at, redirect_type, password_hash, max_clicks, created_at, updated_at
+            FROM urls
+            "#
+        );
+        push_url_filter(&mut query, owner, filter, now);
+        query
+            .push(format_args!(
+                " ORDER BY {} {}, id ASC",
+                filter.sort_by.as_sql(),
+                filter.order.as_sql(),
+            ))
+            .push(" LIMIT ")
+            .push_bind(i64::from(pagination.limit()))
+            .push(" OFFSET ")
+            .push_bind(i64::from(pagination.offset()));
+        
+        let urls = query
+            .build_query_as::<Url>()
+            .fetch_all(self.db.pool())
+            .await?;
+        
+        Ok((urls, total as u64))
+    }
+    
     /// چک کردن وجود short_code
     pub async fn exists(&self, short_code: &str) -> Result<bool> {
-        let result = sqlx::query_scalar::<_, i32>(
-            "SELECT COUNT(*) FROM urls WHERE short_code = ?"
+        let result = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM urls WHERE short_code = $1"
         )
         .bind(short_code)
         .fetch_one(self.db.pool())
@@ -186,7 +320,7 @@ impl UrlRepository {
         let now = Utc::now();
         
         let result = sqlx::query(
-            "DELETE FROM urls WHERE expires_at IS NOT NULL AND expires_at < ?"
+            "DELETE FROM urls WHERE expires_at IS NOT NULL AND expires_at < $1"
         )
         .bind(now)
         .execute(self.db.pool())
@@ -195,14 +329,75 @@ impl UrlRepository {
         Ok(result.rows_affected())
     }
     
+    /// تعداد لینک‌های فعال و منقضی شده
+    ///
+    /// # Returns
+    /// `(active, expired)`
+    pub async fn count_by_status(&self) -> Result<(i64, i64)> {
+        let counts = sqlx::query_as::<_, (i64, i64)>(
+            r#"
+            SELECT
+                CAST(COALESCE(SUM(CASE WHEN expires_at IS NULL OR expires_at > $1 THEN 1 ELSE 0 END), 0) AS BIGINT),
+                CAST(COALESCE(SUM(CASE WHEN expires_at IS NOT NULL AND expires_at <= $1 THEN 1 ELSE 0 END), 0) AS BIGINT)
+            FROM urls
+            "#
+        )
+        .bind(Utc::now())
+        .fetch_one(self.db.pool())
+        .await?;
+        
+        Ok(counts)
+    }
+    
+    /// تعداد لینک‌های ساخته شده در هر روز از `since` به بعد
+    ///
+    /// فقط روزهایی که لینک دارن برمیگردن
+    pub async fn count_created_per_day(&self, since: chrono::DateTime<Utc>) -> Result<Vec<DailyCount>> {
+        let query = format!(
+            r#"
+            SELECT {day} AS date, COUNT(*) AS count
+            FROM urls
+            WHERE created_at >= $1
+            GROUP BY 1
+            ORDER BY 1 ASC
+            "#,
+            day = dialect::day("created_at"),
+        );
+        
+        let days = sqlx::query_as::<_, DailyCount>(&query)
+            .bind(since)
+            .fetch_all(self.db.pool())
+            .await?;
+        
+        Ok(days)
+    }
+    
+    /// پرکلیک‌ترین لینک‌ها
+    pub async fn find_top_by_clicks(&self, limit: u32) -> Result<Vec<Url>> {
+        let urls = sqlx::query_as::<_, Url>(
+            r#"
+            SELECT id, short_code, original_url, title, clicks,
+                   user_id, starts_at, expires_at, redirect_type, password_hash, max_clicks, created_at, updated_at
+            FROM urls
+            ORDER BY clicks DESC, created_at ASC
+            LIMIT $1
+            "#
+        )
+        .bind(i64::from(limit))
+        .fetch_all(self.db.pool())
+        .await?;
+        
+        Ok(urls)
+    }
+    
     /// آمار کلی
     pub async fn get_stats(&self) -> Result<UrlStats> {
         let stats = sqlx::query_as::<_, UrlStats>(
             r#"
             SELECT 
                 COUNT(*) as total_urls,
-                COALESCE(SUM(clicks), 0) as total_clicks,
-                COALESCE(AVG(clicks), 0) as avg_clicks
+                CAST(COALESCE(SUM(clicks), 0) AS BIGINT) as total_clicks,
+                CAST(COALESCE(AVG(clicks), 0) AS DOUBLE PRECISION) as avg_clicks
             FROM urls
             "#
         )
@@ -213,6 +408,52 @@ impl UrlRepository {
     }
 }
 
+/// اضافه کردن شرط‌های WHERE فیلتر URL به query
+fn push_url_filter(
+    builder: &mut QueryBuilder<'_, Db>,
+    owner: Option<&str>,
+    filter: &UrlFilter,
+    now: chrono::DateTime<Utc>,
+) {
+    match owner {
+        Some(user_id) => builder.push(" WHERE user_id = ").push_bind(user_id.to_string()),
+        None => builder.push(" WHERE 1 = 1"),
+    };
+    
+    if let Some(query) = &filter.query {
+        let pattern = like_pattern(query);
+        
+        builder
+            .push(format_args!(" AND (title {} ", dialect::LIKE))
+            .push_bind(pattern.clone())
+            .push(format_args!(" ESCAPE '\\' OR original_url {} ", dialect::LIKE))
+            .push_bind(pattern)
+            .push(" ESCAPE '\\')");
+    }
+    
+    match filter.status {
+        Some(UrlStatusFilter::Active) => {
+            builder.push(" AND (expires_at IS NULL OR expires_at > ").push_bind(now).push(")");
+        }
+        Some(UrlStatusFilter::Expired) => {
+            builder.push(" AND expires_at IS NOT NULL AND expires_at <= ").push_bind(now);
+        }
+        None => {}
+    }
+}
+
+/// الگوی LIKE برای جستجوی «شامل بودن»
+///
+/// wildcard‌های LIKE escape میشن تا متن کاربر literal جستجو بشه
+/// (query باید `ESCAPE '\'` داشته باشه)
+fn like_pattern(query: &str) -> String {
+    let escaped = query
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
 /// آمار URL‌ها
 #[derive(Debug, Clone, FromRow, serde::Serialize)]
 pub struct UrlStats {
@@ -231,9 +472,9 @@ impl Repository for UrlRepository {
         let url = sqlx::query_as::<_, Url>(
             r#"
             SELECT id, short_code, original_url, title, clicks,
-                   user_id, expires_at, created_at, updated_at
+                   user_id, starts_at, expires_at, redirect_type, password_hash, max_clicks, created_at, updated_at
             FROM urls 
-            WHERE id = ?
+            WHERE id = $1
             "#
         )
         .bind(id)
@@ -247,7 +488,7 @@ impl Repository for UrlRepository {
         let urls = sqlx::query_as::<_, Url>(
             r#"
             SELECT id, short_code, original_url, title, clicks,
-                   user_id, expires_at, created_at, updated_at
+                   user_id, starts_at, expires_at, redirect_type, password_hash, max_clicks, created_at, updated_at
             FROM urls 
             ORDER BY created_at DESC
             "#
@@ -265,13 +506,17 @@ impl Repository for UrlRepository {
             original_url: entity.original_url.clone(),
             title: entity.title.clone(),
             user_id: entity.user_id.clone(),
+            starts_at: entity.starts_at,
             expires_at: entity.expires_at,
+            redirect_type: entity.redirect_type,
+            password_hash: entity.password_hash.clone(),
+            max_clicks: entity.max_clicks,
         };
         self.create(&create_url).await
     }
     
     async fn delete(&self, id: &String) -> Result<bool> {
-        let result = sqlx::query("DELETE FROM urls WHERE id = ?")
+        let result = sqlx::query("DELETE FROM urls WHERE id = $1")
             .bind(id)
             .execute(self.db.pool())
             .await?;
@@ -288,10 +533,202 @@ impl Repository for UrlRepository {
     }
 }
 
+// =====================================
+// Click Event Repository
+// =====================================
+use crate::models::{
+    AnalyticsInterval, ClickBatch, ClickBucket, ClickDimension, CreateClickEvent, TopValue,
+};
+use chrono::DateTime;
+
+/// Repository برای رویدادهای کلیک (جدول `click_events`)
+#[derive(Debug, Clone)]
+pub struct ClickRepository {
+    db: Database,
+}
+
+impl ClickRepository {
+    #[must_use]
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+    
+    /// ثبت یک کلیک
+    pub async fn create(&self, event: &CreateClickEvent) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO click_events (id, url_id, ip_address, user_agent, referer, country, clicked_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#
+        )
+        .bind(&event.id)
+        .bind(&event.url_id)
+        .bind(&event.ip_address)
+        .bind(&event.user_agent)
+        .bind(&event.referer)
+        .bind(&event.country)
+        .bind(event.clicked_at)
+        .execute(self.db.pool())
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// نوشتن یک batch از کلیک‌ها در یک تراکنش
+    ///
+    /// # مفاهیم:
+    /// - هر URL فقط یک `UPDATE` با مجموع کلیک‌هاش میگیره
+    /// - رویداد URL‌هایی که در این فاصله حذف شدن بی‌صدا رد میشن
+    ///   (`WHERE EXISTS`)، تا یک لینک حذف شده کل batch رو fail نکنه
+    ///
+    /// # Returns
+    /// تعداد رویدادهای ثبت شده
+    pub async fn record_batch(&self, batch: &ClickBatch) -> Result<u64> {
+        let batch = batch.clone();
+        
+        self.db.transaction(|mut tx| async move {
+            let now = Utc::now();
+            
+            for (url_id, count) in &batch.increments {
+                sqlx::query(
+                    "UPDATE urls SET clicks = clicks + $1, updated_at = $2 WHERE id = $3"
+                )
+                .bind(i64::try_from(*count).unwrap_or(i64::MAX))
+                .bind(now)
+                .bind(url_id)
+                .execute(&mut *tx)
+                .await?;
+            }
+            
+            let mut recorded = 0;
+            for event in &batch.events {
+                let result = sqlx::query(
+                    r#"
+                    INSERT INTO click_events (id, url_id, ip_address, user_agent, referer, country, clicked_at)
+                    SELECT $1, $2, $3, $4, $5, $6, $7
+                    WHERE EXISTS (SELECT 1 FROM urls WHERE id = $2)
+                    "#
+                )
+                .bind(&event.id)
+                .bind(&event.url_id)
+                .bind(&event.ip_address)
+                .bind(&event.user_agent)
+                .bind(&event.referer)
+                .bind(&event.country)
+                .bind(event.clicked_at)
+                .execute(&mut *tx)
+                .await?;
+                
+                recorded += result.rows_affected();
+            }
+            
+            tx.commit().await?;
+            Ok(recorded)
+        }).await
+    }
+    
+    /// تعداد کلیک‌های ثبت شده برای یک URL
+    pub async fn count_by_url(&self, url_id: &str) -> Result<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM click_events WHERE url_id = $1"
+        )
+        .bind(url_id)
+        .fetch_one(self.db.pool())
+        .await?;
+        
+        Ok(count)
+    }
+    
+    /// تعداد کلیک‌های یک URL در یک بازه زمانی
+    pub async fn count_in_range(
+        &self,
+        url_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM click_events WHERE url_id = $1 AND clicked_at >= $2 AND clicked_at < $3"
+        )
+        .bind(url_id)
+        .bind(from)
+        .bind(to)
+        .fetch_one(self.db.pool())
+        .await?;
+        
+        Ok(count)
+    }
+    
+    /// سری زمانی کلیک‌ها، گروه‌بندی شده بر اساس interval
+    ///
+    /// # مفاهیم:
+    /// - `format!` فقط با عبارت‌های ثابت از enum استفاده میشه
+    /// - مقادیر کاربر همیشه bind میشن
+    pub async fn timeline(
+        &self,
+        url_id: &str,
+        interval: AnalyticsInterval,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<ClickBucket>> {
+        let query = format!(
+            r#"
+            SELECT {bucket} as bucket, COUNT(*) as clicks
+            FROM click_events
+            WHERE url_id = $1 AND clicked_at >= $2 AND clicked_at < $3
+            GROUP BY 1
+            ORDER BY 1 ASC
+            "#,
+            bucket = dialect::interval_bucket(interval, "clicked_at"),
+        );
+        
+        let buckets = sqlx::query_as::<_, ClickBucket>(&query)
+            .bind(url_id)
+            .bind(from)
+            .bind(to)
+            .fetch_all(self.db.pool())
+            .await?;
+        
+        Ok(buckets)
+    }
+    
+    /// پرتکرارترین مقادیر یک ستون (referer، user agent، country)
+    pub async fn top_values(
+        &self,
+        url_id: &str,
+        dimension: ClickDimension,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<TopValue>> {
+        let query = format!(
+            r#"
+            SELECT {column} as value, COUNT(*) as clicks
+            FROM click_events
+            WHERE url_id = $1 AND clicked_at >= $2 AND clicked_at < $3
+              AND {column} IS NOT NULL AND {column} != ''
+            GROUP BY {column}
+            ORDER BY clicks DESC, value ASC
+            LIMIT $4
+            "#,
+            column = dimension.as_sql(),
+        );
+        
+        let values = sqlx::query_as::<_, TopValue>(&query)
+            .bind(url_id)
+            .bind(from)
+            .bind(to)
+            .bind(i64::from(limit))
+            .fetch_all(self.db.pool())
+            .await?;
+        
+        Ok(values)
+    }
+}
+
 // =====================================
 // User Repository
 // =====================================
-use crate::models::{User, CreateUser};
+use crate::models::{User, CreateUser, UserFilter, hash_password};
 
 /// Repository برای مدیریت کاربران
 #[derive(Debug, Clone)]
@@ -309,9 +746,9 @@ impl UserRepository {
     pub async fn find_by_email(&self, email: &str) -> Result<Option<User>> {
         let user = sqlx::query_as::<_, User>(
             r#"
-            SELECT id, email, password_hash, name, is_active, created_at, updated_at
+            SELECT id, email, password_hash, name, is_active, role, password_changed_at, created_at, updated_at
             FROM users 
-            WHERE email = ?
+            WHERE email = $1
             "#
         )
         .bind(email)
@@ -328,7 +765,7 @@ impl UserRepository {
         sqlx::query(
             r#"
             INSERT INTO users (id, email, password_hash, name, is_active, created_at, updated_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
             "#
         )
         .bind(&create_user.id)
@@ -348,8 +785,8 @@ impl UserRepository {
     
     /// بررسی وجود email
     pub async fn email_exists(&self, email: &str) -> Result<bool> {
-        let count = sqlx::query_scalar::<_, i32>(
-            "SELECT COUNT(*) FROM users WHERE email = ?"
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM users WHERE email = $1"
         )
         .bind(email)
         .fetch_one(self.db.pool())
@@ -357,6 +794,131 @@ impl UserRepository {
         
         Ok(count > 0)
     }
+    
+    /// تعداد کاربران فعال
+    pub async fn count_active(&self) -> Result<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM users WHERE is_active = TRUE"
+        )
+        .fetch_one(self.db.pool())
+        .await?;
+        
+        Ok(count)
+    }
+    
+    /// بروزرسانی کاربر
+    ///
+    /// ایمیل، نام، وضعیت فعال بودن و نقش رو ذخیره میکنه؛
+    /// رمز عبور از `update_password` تغییر میکنه
+    pub async fn update(&self, user: &User) -> Result<User> {
+        let now = Utc::now();
+        
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET email = $1, name = $2, is_active = $3, role = $4, updated_at = $5
+            WHERE id = $6
+            "#
+        )
+        .bind(&user.email)
+        .bind(&user.name)
+        .bind(user.is_active)
+        .bind(user.role)
+        .bind(now)
+        .bind(&user.id)
+        .execute(self.db.pool())
+        .await?;
+        
+        self.find_by_id(&user.id)
+            .await?
+            .ok_or_else(|| crate::error::AppError::NotFound(format!("User '{}' not found", user.id)))
+    }
+    
+    /// جستجو و صفحه‌بندی کاربران (پنل ادمین)
+    ///
+    /// # Returns
+    /// کاربران صفحه جاری و تعداد کل نتایج
+    pub async fn search(
+        &self,
+        filter: &UserFilter,
+        pagination: &Pagination,
+    ) -> Result<(Vec<User>, u64)> {
+        let mut count_query = QueryBuilder::<Db>::new("SELECT COUNT(*) FROM users");
+        push_user_filter(&mut count_query, filter);
+        let total = count_query
+            .build_query_scalar::<i64>()
+            .fetch_one(self.db.pool())
+            .await?;
+        
+        let mut query = QueryBuilder::<Db>::new(
+            r#"
+            SELECT id, email, password_hash, name, is_active, role, password_changed_at, created_at, updated_at
+            FROM users
+            "#
+        );
+        push_user_filter(&mut query, filter);
+        query
+            .push(" ORDER BY created_at DESC, id ASC LIMIT ")
+            .push_bind(i64::from(pagination.limit()))
+            .push(" OFFSET ")
+            .push_bind(i64::from(pagination.offset()));
+        
+        let users = query
+            .build_query_as::<User>()
+            .fetch_all(self.db.pool())
+            .await?;
+        
+        Ok((users, total as u64))
+    }
+    
+    /// تغییر رمز عبور کاربر
+    ///
+    /// رمز جدید با Argon2 هش میشه و `password_changed_at` بروز میشه
+    /// تا توکن‌های صادر شده قبل از این لحظه باطل بشن
+    pub async fn update_password(&self, id: &str, new_password: &str) -> Result<()> {
+        let password_hash = hash_password(new_password)?;
+        let now = Utc::now();
+        
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET password_hash = $1, password_changed_at = $2, updated_at = $3
+            WHERE id = $4
+            "#
+        )
+        .bind(&password_hash)
+        .bind(now)
+        .bind(now)
+        .bind(id)
+        .execute(self.db.pool())
+        .await?;
+        
+        if result.rows_affected() == 0 {
+            return Err(crate::error::AppError::NotFound(format!("User '{}' not found", id)));
+        }
+        
+        Ok(())
+    }
+}
+
+/// اضافه کردن شرط‌های WHERE فیلتر کاربر به query
+fn push_user_filter(builder: &mut QueryBuilder<'_, Db>, filter: &UserFilter) {
+    builder.push(" WHERE 1 = 1");
+    
+    if let Some(query) = &filter.query {
+        let pattern = like_pattern(query);
+        
+        builder
+            .push(format_args!(" AND (email {} ", dialect::LIKE))
+            .push_bind(pattern.clone())
+            .push(format_args!(" ESCAPE '\\' OR name {} ", dialect::LIKE))
+            .push_bind(pattern)
+            .push(" ESCAPE '\\')");
+    }
+    
+    if let Some(is_active) = filter.is_active {
+        builder.push(" AND is_active = ").push_bind(is_active);
+    }
 }
 
 #[async_trait]
@@ -367,9 +929,9 @@ impl Repository for UserRepository {
     async fn find_by_id(&self, id: &String) -> Result<Option<User>> {
         let user = sqlx::query_as::<_, User>(
             r#"
-            SELECT id, email, password_hash, name, is_active, created_at, updated_at
+            SELECT id, email, password_hash, name, is_active, role, password_changed_at, created_at, updated_at
             FROM users 
-            WHERE id = ?
+            WHERE id = $1
             "#
         )
         .bind(id)
@@ -382,7 +944,7 @@ impl Repository for UserRepository {
     async fn find_all(&self) -> Result<Vec<User>> {
         let users = sqlx::query_as::<_, User>(
             r#"
-            SELECT id, email, password_hash, name, is_active, created_at, updated_at
+            SELECT id, email, password_hash, name, is_active, role, password_changed_at, created_at, updated_at
             FROM users 
             ORDER BY created_at DESC
             "#
@@ -404,7 +966,7 @@ impl Repository for UserRepository {
     }
     
     async fn delete(&self, id: &String) -> Result<bool> {
-        let result = sqlx::query("DELETE FROM users WHERE id = ?")
+        let result = sqlx::query("DELETE FROM users WHERE id = $1")
             .bind(id)
             .execute(self.db.pool())
             .await?;
@@ -421,3 +983,255 @@ impl Repository for UserRepository {
     }
 }
 
+// =====================================
+// Refresh Token Repository
+// =====================================
+use crate::models::{CreateRefreshToken, RefreshToken};
+
+/// Repository برای refresh token‌ها (جدول `refresh_tokens`)
+#[derive(Debug, Clone)]
+pub struct RefreshTokenRepository {
+    db: Database,
+}
+
+impl RefreshTokenRepository {
+    #[must_use]
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+    
+    /// ذخیره توکن جدید
+    pub async fn create(&self, token: &CreateRefreshToken) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO refresh_tokens (id, user_id, token_hash, family_id, expires_at, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#
+        )
+        .bind(&token.id)
+        .bind(&token.user_id)
+        .bind(&token.token_hash)
+        .bind(&token.family_id)
+        .bind(token.expires_at)
+        .bind(Utc::now())
+        .execute(self.db.pool())
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// پیدا کردن توکن با هش
+    pub async fn find_by_hash(&self, token_hash: &str) -> Result<Option<RefreshToken>> {
+        let token = sqlx::query_as::<_, RefreshToken>(
+            r#"
+            SELECT id, user_id, token_hash, family_id, expires_at,
+                   revoked_at, replaced_by, created_at
+            FROM refresh_tokens
+            WHERE token_hash = $1
+            "#
+        )
+        .bind(token_hash)
+        .fetch_optional(self.db.pool())
+        .await?;
+        
+        Ok(token)
+    }
+    
+    /// جایگزینی توکن فعلی با توکن جدید (rotation)
+    ///
+    /// # مفاهیم:
+    /// - باطل کردن توکن قبلی و ذخیره توکن جدید در یک تراکنش
+    /// - شرط `revoked_at IS NULL` جلوی استفاده همزمان از یک توکن رو میگیره
+    ///
+    /// # Returns
+    /// `false` اگه توکن قبلی تا این لحظه باطل شده باشه (هیچ تغییری ذخیره نمیشه)
+    pub async fn rotate(&self, current_id: &str, next: &CreateRefreshToken) -> Result<bool> {
+        let current_id = current_id.to_string();
+        let next = next.clone();
+        
+        self.db.transaction(|mut tx| async move {
+            let now = Utc::now();
+            
+            let result = sqlx::query(
+                r#"
+                UPDATE refresh_tokens
+                SET revoked_at = $1, replaced_by = $2
+                WHERE id = $3 AND revoked_at IS NULL
+                "#
+            )
+            .bind(now)
+            .bind(&next.id)
+            .bind(&current_id)
+            .execute(&mut *tx)
+            .await?;
+            
+            if result.rows_affected() == 0 {
+                // tx بدون commit drop میشه و rollback میشه
+                return Ok(false);
+            }
+            
+            sqlx::query(
+                r#"
+                INSERT INTO refresh_tokens (id, user_id, token_hash, family_id, expires_at, created_at)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                "#
+            )
+            .bind(&next.id)
+            .bind(&next.user_id)
+            .bind(&next.token_hash)
+            .bind(&next.family_id)
+            .bind(next.expires_at)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+            
+            tx.commit().await?;
+            Ok(true)
+        }).await
+    }
+    
+    /// باطل کردن همه توکن‌های یک family (یک نشست ورود)
+    pub async fn revoke_family(&self, family_id: &str) -> Result<u64> {
+        let result = sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = $1 WHERE family_id = $2 AND revoked_at IS NULL"
+        )
+        .bind(Utc::now())
+        .bind(family_id)
+        .execute(self.db.pool())
+        .await?;
+        
+        Ok(result.rows_affected())
+    }
+    
+    /// باطل کردن همه توکن‌های یک کاربر (مثلا بعد از تغییر رمز عبور)
+    pub async fn revoke_all_for_user(&self, user_id: &str) -> Result<u64> {
+        let result = sqlx::query(
+            "UPDATE refresh_tokens SET revoked_at = $1 WHERE user_id = $2 AND revoked_at IS NULL"
+        )
+        .bind(Utc::now())
+        .bind(user_id)
+        .execute(self.db.pool())
+        .await?;
+        
+        Ok(result.rows_affected())
+    }
+}
+
+// =====================================
+// API Key Repository
+// =====================================
+use crate::models::{ApiKey, CreateApiKey};
+
+/// Repository برای API key‌ها (جدول `api_keys`)
+#[derive(Debug, Clone)]
+pub struct ApiKeyRepository {
+    db: Database,
+}
+
+impl ApiKeyRepository {
+    #[must_use]
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+    
+    /// ذخیره کلید جدید
+    pub async fn create(&self, key: &CreateApiKey) -> Result<ApiKey> {
+        sqlx::query(
+            r#"
+            INSERT INTO api_keys (id, user_id, name, key_prefix, key_hash, scopes, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#
+        )
+        .bind(&key.id)
+        .bind(&key.user_id)
+        .bind(&key.name)
+        .bind(&key.key_prefix)
+        .bind(&key.key_hash)
+        .bind(&key.scopes)
+        .bind(Utc::now())
+        .execute(self.db.pool())
+        .await?;
+        
+        let key = sqlx::query_as::<_, ApiKey>(
+            r#"
+            SELECT id, user_id, name, key_prefix, key_hash, scopes,
+                   last_used_at, revoked_at, created_at
+            FROM api_keys
+            WHERE id = $1
+            "#
+        )
+        .bind(&key.id)
+        .fetch_one(self.db.pool())
+        .await?;
+        
+        Ok(key)
+    }
+    
+    /// پیدا کردن کلید فعال با هش
+    ///
+    /// کلیدهای باطل شده و کلیدهای کاربرهای غیرفعال برنمیگردن
+    pub async fn find_active_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>> {
+        let key = sqlx::query_as::<_, ApiKey>(
+            r#"
+            SELECT k.id, k.user_id, k.name, k.key_prefix, k.key_hash, k.scopes,
+                   k.last_used_at, k.revoked_at, k.created_at
+            FROM api_keys k
+            JOIN users u ON u.id = k.user_id
+            WHERE k.key_hash = $1 AND k.revoked_at IS NULL AND u.is_active = TRUE
+            "#
+        )
+        .bind(key_hash)
+        .fetch_optional(self.db.pool())
+        .await?;
+        
+        Ok(key)
+    }
+    
+    /// کلیدهای فعال یک کاربر
+    pub async fn find_active_by_user(&self, user_id: &str) -> Result<Vec<ApiKey>> {
+        let keys = sqlx::query_as::<_, ApiKey>(
+            r#"
+            SELECT id, user_id, name, key_prefix, key_hash, scopes,
+                   last_used_at, revoked_at, created_at
+            FROM api_keys
+            WHERE user_id = $1 AND revoked_at IS NULL
+            ORDER BY created_at DESC
+            "#
+        )
+        .bind(user_id)
+        .fetch_all(self.db.pool())
+        .await?;
+        
+        Ok(keys)
+    }
+    
+    /// باطل کردن کلید (فقط توسط مالک)
+    ///
+    /// # Returns
+    /// `false` اگه کلید پیدا نشد، مال کاربر دیگه‌ای بود یا قبلا باطل شده بود
+    pub async fn revoke(&self, id: &str, user_id: &str) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE api_keys SET revoked_at = $1 WHERE id = $2 AND user_id = $3 AND revoked_at IS NULL"
+        )
+        .bind(Utc::now())
+        .bind(id)
+        .bind(user_id)
+        .execute(self.db.pool())
+        .await?;
+        
+        Ok(result.rows_affected() > 0)
+    }
+    
+    /// ثبت زمان آخرین استفاده
+    pub async fn touch(&self, id: &str) -> Result<()> {
+        sqlx::query("UPDATE api_keys SET last_used_at = $1 WHERE id = $2")
+            .bind(Utc::now())
+            .bind(id)
+            .execute(self.db.pool())
+            .await?;
+        
+        Ok(())
+    }
+}
+
+
@@ -0,0 +1,430 @@
+//! # Storage Traits
+//!
+//! interface‌های ذخیره‌سازی که سرویس‌ها بهشون وابسته هستن.
+//!
+//! همه سرویس‌ها به جای struct‌های SQL، `Arc<dyn ...>` از این trait‌ها
+//! نگه میدارن. دو پیاده‌سازی داریم:
+//! - Repository‌های SQL (`UrlRepository`، `UserRepository`، ...) روی `Database`
+//! - `MemoryStore` که همه چیز رو در حافظه نگه میداره (تست‌ها و نسخه دمو)
+//!
+//! ## مفاهیم Rust:
+//! - **Trait Objects**: `Arc<dyn UrlStore>` به جای generic، تا نوع سرویس‌ها
+//!   و `AppState` ساده بمونه
+//! - **Default Methods**: متدی که بر اساس متدهای دیگه پیاده‌سازی شده
+//! - **Delegation**: پیاده‌سازی SQL فقط متدهای repository رو صدا میزنه
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use super::{
+    ApiKeyRepository, ClickRepository, RefreshTokenRepository, Repository, UrlRepository,
+    UrlStats, UserRepository,
+};
+use crate::{
+    error::Result,
+    models::{
+        AnalyticsInterval, ApiKey, ClickBatch, ClickBucket, ClickDimension, CreateApiKey,
+        CreateClickEvent, CreateRefreshToken, CreateUrl, CreateUser, DailyCount, Pagination,
+        RefreshToken, TopValue, Url, UrlFilter, User, UserFilter,
+    },
+};
+
+// =====================================
+// URL Store
+// =====================================
+/// ذخیره‌سازی URL‌ها
+#[async_trait]
+pub trait UrlStore: Send + Sync + Debug {
+    /// پیدا کردن با ID
+    async fn find_by_id(&self, id: &str) -> Result<Option<Url>>;
+    
+    /// پیدا کردن با short_code
+    async fn find_by_short_code(&self, short_code: &str) -> Result<Option<Url>>;
+    
+    /// چک کردن وجود short_code
+    async fn exists(&self, short_code: &str) -> Result<bool>;
+    
+    /// ایجاد URL جدید
+    ///
+    /// # Errors
+    /// short_code تکراری
+    async fn create(&self, create_url: &CreateUrl) -> Result<Url>;
+    
+    /// ذخیره مقصد، عنوان و تاریخ انقضا (شمارنده کلیک دست نمیخوره)
+    async fn update(&self, url: &Url) -> Result<Url>;
+    
+    /// افزایش اتمی شمارنده کلیک؛ `false` اگه کد وجود نداشته باشه یا `max_clicks` پر شده باشه
+    async fn increment_clicks(&self, short_code: &str) -> Result<bool>;
+    
+    /// حذف با ID (کلیک‌های ثبت شده هم حذف میشن)
+    async fn delete(&self, id: &str) -> Result<bool>;
+    
+    /// حذف چند URL متعلق به یک کاربر؛ شناسه‌هایی که واقعا حذف شدن برمیگردن
+    async fn delete_many_owned(&self, ids: &[String], user_id: &str) -> Result<Vec<String>>;
+    
+    /// جستجو، فیلتر و صفحه‌بندی (`owner = None` یعنی همه URL‌ها)
+    async fn search(
+        &self,
+        owner: Option<&str>,
+        filter: &UrlFilter,
+        pagination: &Pagination,
+    ) -> Result<(Vec<Url>, u64)>;
+    
+    /// جستجو، فیلتر و صفحه‌بندی URL‌های یک کاربر
+    async fn search_by_user(
+        &self,
+        user_id: &str,
+        filter: &UrlFilter,
+        pagination: &Pagination,
+    ) -> Result<(Vec<Url>, u64)> {
+        self.search(Some(user_id), filter, pagination).await
+    }
+    
+    /// حذف URL‌های منقضی شده
+    async fn delete_expired(&self) -> Result<u64>;
+    
+    /// آمار کلی
+    async fn get_stats(&self) -> Result<UrlStats>;
+    
+    /// تعداد لینک‌های فعال و منقضی شده: `(active, expired)`
+    async fn count_by_status(&self) -> Result<(i64, i64)>;
+    
+    /// تعداد لینک‌های ساخته شده در هر روز از `since` به بعد (فقط روزهای غیر خالی)
+    async fn count_created_per_day(&self, since: DateTime<Utc>) -> Result<Vec<DailyCount>>;
+    
+    /// پرکلیک‌ترین لینک‌ها
+    async fn find_top_by_clicks(&self, limit: u32) -> Result<Vec<Url>>;
+}
+
+// =====================================
+// Click Store
+// =====================================
+/// ذخیره‌سازی رویدادهای کلیک
+#[async_trait]
+pub trait ClickStore: Send + Sync + Debug {
+    /// ثبت یک کلیک
+    async fn create(&self, event: &CreateClickEvent) -> Result<()>;
+    
+    /// افزایش شمارنده‌ها و ثبت رویدادهای یک batch به صورت اتمی
+    ///
+    /// رویداد URL‌های حذف شده نادیده گرفته میشه؛ تعداد رویدادهای ثبت شده برمیگرده
+    async fn record_batch(&self, batch: &ClickBatch) -> Result<u64>;
+    
+    /// تعداد کلیک‌های ثبت شده برای یک URL
+    async fn count_by_url(&self, url_id: &str) -> Result<i64>;
+    
+    /// تعداد کلیک‌های یک URL در بازه `[from, to)`
+    async fn count_in_range(
+        &self,
+        url_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<i64>;
+    
+    /// سری زمانی کلیک‌ها، مرتب شده بر اساس bucket
+    async fn timeline(
+        &self,
+        url_id: &str,
+        interval: AnalyticsInterval,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<ClickBucket>>;
+    
+    /// پرتکرارترین مقادیر یک بعد (مقادیر خالی حساب نمیشن)
+    async fn top_values(
+        &self,
+        url_id: &str,
+        dimension: ClickDimension,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<TopValue>>;
+}
+
+// =====================================
+// User Store
+// =====================================
+/// ذخیره‌سازی کاربران
+#[async_trait]
+pub trait UserStore: Send + Sync + Debug {
+    /// پیدا کردن با ID
+    async fn find_by_id(&self, id: &str) -> Result<Option<User>>;
+    
+    /// پیدا کردن با email
+    async fn find_by_email(&self, email: &str) -> Result<Option<User>>;
+    
+    /// بررسی وجود email
+    async fn email_exists(&self, email: &str) -> Result<bool>;
+    
+    /// ایجاد کاربر جدید
+    ///
+    /// # Errors
+    /// email تکراری
+    async fn create(&self, create_user: &CreateUser) -> Result<User>;
+    
+    /// ذخیره ایمیل، نام، وضعیت فعال بودن و نقش
+    async fn update(&self, user: &User) -> Result<User>;
+    
+    /// هش و ذخیره رمز جدید و بروز کردن `password_changed_at`
+    ///
+    /// # Errors
+    /// `NotFound` اگه کاربر وجود نداشته باشه
+    async fn update_password(&self, id: &str, new_password: &str) -> Result<()>;
+    
+    /// جستجو و صفحه‌بندی کاربران (پنل ادمین)
+    async fn search(&self, filter: &UserFilter, pagination: &Pagination) -> Result<(Vec<User>, u64)>;
+    
+    /// تعداد کل کاربران
+    async fn count(&self) -> Result<i64>;
+    
+    /// تعداد کاربران فعال
+    async fn count_active(&self) -> Result<i64>;
+}
+
+// =====================================
+// Refresh Token Store
+// =====================================
+/// ذخیره‌سازی refresh token‌ها
+#[async_trait]
+pub trait RefreshTokenStore: Send + Sync + Debug {
+    /// ذخیره توکن جدید
+    async fn create(&self, token: &CreateRefreshToken) -> Result<()>;
+    
+    /// پیدا کردن توکن با هش
+    async fn find_by_hash(&self, token_hash: &str) -> Result<Option<RefreshToken>>;
+    
+    /// باطل کردن توکن فعلی و ذخیره توکن بعدی به صورت اتمی
+    ///
+    /// `false` اگه توکن فعلی قبلا باطل شده باشه (هیچ تغییری ذخیره نمیشه)
+    async fn rotate(&self, current_id: &str, next: &CreateRefreshToken) -> Result<bool>;
+    
+    /// باطل کردن همه توکن‌های یک family
+    async fn revoke_family(&self, family_id: &str) -> Result<u64>;
+    
+    /// باطل کردن همه توکن‌های یک کاربر
+    async fn revoke_all_for_user(&self, user_id: &str) -> Result<u64>;
+}
+
+// =====================================
+// API Key Store
+// =====================================
+/// ذخیره‌سازی API key‌ها
+#[async_trait]
+pub trait ApiKeyStore: Send + Sync + Debug {
+    /// ذخیره کلید جدید
+    async fn create(&self, key: &CreateApiKey) -> Result<ApiKey>;
+    
+    /// پیدا کردن کلید فعال با هش (کلیدهای باطل شده و کاربرهای غیرفعال برنمیگردن)
+    async fn find_active_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>>;
+    
+    /// کلیدهای فعال یک کاربر، جدیدترین اول
+    async fn find_active_by_user(&self, user_id: &str) -> Result<Vec<ApiKey>>;
+    
+    /// باطل کردن کلید توسط مالک؛ `false` اگه پیدا نشد یا قبلا باطل شده بود
+    async fn revoke(&self, id: &str, user_id: &str) -> Result<bool>;
+    
+    /// ثبت زمان آخرین استفاده
+    async fn touch(&self, id: &str) -> Result<()>;
+}
+
+// =====================================
+// SQL Implementations
+// =====================================
+// `Type::method` متد inherent رو صدا میزنه، نه متد trait رو
+
+#[async_trait]
+impl UrlStore for UrlRepository {
+    async fn find_by_id(&self, id: &str) -> Result<Option<Url>> {
+        Repository::find_by_id(self, &id.to_string()).await
+    }
+    
+    async fn find_by_short_code(&self, short_code: &str) -> Result<Option<Url>> {
+        UrlRepository::find_by_short_code(self, short_code).await
+    }
+    
+    async fn exists(&self, short_code: &str) -> Result<bool> {
+        UrlRepository::exists(self, short_code).await
+    }
+    
+    async fn create(&self, create_url: &CreateUrl) -> Result<Url> {
+        UrlRepository::create(self, create_url).await
+    }
+    
+    async fn update(&self, url: &Url) -> Result<Url> {
+        UrlRepository::update(self, url).await
+    }
+    
+    async fn increment_clicks(&self, short_code: &str) -> Result<bool> {
+        UrlRepository::increment_clicks(self, short_code).await
+    }
+    
+    async fn delete(&self, id: &str) -> Result<bool> {
+        Repository::delete(self, &id.to_string()).await
+    }
+    
+    async fn delete_many_owned(&self, ids: &[String], user_id: &str) -> Result<Vec<String>> {
+        UrlRepository::delete_many_owned(self, ids, user_id).await
+    }
+    
+    async fn search(
+        &self,
+        owner: Option<&str>,
+        filter: &UrlFilter,
+        pagination: &Pagination,
+    ) -> Result<(Vec<Url>, u64)> {
+        UrlRepository::search(self, owner, filter, pagination).await
+    }
+    
+    async fn delete_expired(&self) -> Result<u64> {
+        UrlRepository::delete_expired(self).await
+    }
+    
+    async fn get_stats(&self) -> Result<UrlStats> {
+        UrlRepository::get_stats(self).await
+    }
+    
+    async fn count_by_status(&self) -> Result<(i64, i64)> {
+        UrlRepository::count_by_status(self).await
+    }
+    
+    async fn count_created_per_day(&self, since: DateTime<Utc>) -> Result<Vec<DailyCount>> {
+        UrlRepository::count_created_per_day(self, since).await
+    }
+    
+    async fn find_top_by_clicks(&self, limit: u32) -> Result<Vec<Url>> {
+        UrlRepository::find_top_by_clicks(self, limit).await
+    }
+}
+
+#[async_trait]
+impl ClickStore for ClickRepository {
+    async fn create(&self, event: &CreateClickEvent) -> Result<()> {
+        ClickRepository::create(self, event).await
+    }
+    
+    async fn record_batch(&self, batch: &ClickBatch) -> Result<u64> {
+        ClickRepository::record_batch(self, batch).await
+    }
+    
+    async fn count_by_url(&self, url_id: &str) -> Result<i64> {
+        ClickRepository::count_by_url(self, url_id).await
+    }
+    
+    async fn count_in_range(
+        &self,
+        url_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<i64> {
+        ClickRepository::count_in_range(self, url_id, from, to).await
+    }
+    
+    async fn timeline(
+        &self,
+        url_id: &str,
+        interval: AnalyticsInterval,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<ClickBucket>> {
+        ClickRepository::timeline(self, url_id, interval, from, to).await
+    }
+    
+    async fn top_values(
+        &self,
+        url_id: &str,
+        dimension: ClickDimension,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<TopValue>> {
+        ClickRepository::top_values(self, url_id, dimension, from, to, limit).await
+    }
+}
+
+#[async_trait]
+impl UserStore for UserRepository {
+    async fn find_by_id(&self, id: &str) -> Result<Option<User>> {
+        Repository::find_by_id(self, &id.to_string()).await
+    }
+    
+    async fn find_by_email(&self, email: &str) -> Result<Option<User>> {
+        UserRepository::find_by_email(self, email).await
+    }
+    
+    async fn email_exists(&self, email: &str) -> Result<bool> {
+        UserRepository::email_exists(self, email).await
+    }
+    
+    async fn create(&self, create_user: &CreateUser) -> Result<User> {
+        UserRepository::create(self, create_user).await
+    }
+    
+    async fn update(&self, user: &User) -> Result<User> {
+        UserRepository::update(self, user).await
+    }
+    
+    async fn update_password(&self, id: &str, new_password: &str) -> Result<()> {
+        UserRepository::update_password(self, id, new_password).await
+    }
+    
+    async fn search(&self, filter: &UserFilter, pagination: &Pagination) -> Result<(Vec<User>, u64)> {
+        UserRepository::search(self, filter, pagination).await
+    }
+    
+    async fn count(&self) -> Result<i64> {
+        Repository::count(self).await
+    }
+    
+    async fn count_active(&self) -> Result<i64> {
+        UserRepository::count_active(self).await
+    }
+}
+
+#[async_trait]
+impl RefreshTokenStore for RefreshTokenRepository {
+    async fn create(&self, token: &CreateRefreshToken) -> Result<()> {
+        RefreshTokenRepository::create(self, token).await
+    }
+    
+    async fn find_by_hash(&self, token_hash: &str) -> Result<Option<RefreshToken>> {
+        RefreshTokenRepository::find_by_hash(self, token_hash).await
+    }
+    
+    async fn rotate(&self, current_id: &str, next: &CreateRefreshToken) -> Result<bool> {
+        RefreshTokenRepository::rotate(self, current_id, next).await
+    }
+    
+    async fn revoke_family(&self, family_id: &str) -> Result<u64> {
+        RefreshTokenRepository::revoke_family(self, family_id).await
+    }
+    
+    async fn revoke_all_for_user(&self, user_id: &str) -> Result<u64> {
+        RefreshTokenRepository::revoke_all_for_user(self, user_id).await
+    }
+}
+
+
+#[async_trait]
+impl ApiKeyStore for ApiKeyRepository {
+    async fn create(&self, key: &CreateApiKey) -> Result<ApiKey> {
+        ApiKeyRepository::create(self, key).await
+    }
+    
+    async fn find_active_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>> {
+        ApiKeyRepository::find_active_by_hash(self, key_hash).await
+    }
+    
+    async fn find_active_by_user(&self, user_id: &str) -> Result<Vec<ApiKey>> {
+        ApiKeyRepository::find_active_by_user(self, user_id).await
+    }
+    
+    async fn revoke(&self, id: &str, user_id: &str) -> Result<bool> {
+        ApiKeyRepository::revoke(self, id, user_id).await
+    }
+    
+    async fn touch(&self, id: &str) -> Result<()> {
+        ApiKeyRepository::touch(self, id).await
+    }
+}
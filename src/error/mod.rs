@@ -23,6 +23,7 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::error;
@@ -79,9 +80,17 @@ pub enum AppError {
     #[error("Conflict: {0}")]
     Conflict(String),
     
+    /// دیگه در دسترس نیست (مثلا سقف کلیک پر شده) - 410
+    #[error("Gone: {0}")]
+    Gone(String),
+    
+    /// هنوز در دسترس نیست (زمان شروع لینک نرسیده) - 403
+    #[error("This URL is not available until {0}")]
+    NotYetAvailable(DateTime<Utc>),
+    
     /// محدودیت نرخ - 429
-    #[error("Too many requests")]
-    RateLimited,
+    #[error("Too many requests, retry after {} seconds", .0.retry_after_secs)]
+    RateLimited(RateLimitInfo),
     
     /// خطای اعتبارسنجی - 422
     #[error("Validation error: {0}")]
@@ -129,6 +138,27 @@ pub enum AppError {
     UrlParse(#[from] url::ParseError),
 }
 
+/// اطلاعات rate limit که در header‌های پاسخ برمیگرده
+///
+/// # Headers
+/// - `X-RateLimit-Limit`: ظرفیت burst
+/// - `X-RateLimit-Remaining`: درخواست‌های باقیمونده
+/// - `Retry-After`: ثانیه تا درخواست مجاز بعدی (فقط در 429)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimitInfo {
+    pub limit: u32,
+    pub remaining: u32,
+    pub retry_after_secs: u64,
+}
+
+impl RateLimitInfo {
+    /// اضافه کردن header‌های `X-RateLimit-*` به response
+    pub fn apply_headers(&self, headers: &mut axum::http::HeaderMap) {
+        headers.insert("X-RateLimit-Limit", self.limit.into());
+        headers.insert("X-RateLimit-Remaining", self.remaining.into());
+    }
+}
+
 impl AppError {
     /// گرفتن HTTP status code متناسب با خطا
     ///
@@ -145,7 +175,9 @@ impl AppError {
             Self::Forbidden(_) => StatusCode::FORBIDDEN,
             Self::NotFound(_) => StatusCode::NOT_FOUND,
             Self::Conflict(_) => StatusCode::CONFLICT,
-            Self::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            Self::Gone(_) => StatusCode::GONE,
+            Self::NotYetAvailable(_) => StatusCode::FORBIDDEN,
+            Self::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
             Self::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
             
             // 5xx Server Errors
@@ -264,8 +296,22 @@ impl IntoResponse for AppError {
         )
         .with_status(status);
         
+        let rate_limit = match &self {
+            Self::RateLimited(info) => Some(*info),
+            _ => None,
+        };
+        
         // برگردوندن tuple که axum بلده تبدیل کنه
-        (status, Json(error_response)).into_response()
+        let mut response = (status, Json(error_response)).into_response();
+        
+        // header‌های rate limit برای اینکه کلاینت بدونه کی دوباره تلاش کنه
+        if let Some(info) = rate_limit {
+            let headers = response.headers_mut();
+            info.apply_headers(headers);
+            headers.insert(axum::http::header::RETRY_AFTER, info.retry_after_secs.into());
+        }
+        
+        response
     }
 }
 
@@ -359,12 +405,32 @@ mod tests {
             StatusCode::BAD_REQUEST
         );
         
+        assert_eq!(
+            AppError::Gone("test".to_string()).status_code(),
+            StatusCode::GONE
+        );
+        
         assert_eq!(
             AppError::Internal("test".to_string()).status_code(),
             StatusCode::INTERNAL_SERVER_ERROR
         );
     }
     
+    #[test]
+    fn test_rate_limited_headers() {
+        let info = RateLimitInfo {
+            limit: 30,
+            remaining: 0,
+            retry_after_secs: 2,
+        };
+        let response = AppError::RateLimited(info).into_response();
+        
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()["Retry-After"], "2");
+        assert_eq!(response.headers()["X-RateLimit-Limit"], "30");
+        assert_eq!(response.headers()["X-RateLimit-Remaining"], "0");
+    }
+    
     #[test]
     fn test_error_response() {
         let response = ErrorResponse::new("NOT_FOUND", "Resource not found")
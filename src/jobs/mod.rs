@@ -0,0 +1,302 @@
+//! # ماژول کارهای پس‌زمینه (Background Jobs)
+//!
+//! این ماژول کارهای نگهداری دوره‌ای رو کنار سرور HTTP اجرا میکنه:
+//! پاکسازی لینک‌های منقضی، هرس کردن map داخلی rate limiter و هر کار
+//! نگهداری دیگه‌ای که بعداً اضافه بشه.
+//!
+//! ## مفاهیم Rust:
+//! - **`tokio::spawn`**: اجرای هر job در task جداگانه
+//! - **`tokio::time::interval`**: تیک‌های دوره‌ای بدون drift
+//! - **`watch` channel**: ارسال سیگنال توقف به همه task‌ها
+//! - **Trait Objects**: نگهداری closure‌های async با `Box<dyn Fn>`
+//!
+//! ## مثال
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use url_shortener::jobs::Scheduler;
+//!
+//! # async fn example() {
+//! let handle = Scheduler::new()
+//!     .every("noop", Duration::from_secs(60), || async { Ok(0) })
+//!     .start();
+//!
+//! // ... اجرای سرور ...
+//!
+//! handle.shutdown().await;
+//! # }
+//! ```
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    time::Duration,
+};
+
+use tokio::{
+    sync::watch,
+    task::JoinHandle,
+    time::{self, Instant, MissedTickBehavior},
+};
+use tracing::{debug, info, warn};
+
+use crate::{error::Result, services::AppState};
+
+/// Future برگشتی از یک job - تعداد آیتم‌های پردازش شده
+type JobFuture = Pin<Box<dyn Future<Output = Result<u64>> + Send>>;
+
+/// تابع یک job که هر بار یه future تازه میسازه
+type JobFn = Arc<dyn Fn() -> JobFuture + Send + Sync>;
+
+/// یک job ثبت شده در scheduler
+struct ScheduledJob {
+    name: &'static str,
+    interval: Duration,
+    run: JobFn,
+}
+
+// =====================================
+// Scheduler
+// =====================================
+/// اجراکننده کارهای دوره‌ای
+///
+/// # مفاهیم:
+/// - Builder Pattern: job‌ها با `every()` اضافه میشن
+/// - هر job در task خودش اجرا میشه تا یه job کند بقیه رو معطل نکنه
+/// - اولین اجرا بعد از گذشتن یک interval هست، نه لحظه استارت
+#[derive(Default)]
+pub struct Scheduler {
+    jobs: Vec<ScheduledJob>,
+}
+
+impl Scheduler {
+    /// ساخت scheduler خالی
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// اضافه کردن job دوره‌ای
+    ///
+    /// interval صفر یعنی job غیرفعاله و ثبت نمیشه.
+    ///
+    /// # Arguments
+    /// * `name` - نام job برای لاگ‌ها
+    /// * `interval` - فاصله بین اجراها
+    /// * `job` - closure که یه future با تعداد آیتم‌های پردازش شده برمیگردونه
+    #[must_use]
+    pub fn every<F, Fut>(mut self, name: &'static str, interval: Duration, job: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<u64>> + Send + 'static,
+    {
+        if interval.is_zero() {
+            info!(job = name, "Background job disabled");
+            return self;
+        }
+        
+        self.jobs.push(ScheduledJob {
+            name,
+            interval,
+            run: Arc::new(move || Box::pin(job()) as JobFuture),
+        });
+        self
+    }
+    
+    /// تعداد job‌های فعال
+    #[must_use]
+    pub fn job_count(&self) -> usize {
+        self.jobs.len()
+    }
+    
+    /// شروع همه job‌ها در پس‌زمینه
+    ///
+    /// # مفاهیم:
+    /// - `self` مصرف میشه: بعد از start دیگه نمیشه job اضافه کرد
+    /// - `SchedulerHandle` برگشتی برای توقف graceful استفاده میشه
+    #[must_use]
+    pub fn start(self) -> SchedulerHandle {
+        let (shutdown, _) = watch::channel(false);
+        
+        let tasks = self
+            .jobs
+            .into_iter()
+            .map(|job| tokio::spawn(run_job(job, shutdown.subscribe())))
+            .collect();
+        
+        SchedulerHandle { shutdown, tasks }
+    }
+}
+
+/// حلقه اجرای یک job تا رسیدن سیگنال توقف
+async fn run_job(job: ScheduledJob, mut shutdown: watch::Receiver<bool>) {
+    let mut ticker = time::interval_at(Instant::now() + job.interval, job.interval);
+    // اگه یه اجرا طول کشید، تیک‌های عقب افتاده پشت هم اجرا نشن
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    
+    info!(job = job.name, interval_secs = job.interval.as_secs(), "Background job scheduled");
+    
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = shutdown.changed() => break,
+        }
+        
+        let started = Instant::now();
+        match (job.run)().await {
+            Ok(processed) => debug!(
+                job = job.name,
+                processed,
+                duration_ms = started.elapsed().as_millis() as u64,
+                "Background job finished"
+            ),
+            Err(e) => warn!(
+                job = job.name,
+                error = %e,
+                duration_ms = started.elapsed().as_millis() as u64,
+                "Background job failed"
+            ),
+        }
+    }
+    
+    info!(job = job.name, "Background job stopped");
+}
+
+// =====================================
+// Scheduler Handle
+// =====================================
+/// کنترل job‌های در حال اجرا
+///
+/// اگه بدون `shutdown()` drop بشه، task‌ها تا پایان runtime ادامه میدن.
+pub struct SchedulerHandle {
+    shutdown: watch::Sender<bool>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl SchedulerHandle {
+    /// توقف graceful همه job‌ها
+    ///
+    /// job‌ای که وسط اجراست تموم میشه و بعد حلقه‌اش بسته میشه.
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.send(true);
+        
+        for task in self.tasks {
+            if let Err(e) = task.await {
+                warn!(error = %e, "Background job task panicked");
+            }
+        }
+        
+        info!("Background jobs stopped");
+    }
+}
+
+// =====================================
+// Maintenance Jobs
+// =====================================
+/// ساخت scheduler با کارهای نگهداری استاندارد برنامه
+///
+/// # Jobs
+/// - `cleanup_expired_urls`: حذف لینک‌های منقضی (`CLEANUP_INTERVAL_SECS`)
+/// - `prune_rate_limiter`: هرس کلید‌های rate limiter API و تلاش رمز لینک‌ها (`RATE_LIMIT_CLEANUP_INTERVAL_SECS`)
+#[must_use]
+pub fn maintenance_scheduler(state: &AppState) -> Scheduler {
+    let config = state.config();
+    
+    let url_service = state.url_service.clone();
+    let rate_limiter = state.rate_limiter.clone();
+    let unlock_limiter = state.unlock_limiter.clone();
+    
+    Scheduler::new()
+        .every(
+            "cleanup_expired_urls",
+            Duration::from_secs(config.cleanup_interval_secs),
+            move || {
+                let url_service = url_service.clone();
+                async move { url_service.cleanup_expired().await }
+            },
+        )
+        .every(
+            "prune_rate_limiter",
+            Duration::from_secs(config.rate_limit_cleanup_interval_secs),
+            move || {
+                let rate_limiter = rate_limiter.clone();
+                let unlock_limiter = unlock_limiter.clone();
+                async move {
+                    let before = rate_limiter.tracked_keys() + unlock_limiter.tracked_keys();
+                    rate_limiter.cleanup();
+                    unlock_limiter.cleanup();
+                    let after = rate_limiter.tracked_keys() + unlock_limiter.tracked_keys();
+                    Ok(before.saturating_sub(after) as u64)
+                }
+            },
+        )
+}
+
+// =====================================
+// Tests
+// =====================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use crate::error::AppError;
+    
+    /// job‌های با interval صفر ثبت نمیشن
+    #[test]
+    fn test_zero_interval_disables_job() {
+        let scheduler = Scheduler::new()
+            .every("disabled", Duration::ZERO, || async { Ok(0) })
+            .every("enabled", Duration::from_secs(1), || async { Ok(0) });
+        
+        assert_eq!(scheduler.job_count(), 1);
+    }
+    
+    /// job به صورت دوره‌ای اجرا میشه و بعد از shutdown متوقف میشه
+    #[tokio::test(start_paused = true)]
+    async fn test_job_runs_periodically_until_shutdown() {
+        let runs = Arc::new(AtomicU64::new(0));
+        let counter = runs.clone();
+        
+        let handle = Scheduler::new()
+            .every("counter", Duration::from_secs(10), move || {
+                let counter = counter.clone();
+                async move { Ok(counter.fetch_add(1, Ordering::SeqCst)) }
+            })
+            .start();
+        
+        // قبل از اولین interval اجرا نمیشه
+        time::sleep(Duration::from_secs(5)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+        
+        time::sleep(Duration::from_secs(30)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        
+        handle.shutdown().await;
+        
+        time::sleep(Duration::from_secs(60)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+    }
+    
+    /// خطای یک job حلقه رو متوقف نمیکنه
+    #[tokio::test(start_paused = true)]
+    async fn test_failing_job_keeps_running() {
+        let runs = Arc::new(AtomicU64::new(0));
+        let counter = runs.clone();
+        
+        let handle = Scheduler::new()
+            .every("failing", Duration::from_secs(1), move || {
+                let counter = counter.clone();
+                async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    Err(AppError::Internal("boom".to_string()))
+                }
+            })
+            .start();
+        
+        time::sleep(Duration::from_millis(3500)).await;
+        handle.shutdown().await;
+        
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+    }
+}
+
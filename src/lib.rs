@@ -14,6 +14,7 @@
 //! ├── models/         # مدل‌های داده
 //! ├── services/       # منطق کسب‌وکار
 //! ├── api/            # لایه API
+//! ├── jobs/           # کارهای پس‌زمینه
 //! └── utils/          # توابع کمکی
 //! ```
 //!
@@ -59,6 +60,9 @@ pub mod services;
 /// ماژول API و HTTP Handlers
 pub mod api;
 
+/// ماژول کارهای پس‌زمینه (پاکسازی دوره‌ای و نگهداری)
+pub mod jobs;
+
 /// ماژول توابع کمکی
 pub mod utils;
 
@@ -10,18 +10,20 @@
 //! - `Result<T, E>`: مدیریت خطا
 //! - `?` operator: انتشار خطا به بالا
 
-use std::net::SocketAddr;
+use std::{sync::Arc, time::Duration};
 
-use tokio::net::TcpListener;
-use tracing::{info, Level};
+use tokio::{signal, sync::Notify};
+use tracing::{info, warn};
 use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 // وارد کردن ماژول‌ها از کتابخانه‌مون
 use url_shortener::{
-    api::create_router,
+    api::{create_router_with_state, Listener},
     config::Config,
     database::Database,
     error::Result,
+    jobs::maintenance_scheduler,
+    services::AppState,
 };
 
 /// نقطه ورود اصلی برنامه
@@ -42,43 +44,125 @@ async fn main() -> Result<()> {
     // در Rust خطاها رو باید handle کنیم، اینجا اگه فایل نباشه اوکیه
     dotenvy::dotenv().ok();
 
+    // `--print-config`: نمایش config نهایی (بعد از همه لایه‌ها) و خروج
+    // secret‌ها mask میشن تا خروجی توی لاگ CI یا ترمینال امن باشه
+    if std::env::args().skip(1).any(|arg| arg == "--print-config") {
+        let config = Config::load()?;
+        println!("{}", serde_json::to_string_pretty(&config.masked())?);
+        return Ok(());
+    }
+
     // راه‌اندازی سیستم لاگینگ
     // این یه نمونه از Builder Pattern هست
     init_tracing();
 
     info!("🚀 Starting URL Shortener Service...");
 
-    // لود کردن تنظیمات
+    // لود کردن تنظیمات: پیش‌فرض‌ها، فایل‌های config/ و بعد متغیرهای محیطی
     // `?` یعنی اگه خطا بود، همینجا return کن
-    let config = Config::from_env()?;
-    info!("✅ Configuration loaded successfully");
-
-    // اتصال به دیتابیس
-    // `Arc<T>` برای share کردن ownership بین thread‌ها
-    let database = Database::connect(&config.database_url).await?;
-    info!("✅ Database connected successfully");
-
-    // اجرای migration‌ها
-    database.migrate().await?;
-    info!("✅ Database migrations applied");
+    let config = Config::load()?;
+    info!(environment = ?config.environment, "✅ Configuration loaded successfully");
 
-    // ساخت router با تمام route‌ها و middleware‌ها
+    // ساخت state مشترک - هم router و هم کارهای پس‌زمینه ازش استفاده میکنن
     // این یه نمونه از Dependency Injection هست
-    let app = create_router(database, config.clone());
-
-    // آدرس سرور
-    let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
-    info!("🌐 Server listening on http://{}", addr);
+    let state = if config.uses_memory_store() {
+        // نسخه دمو: هیچ فایلی ساخته نمیشه و داده‌ها با خاموش شدن از بین میرن
+        warn!("⚠️  Using in-memory storage, all data will be lost on shutdown");
+        AppState::in_memory(config.clone())
+    } else {
+        // اتصال به دیتابیس
+        let database = Database::connect(&config.database_url).await?;
+        info!("✅ Database connected successfully");
+
+        // اجرای migration‌ها
+        database.migrate().await?;
+        info!("✅ Database migrations applied");
+
+        AppState::new(database, config.clone())
+    };
+
+    // شروع کارهای نگهداری دوره‌ای (پاکسازی لینک‌ها، هرس rate limiter)
+    let jobs = maintenance_scheduler(&state).start();
+    info!("✅ Background jobs started");
 
-    // ساخت listener و اجرای سرور
-    let listener = TcpListener::bind(addr).await?;
-
-    // اجرای سرور - این بلاک تا ابد اجرا میشه
-    axum::serve(listener, app)
-        .await
-        .map_err(|e| url_shortener::error::AppError::Server(e.to_string()))?;
+    // ساخت router با تمام route‌ها و middleware‌ها
+    // یه clone نگه میداریم تا بعد از بسته شدن سرور منابع رو آزاد کنیم
+    let app = create_router_with_state(state.clone());
+
+    // ساخت listener - Unix socket یا هاست/پورت تنظیم شده
+    let listener = Listener::bind(&config).await?;
+    info!("🌐 Server listening on {}", listener);
+
+    // اجرای سرور تا رسیدن SIGINT/SIGTERM
+    // بعد از سیگنال، listener بسته میشه و درخواست‌های در حال اجرا تموم میشن
+    let signalled = Arc::new(Notify::new());
+    let server = listener.serve(app, {
+        let signalled = signalled.clone();
+        async move {
+            shutdown_signal().await;
+            signalled.notify_one();
+        }
+    });
+
+    // اگه drain بیشتر از timeout طول بکشه، اتصال‌های باقیمونده رها میشن
+    let drain_timeout = Duration::from_secs(config.shutdown_timeout_secs);
+    let served = tokio::select! {
+        result = server => result,
+        () = async {
+            signalled.notified().await;
+            tokio::time::sleep(drain_timeout).await;
+        } => {
+            warn!(timeout_secs = drain_timeout.as_secs(), "Drain timeout exceeded, dropping open connections");
+            Ok(())
+        }
+    };
+    info!("🛑 HTTP server stopped");
+
+    // توقف graceful کارهای پس‌زمینه بعد از بسته شدن سرور (حتی با خطا)
+    jobs.shutdown().await;
+
+    // flush کردن ثبت‌های کلیک و بستن pool دیتابیس
+    state.shutdown().await;
+    info!("👋 Shutdown complete");
+
+    served
+}
 
-    Ok(())
+/// منتظر موندن برای سیگنال خاموش شدن
+///
+/// # مفاهیم:
+/// - `tokio::signal::ctrl_c`: سیگنال SIGINT (Ctrl+C)
+/// - `SignalKind::terminate`: سیگنال SIGTERM که orchestrator‌ها موقع deploy میفرستن
+/// - `#[cfg(unix)]`: کامپایل شرطی - SIGTERM فقط روی unix وجود داره
+/// - `tokio::select!`: هر کدوم زودتر رسید
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = signal::ctrl_c().await {
+            warn!(error = %e, "Failed to listen for Ctrl+C");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut stream) => {
+                stream.recv().await;
+            }
+            Err(e) => {
+                warn!(error = %e, "Failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => info!("Received Ctrl+C, shutting down"),
+        () = terminate => info!("Received SIGTERM, shutting down"),
+    }
 }
 
 /// راه‌اندازی سیستم tracing برای لاگینگ
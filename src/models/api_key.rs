@@ -0,0 +1,209 @@
+//! # مدل API Key
+//!
+//! کلیدهای دسترسی برنامه‌ای با scope محدود
+//!
+//! ## مفاهیم:
+//! - کلید فقط یک بار (موقع ساخت) به کاربر نشون داده میشه
+//! - در دیتابیس فقط هش SHA-256 کلید ذخیره میشه
+//! - هر کلید فقط به scope‌های خودش دسترسی داره
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::FromRow;
+use validator::Validate;
+
+/// پیشوند همه API key‌ها - برای تشخیص از JWT در header `Authorization`
+pub const API_KEY_PREFIX: &str = "usk_";
+
+/// طول بخش تصادفی کلید
+const API_KEY_SECRET_LENGTH: usize = 40;
+
+/// تعداد کاراکترهای اول کلید که برای نمایش ذخیره میشه
+const API_KEY_DISPLAY_LENGTH: usize = 12;
+
+// =====================================
+// Scopes
+// =====================================
+/// دسترسی‌هایی که میشه به یک API key داد
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ApiScope {
+    /// خوندن لیست URL‌های کاربر
+    #[serde(rename = "urls:read")]
+    UrlsRead,
+    
+    /// ساخت، ویرایش و حذف URL
+    #[serde(rename = "urls:write")]
+    UrlsWrite,
+    
+    /// خوندن آمار کلیک‌ها
+    #[serde(rename = "analytics:read")]
+    AnalyticsRead,
+}
+
+impl ApiScope {
+    /// نام scope همونطور که در API و دیتابیس استفاده میشه
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::UrlsRead => "urls:read",
+            Self::UrlsWrite => "urls:write",
+            Self::AnalyticsRead => "analytics:read",
+        }
+    }
+    
+    /// پارس کردن یک scope از رشته
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "urls:read" => Some(Self::UrlsRead),
+            "urls:write" => Some(Self::UrlsWrite),
+            "analytics:read" => Some(Self::AnalyticsRead),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ApiScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// =====================================
+// API Key Entity
+// =====================================
+/// Entity یک API key (ردیف جدول `api_keys`)
+#[derive(Debug, Clone, FromRow)]
+pub struct ApiKey {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    
+    /// چند کاراکتر اول کلید (مثلا `usk_AbC12345`) برای شناسایی
+    pub key_prefix: String,
+    
+    /// هش SHA-256 کلید (hex)
+    pub key_hash: String,
+    
+    /// scope‌ها جدا شده با کاما
+    pub scopes: String,
+    
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ApiKey {
+    /// لیست scope‌های این کلید
+    ///
+    /// مقادیر ناشناخته نادیده گرفته میشن
+    #[must_use]
+    pub fn scopes(&self) -> Vec<ApiScope> {
+        self.scopes
+            .split(',')
+            .filter_map(|s| ApiScope::parse(s.trim()))
+            .collect()
+    }
+}
+
+// =====================================
+// Create API Key DTO
+// =====================================
+/// داده برای ذخیره API key جدید (داخلی)
+#[derive(Debug, Clone)]
+pub struct CreateApiKey {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub key_prefix: String,
+    pub key_hash: String,
+    pub scopes: String,
+}
+
+impl CreateApiKey {
+    /// تولید یه API key تصادفی جدید
+    ///
+    /// خروجی دوم کلید خام هست که فقط یک بار به کاربر برمیگرده
+    #[must_use]
+    pub fn generate(
+        user_id: impl Into<String>,
+        name: impl Into<String>,
+        scopes: &[ApiScope],
+    ) -> (Self, String) {
+        let key = format!("{}{}", API_KEY_PREFIX, nanoid::nanoid!(API_KEY_SECRET_LENGTH));
+        
+        let mut scopes = scopes.to_vec();
+        scopes.sort();
+        scopes.dedup();
+        
+        let record = Self {
+            id: nanoid::nanoid!(21),
+            user_id: user_id.into(),
+            name: name.into(),
+            key_prefix: key[..API_KEY_DISPLAY_LENGTH].to_string(),
+            key_hash: hash_api_key(&key),
+            scopes: scopes
+                .iter()
+                .map(ApiScope::as_str)
+                .collect::<Vec<_>>()
+                .join(","),
+        };
+        
+        (record, key)
+    }
+}
+
+/// هش SHA-256 یک API key
+#[must_use]
+pub fn hash_api_key(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+// =====================================
+// API Request/Response DTOs
+// =====================================
+/// درخواست ساخت API key
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct CreateApiKeyRequest {
+    #[validate(length(min = 1, max = 100, message = "Name must be 1-100 characters"))]
+    pub name: String,
+    
+    #[validate(length(min = 1, message = "At least one scope is required"))]
+    pub scopes: Vec<ApiScope>,
+}
+
+/// اطلاعات یک API key (بدون خود کلید)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyResponse {
+    pub id: String,
+    pub name: String,
+    pub prefix: String,
+    pub scopes: Vec<ApiScope>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ApiKey> for ApiKeyResponse {
+    fn from(key: ApiKey) -> Self {
+        Self {
+            scopes: key.scopes(),
+            id: key.id,
+            name: key.name,
+            prefix: key.key_prefix,
+            last_used_at: key.last_used_at,
+            created_at: key.created_at,
+        }
+    }
+}
+
+/// پاسخ ساخت API key
+///
+/// `key` فقط همین یک بار برمیگرده و بعدا قابل بازیابی نیست
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreatedApiKeyResponse {
+    #[serde(flatten)]
+    pub api_key: ApiKeyResponse,
+    
+    pub key: String,
+}
@@ -0,0 +1,221 @@
+//! # مدل کلیک (Click Event)
+//!
+//! Entity و DTO‌های مربوط به ثبت هر کلیک روی لینک کوتاه
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// حداکثر طول User-Agent ذخیره شده
+const MAX_USER_AGENT_LENGTH: usize = 512;
+
+/// حداکثر طول Referer ذخیره شده
+const MAX_REFERER_LENGTH: usize = 2048;
+
+// =====================================
+// Click Event Entity
+// =====================================
+/// Entity یک کلیک (ردیف جدول `click_events`)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ClickEvent {
+    pub id: String,
+    
+    /// شناسه URL کلیک شده
+    pub url_id: String,
+    
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub referer: Option<String>,
+    pub country: Option<String>,
+    pub clicked_at: DateTime<Utc>,
+}
+
+// =====================================
+// Click Info
+// =====================================
+/// اطلاعات کلاینت که در لحظه redirect از request استخراج میشه
+///
+/// # مفاهیم:
+/// - handler این رو از extractor‌ها میسازه
+/// - سرویس نیازی به دونستن جزئیات HTTP نداره
+#[derive(Debug, Clone, Default)]
+pub struct ClickInfo {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub referer: Option<String>,
+    /// کد دو حرفی کشور (ISO 3166-1) از header CDN
+    pub country: Option<String>,
+}
+
+// =====================================
+// Create Click Event DTO
+// =====================================
+/// داده برای ثبت کلیک جدید (داخلی)
+#[derive(Debug, Clone)]
+pub struct CreateClickEvent {
+    pub id: String,
+    pub url_id: String,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub referer: Option<String>,
+    pub country: Option<String>,
+    /// زمان redirect (نه زمان نوشتن در دیتابیس که ممکنه دیرتر باشه)
+    pub clicked_at: DateTime<Utc>,
+}
+
+impl CreateClickEvent {
+    /// ساخت رویداد کلیک برای یک URL
+    ///
+    /// مقادیر خیلی طولانی کوتاه میشن تا header‌های مخرب جدول رو پر نکنن
+    #[must_use]
+    pub fn new(url_id: impl Into<String>, info: ClickInfo) -> Self {
+        Self {
+            id: nanoid::nanoid!(21),
+            url_id: url_id.into(),
+            ip_address: info.ip_address,
+            user_agent: info.user_agent
+                .map(|ua| crate::utils::truncate(&ua, Some(MAX_USER_AGENT_LENGTH))),
+            referer: info.referer
+                .map(|r| crate::utils::truncate(&r, Some(MAX_REFERER_LENGTH))),
+            country: info.country,
+            clicked_at: Utc::now(),
+        }
+    }
+}
+
+// =====================================
+// Click Batch
+// =====================================
+/// کلیک‌های جمع شده که با هم در یک تراکنش نوشته میشن (داخلی)
+///
+/// # مفاهیم:
+/// - افزایش شمارنده هر URL فقط یک بار با مجموع کلیک‌ها انجام میشه
+/// - کلید `url_id` هست نه short_code، تا اگه لینک در این فاصله حذف و کدش
+///   دوباره استفاده بشه کلیک‌ها به لینک جدید نرسن
+/// - رویدادهای تکی همه نگه داشته میشن چون analytics بهشون نیاز داره
+#[derive(Debug, Clone, Default)]
+pub struct ClickBatch {
+    /// تعداد کلیک هر URL (کلید: `url_id`)
+    pub increments: HashMap<String, u64>,
+    
+    /// رویدادهای کلیک به ترتیب رسیدن
+    pub events: Vec<CreateClickEvent>,
+}
+
+impl ClickBatch {
+    /// اضافه کردن یک کلیک
+    pub fn push(&mut self, event: CreateClickEvent) {
+        *self.increments.entry(event.url_id.clone()).or_default() += 1;
+        self.events.push(event);
+    }
+    
+    /// اضافه کردن رویداد کلیکی که شمارنده‌اش قبلا زیاد شده (لینک‌های با `max_clicks`)
+    pub fn push_event(&mut self, event: CreateClickEvent) {
+        self.events.push(event);
+    }
+    
+    /// تعداد کلیک‌های این batch
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+    
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+// =====================================
+// Analytics
+// =====================================
+/// بازه زمانی برای گروه‌بندی کلیک‌ها
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AnalyticsInterval {
+    Hour,
+    #[default]
+    Day,
+    Week,
+}
+
+impl AnalyticsInterval {
+    /// بازه پیش‌فرض وقتی `from` مشخص نشده
+    #[must_use]
+    pub fn default_span(&self) -> chrono::Duration {
+        match self {
+            Self::Hour => chrono::Duration::hours(24),
+            Self::Day => chrono::Duration::days(30),
+            Self::Week => chrono::Duration::weeks(12),
+        }
+    }
+}
+
+/// ستون‌هایی که میشه روشون breakdown گرفت
+///
+/// # مفاهیم:
+/// - فقط مقادیر این enum وارد SQL میشن (جلوگیری از SQL injection)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClickDimension {
+    Referer,
+    UserAgent,
+    Country,
+}
+
+impl ClickDimension {
+    /// نام ستون در جدول `click_events`
+    #[must_use]
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            Self::Referer => "referer",
+            Self::UserAgent => "user_agent",
+            Self::Country => "country",
+        }
+    }
+}
+
+/// پارامترهای query برای analytics
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AnalyticsQuery {
+    #[serde(default)]
+    pub interval: AnalyticsInterval,
+    
+    /// شروع بازه (پیش‌فرض: `to` منهای بازه پیش‌فرض interval)
+    pub from: Option<DateTime<Utc>>,
+    
+    /// پایان بازه (پیش‌فرض: الان)
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// تعداد کلیک در یک bucket زمانی
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ClickBucket {
+    pub bucket: String,
+    pub clicks: i64,
+}
+
+/// یک مقدار پرتکرار (referrer، user agent یا کشور)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TopValue {
+    pub value: String,
+    pub clicks: i64,
+}
+
+/// پاسخ analytics یک لینک
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UrlAnalytics {
+    pub short_code: String,
+    pub interval: AnalyticsInterval,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    
+    /// تعداد کلیک‌ها در بازه
+    pub total_clicks: i64,
+    
+    pub timeline: Vec<ClickBucket>,
+    pub top_referrers: Vec<TopValue>,
+    pub top_user_agents: Vec<TopValue>,
+    pub countries: Vec<TopValue>,
+}
@@ -7,6 +7,10 @@
 //! - Request/Response separation: جداسازی ورودی از خروجی
 
 use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::Validate;
+
+use super::UrlResponse;
 
 // =====================================
 // Generic API Responses
@@ -85,6 +89,111 @@ impl HealthResponse {
             uptime_seconds: None,
         }
     }
+    
+    /// اضافه کردن uptime
+    #[must_use]
+    pub fn with_uptime(mut self, uptime_seconds: u64) -> Self {
+        self.uptime_seconds = Some(uptime_seconds);
+        self
+    }
+}
+
+/// وضعیت connection pool دیتابیس
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolStatus {
+    /// تعداد کل اتصال‌های باز
+    pub size: u32,
+    
+    /// اتصال‌های بیکار
+    pub idle: u32,
+    
+    pub max_connections: u32,
+}
+
+impl PoolStatus {
+    /// درصد اتصال‌های در حال استفاده نسبت به حداکثر
+    #[must_use]
+    pub fn utilization(&self) -> f64 {
+        if self.max_connections == 0 {
+            return 0.0;
+        }
+        
+        f64::from(self.size.saturating_sub(self.idle)) / f64::from(self.max_connections) * 100.0
+    }
+}
+
+/// نتیجه بررسی دیتابیس در readiness
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseCheck {
+    pub reachable: bool,
+    
+    /// زمان پاسخ ping (میلی‌ثانیه)
+    pub latency_ms: u64,
+    
+    /// `None` اگه دیتابیس در دسترس نباشه
+    pub pending_migrations: Option<usize>,
+    
+    pub pool: PoolStatus,
+    
+    /// درصد استفاده از pool
+    pub pool_utilization: f64,
+    
+    /// خطای ping (دیتابیس در دسترس نیست)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    
+    /// خطای خوندن وضعیت migration‌ها؛ دیتابیس در دسترسه ولی not ready
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub migration_error: Option<String>,
+}
+
+impl DatabaseCheck {
+    /// نتیجه برای ذخیره‌سازی در حافظه: همیشه در دسترس، بدون migration و pool
+    #[must_use]
+    pub fn in_memory() -> Self {
+        let pool = PoolStatus { size: 0, idle: 0, max_connections: 0 };
+        
+        Self {
+            reachable: true,
+            latency_ms: 0,
+            pending_migrations: Some(0),
+            pool,
+            pool_utilization: pool.utilization(),
+            error: None,
+            migration_error: None,
+        }
+    }
+}
+
+/// پاسخ readiness probe
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadinessResponse {
+    /// `ready` یا `not_ready`
+    pub status: String,
+    pub version: String,
+    pub uptime_seconds: u64,
+    pub database: DatabaseCheck,
+}
+
+impl ReadinessResponse {
+    /// آماده دریافت ترافیک: دیتابیس در دسترس و همه migration‌ها اجرا شده
+    #[must_use]
+    pub fn new(database: DatabaseCheck, uptime_seconds: u64) -> Self {
+        let ready = database.reachable && database.pending_migrations == Some(0);
+        
+        Self {
+            status: if ready { "ready" } else { "not_ready" }.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            uptime_seconds,
+            database,
+        }
+    }
+    
+    /// آیا سرویس آماده‌ست؟
+    #[must_use]
+    pub fn is_ready(&self) -> bool {
+        self.status == "ready"
+    }
 }
 
 // =====================================
@@ -97,6 +206,70 @@ pub struct SystemStats {
     pub total_clicks: i64,
     pub total_users: i64,
     pub average_clicks_per_url: f64,
+    
+    /// کاربرانی که غیرفعال نشدن
+    pub active_users: i64,
+    
+    /// لینک‌های بدون انقضا یا با انقضای آینده
+    pub active_urls: i64,
+    pub expired_urls: i64,
+    
+    /// تعداد لینک ساخته شده در هر روز (روزهای بدون لینک با صفر)
+    pub urls_created_per_day: Vec<DailyCount>,
+    
+    /// پرکلیک‌ترین لینک‌ها
+    pub top_urls: Vec<UrlResponse>,
+    
+    /// وضعیت cache ریدایرکت همین instance
+    pub redirect_cache: RedirectCacheStats,
+    
+    /// وضعیت صف ثبت کلیک‌های همین instance
+    pub click_writer: ClickWriterStats,
+}
+
+/// آمار cache ریدایرکت
+///
+/// شمارنده‌ها از شروع برنامه جمع میشن؛ ورودی‌های منفی (کد ناموجود) هم hit حساب میشن
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RedirectCacheStats {
+    pub enabled: bool,
+    pub capacity: usize,
+    /// تعداد ورودی‌های فعلی (شامل منقضی شده‌هایی که هنوز بیرون نرفتن)
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// نسبت hit به کل درخواست‌ها (۰ تا ۱)
+    pub hit_ratio: f64,
+}
+
+/// آمار نوشتن دسته‌ای کلیک‌ها
+///
+/// شمارنده‌ها از شروع برنامه جمع میشن. `queue_full` زیاد یعنی دیتابیس از
+/// ترافیک ریدایرکت عقب افتاده و redirect‌ها منتظر جا در صف موندن.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClickWriterStats {
+    pub queue_capacity: usize,
+    /// کلیک‌های داخل صف که هنوز به batch نرسیدن
+    pub queued: usize,
+    /// کلیک‌های batch فعلی که منتظر flush هستن
+    pub buffered: u64,
+    /// کل کلیک‌هایی که وارد صف شدن
+    pub enqueued: u64,
+    /// رویدادهای ثبت شده در دیتابیس
+    pub recorded: u64,
+    /// کلیک‌هایی که به خاطر خطای دیتابیس از دست رفتن
+    pub failed: u64,
+    /// تعداد flush‌های موفق
+    pub batches: u64,
+    /// دفعاتی که صف پر بود و redirect منتظر موند
+    pub queue_full: u64,
+}
+
+/// تعداد در یک روز (تاریخ به فرمت `YYYY-MM-DD`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct DailyCount {
+    pub date: String,
+    pub count: i64,
 }
 
 // =====================================
@@ -126,8 +299,10 @@ pub struct SearchParams {
 // Batch Operations
 // =====================================
 /// درخواست حذف دسته‌ای
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Validate)]
 pub struct BatchDeleteRequest {
+    /// شناسه URL‌ها (فیلد `id`، نه short_code)
+    #[validate(length(min = 1, max = 1000, message = "Between 1 and 1000 ids are allowed"))]
     pub ids: Vec<String>,
 }
 
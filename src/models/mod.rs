@@ -18,11 +18,17 @@
 
 mod url;
 mod user;
+mod click;
+mod refresh_token;
+mod api_key;
 mod dto;
 
 // Re-export همه مدل‌ها
 pub use url::*;
 pub use user::*;
+pub use click::*;
+pub use refresh_token::*;
+pub use api_key::*;
 pub use dto::*;
 
 use chrono::{DateTime, Utc};
@@ -191,6 +197,17 @@ impl Pagination {
     pub fn limit(&self) -> u32 {
         self.per_page.min(100) // حداکثر 100
     }
+    
+    /// نسخه محدود شده (page >= 1 و 1 <= per_page <= 100)
+    ///
+    /// تا offset، limit و `PaginationInfo` همه با یک per_page حساب بشن
+    #[must_use]
+    pub fn normalized(&self) -> Self {
+        Self {
+            page: self.page.max(1),
+            per_page: self.limit().max(1),
+        }
+    }
 }
 
 /// نتیجه صفحه‌بندی شده
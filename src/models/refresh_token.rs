@@ -0,0 +1,116 @@
+//! # مدل Refresh Token
+//!
+//! توکن‌های طولانی‌مدت و opaque برای گرفتن access token جدید
+//!
+//! ## مفاهیم:
+//! - **Opaque token**: یه رشته تصادفی بدون اطلاعات داخلی (برخلاف JWT)
+//! - **Rotation**: هر بار استفاده، توکن با یه توکن جدید عوض میشه
+//! - **Reuse detection**: استفاده دوباره از توکن rotate شده یعنی دزدی
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::FromRow;
+use validator::Validate;
+
+/// طول توکن تولید شده (کاراکتر)
+const REFRESH_TOKEN_LENGTH: usize = 64;
+
+// =====================================
+// Refresh Token Entity
+// =====================================
+/// Entity یک refresh token (ردیف جدول `refresh_tokens`)
+///
+/// خود توکن هیچوقت ذخیره نمیشه، فقط هش اون
+#[derive(Debug, Clone, FromRow)]
+pub struct RefreshToken {
+    pub id: String,
+    pub user_id: String,
+    
+    /// هش SHA-256 توکن (hex)
+    pub token_hash: String,
+    
+    /// همه توکن‌های حاصل از یک ورود family یکسان دارن
+    pub family_id: String,
+    
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    
+    /// توکنی که بعد از rotation جایگزین این یکی شده
+    pub replaced_by: Option<String>,
+    
+    pub created_at: DateTime<Utc>,
+}
+
+impl RefreshToken {
+    /// آیا توکن منقضی شده؟
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        self.expires_at < Utc::now()
+    }
+    
+    /// آیا توکن باطل شده (rotate یا logout)؟
+    #[must_use]
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+}
+
+// =====================================
+// Create Refresh Token DTO
+// =====================================
+/// داده برای ذخیره refresh token جدید (داخلی)
+#[derive(Debug, Clone)]
+pub struct CreateRefreshToken {
+    pub id: String,
+    pub user_id: String,
+    pub token_hash: String,
+    pub family_id: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl CreateRefreshToken {
+    /// تولید یه refresh token تصادفی جدید
+    ///
+    /// # مفاهیم:
+    /// - خروجی دوم توکن خام هست که فقط یک بار به کلاینت داده میشه
+    /// - `family_id` برای rotation از توکن قبلی میاد؛ برای ورود جدید `None`
+    #[must_use]
+    pub fn generate(
+        user_id: impl Into<String>,
+        family_id: Option<String>,
+        ttl: chrono::Duration,
+    ) -> (Self, String) {
+        let token = nanoid::nanoid!(REFRESH_TOKEN_LENGTH);
+        
+        let record = Self {
+            id: nanoid::nanoid!(21),
+            user_id: user_id.into(),
+            token_hash: hash_refresh_token(&token),
+            family_id: family_id.unwrap_or_else(|| nanoid::nanoid!(21)),
+            expires_at: Utc::now() + ttl,
+        };
+        
+        (record, token)
+    }
+}
+
+/// هش SHA-256 یک refresh token
+///
+/// # مفاهیم:
+/// - توکن‌ها تصادفی و طولانی هستن، پس برخلاف رمز عبور نیازی به Argon2 نیست
+/// - هش قطعی اجازه میده با ایندکس پیداش کنیم
+#[must_use]
+pub fn hash_refresh_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+// =====================================
+// API Request DTOs
+// =====================================
+/// درخواست refresh یا logout
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct RefreshTokenRequest {
+    #[validate(length(min = 1, message = "Refresh token is required"))]
+    pub refresh_token: String,
+}
@@ -3,10 +3,13 @@
 //! Entity و DTO‌های مربوط به URL
 
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use sqlx::FromRow;
 use validator::Validate;
 
+use super::{SearchParams, SortOrder};
+use crate::error::AppError;
+
 // =====================================
 // URL Entity
 // =====================================
@@ -36,9 +39,22 @@ pub struct Url {
     /// شناسه کاربر مالک (اختیاری)
     pub user_id: Option<String>,
     
+    /// زمان شروع (اختیاری) - قبلش لینک باز نمیشه
+    pub starts_at: Option<DateTime<Utc>>,
+    
     /// تاریخ انقضا (اختیاری)
     pub expires_at: Option<DateTime<Utc>>,
     
+    /// status code ریدایرکت
+    pub redirect_type: RedirectType,
+    
+    /// هش Argon2 رمز لینک (اختیاری) - هرگز به کلاینت ارسال نمیشه
+    #[serde(skip)]
+    pub password_hash: Option<String>,
+    
+    /// حداکثر تعداد کلیک (اختیاری) - بعدش لینک دیگه باز نمیشه
+    pub max_clicks: Option<i64>,
+    
     /// تاریخ ایجاد
     pub created_at: DateTime<Utc>,
     
@@ -55,7 +71,25 @@ impl Url {
     #[must_use]
     pub fn is_expired(&self) -> bool {
         self.expires_at
-            .map_or(false, |exp| exp < Utc::now())
+            .is_some_and(|exp| exp < Utc::now())
+    }
+    
+    /// آیا زمان شروع لینک هنوز نرسیده؟
+    #[must_use]
+    pub fn is_not_started(&self) -> bool {
+        self.starts_at.is_some_and(|start| start > Utc::now())
+    }
+    
+    /// آیا سقف کلیک پر شده؟
+    #[must_use]
+    pub fn is_exhausted(&self) -> bool {
+        self.max_clicks.is_some_and(|max| self.clicks >= max)
+    }
+    
+    /// آیا برای باز کردن لینک رمز لازمه؟
+    #[must_use]
+    pub fn is_password_protected(&self) -> bool {
+        self.password_hash.is_some()
     }
     
     /// گرفتن لینک کوتاه کامل
@@ -65,6 +99,67 @@ impl Url {
     }
 }
 
+// =====================================
+// Redirect Type
+// =====================================
+/// status code ریدایرکت یک لینک
+///
+/// # مفاهیم:
+/// - `#[repr(i32)]` + `sqlx::Type`: ذخیره به صورت عدد (همون status code) در دیتابیس
+/// - `#[serde(try_from, into)]`: در JSON و config هم عدد هست (`301`، `308`، ...)
+/// - نوع‌های دائمی (301/308) توسط مرورگر و موتورهای جستجو cache میشن؛
+///   تغییر مقصد بعدا به کاربرهای قبلی نمیرسه
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, sqlx::Type)]
+#[serde(try_from = "u16", into = "u16")]
+#[repr(i32)]
+pub enum RedirectType {
+    /// 301 - دائمی (برای SEO)
+    MovedPermanently = 301,
+    
+    /// 302 - موقت (برای کلاینت‌های قدیمی)
+    Found = 302,
+    
+    /// 307 - موقت، متد و body درخواست حفظ میشه
+    #[default]
+    TemporaryRedirect = 307,
+    
+    /// 308 - دائمی، متد و body درخواست حفظ میشه
+    PermanentRedirect = 308,
+}
+
+impl RedirectType {
+    /// status code HTTP
+    #[must_use]
+    pub fn as_u16(self) -> u16 {
+        match self {
+            Self::MovedPermanently => 301,
+            Self::Found => 302,
+            Self::TemporaryRedirect => 307,
+            Self::PermanentRedirect => 308,
+        }
+    }
+}
+
+impl TryFrom<u16> for RedirectType {
+    type Error = String;
+    
+    fn try_from(code: u16) -> std::result::Result<Self, Self::Error> {
+        match code {
+            301 => Ok(Self::MovedPermanently),
+            302 => Ok(Self::Found),
+            307 => Ok(Self::TemporaryRedirect),
+            308 => Ok(Self::PermanentRedirect),
+            other => Err(format!("Invalid redirect type {other}, expected 301, 302, 307 or 308")),
+        }
+    }
+}
+
+impl From<RedirectType> for u16 {
+    fn from(redirect_type: RedirectType) -> Self {
+        redirect_type.as_u16()
+    }
+}
+
 // =====================================
 // Create URL DTO
 // =====================================
@@ -78,7 +173,11 @@ pub struct CreateUrl {
     pub original_url: String,
     pub title: Option<String>,
     pub user_id: Option<String>,
+    pub starts_at: Option<DateTime<Utc>>,
     pub expires_at: Option<DateTime<Utc>>,
+    pub redirect_type: RedirectType,
+    pub password_hash: Option<String>,
+    pub max_clicks: Option<i64>,
 }
 
 // =====================================
@@ -99,26 +198,92 @@ pub struct CreateUrlRequest {
     
     /// کد سفارشی (اختیاری)
     #[validate(length(min = 3, max = 20, message = "Custom code must be 3-20 characters"))]
-    #[validate(regex(path = "crate::utils::VALID_SHORT_CODE", message = "Invalid custom code format"))]
+    #[validate(regex(path = *crate::utils::VALID_SHORT_CODE, message = "Invalid custom code format"))]
     pub custom_code: Option<String>,
     
     /// عنوان (اختیاری)
     #[validate(length(max = 200, message = "Title is too long"))]
     pub title: Option<String>,
     
+    /// زمان شروع (اختیاری) - لینک قبل از این زمان باز نمیشه
+    pub starts_at: Option<DateTime<Utc>>,
+    
     /// مدت اعتبار به ساعت (اختیاری)
     pub expires_in_hours: Option<u32>,
+    
+    /// status code ریدایرکت (اختیاری، پیش‌فرض از `DEFAULT_REDIRECT_TYPE`)
+    pub redirect_type: Option<RedirectType>,
+    
+    /// رمز باز کردن لینک (اختیاری)
+    #[validate(length(min = 4, max = 128, message = "Link password must be 4-128 characters"))]
+    pub password: Option<String>,
+    
+    /// حداکثر تعداد کلیک (اختیاری، 1 = لینک یک‌بار مصرف)
+    #[validate(range(min = 1, message = "max_clicks must be at least 1"))]
+    pub max_clicks: Option<u32>,
 }
 
 /// درخواست بروزرسانی URL
-#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+///
+/// فیلدهای `None` تغییر نمیکنن؛ مقدار خالی (`""` یا `0`) فیلد رو پاک میکنه.
+/// فیلدهای `Option<Option<_>>` با `null` صریح پاک میشن
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
 pub struct UpdateUrlRequest {
-    /// عنوان جدید
+    /// آدرس مقصد جدید
+    #[validate(url(message = "Invalid URL format"))]
+    #[validate(length(max = 2048, message = "URL is too long"))]
+    pub url: Option<String>,
+    
+    /// عنوان جدید؛ رشته خالی عنوان رو حذف میکنه
     #[validate(length(max = 200, message = "Title is too long"))]
     pub title: Option<String>,
     
-    /// مدت اعتبار جدید
+    /// مدت اعتبار جدید؛ `0` انقضا رو حذف میکنه
     pub expires_in_hours: Option<u32>,
+    
+    /// status code ریدایرکت جدید
+    pub redirect_type: Option<RedirectType>,
+    
+    /// رمز جدید لینک؛ `null` یا رشته خالی رمز رو حذف میکنه
+    #[serde(default, deserialize_with = "null_or_empty", skip_serializing_if = "Option::is_none")]
+    #[validate(length(min = 4, max = 128, message = "Link password must be 4-128 characters"))]
+    pub password: Option<Option<String>>,
+    
+    /// زمان شروع جدید؛ `null` لینک رو از همین حالا باز میکنه
+    #[serde(default, deserialize_with = "explicit_null", skip_serializing_if = "Option::is_none")]
+    pub starts_at: Option<Option<DateTime<Utc>>>,
+    
+    /// سقف کلیک جدید؛ `null` سقف رو حذف میکنه
+    #[serde(default, deserialize_with = "explicit_null", skip_serializing_if = "Option::is_none")]
+    #[validate(range(min = 1, message = "max_clicks must be at least 1"))]
+    pub max_clicks: Option<Option<u32>>,
+}
+
+/// فرق گذاشتن بین فیلد ارسال نشده (`None`) و `null` صریح (`Some(None)`)
+///
+/// همراه `#[serde(default)]` استفاده میشه تا نبودن فیلد `None` بمونه
+fn explicit_null<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// مثل `explicit_null`، ولی رشته خالی هم مثل `null` فیلد رو پاک میکنه
+fn null_or_empty<'de, D>(deserializer: D) -> Result<Option<Option<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: Option<Option<String>> = explicit_null(deserializer)?;
+    
+    Ok(value.map(|password| password.filter(|password| !password.is_empty())))
+}
+
+/// فرم باز کردن لینک رمزدار (`POST /:code`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct UnlockUrlRequest {
+    pub password: String,
 }
 
 // =====================================
@@ -135,7 +300,11 @@ pub struct UrlResponse {
     pub original_url: String,
     pub title: Option<String>,
     pub clicks: i64,
+    pub starts_at: Option<DateTime<Utc>>,
     pub expires_at: Option<DateTime<Utc>>,
+    pub redirect_type: RedirectType,
+    pub password_protected: bool,
+    pub max_clicks: Option<i64>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -154,7 +323,11 @@ impl UrlResponse {
             original_url: url.original_url.clone(),
             title: url.title.clone(),
             clicks: url.clicks,
+            starts_at: url.starts_at,
             expires_at: url.expires_at,
+            redirect_type: url.redirect_type,
+            password_protected: url.is_password_protected(),
+            max_clicks: url.max_clicks,
             created_at: url.created_at,
         }
     }
@@ -166,6 +339,94 @@ pub struct RedirectResponse {
     pub original_url: String,
 }
 
+// =====================================
+// Search & Filter
+// =====================================
+/// فیلتر وضعیت لینک
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrlStatusFilter {
+    /// بدون انقضا یا هنوز منقضی نشده
+    Active,
+    /// تاریخ انقضا گذشته
+    Expired,
+}
+
+/// فیلد مرتب‌سازی لیست URL‌ها
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UrlSortField {
+    #[default]
+    CreatedAt,
+    Clicks,
+}
+
+impl UrlSortField {
+    /// نام ستون در SQL
+    #[must_use]
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            Self::CreatedAt => "created_at",
+            Self::Clicks => "clicks",
+        }
+    }
+}
+
+/// فیلتر تایپ‌شده برای جستجو در URL‌ها
+///
+/// # مفاهیم:
+/// - `SearchParams` رشته‌های خام query string هستن
+/// - این struct فقط مقادیر مجاز رو نگه میداره، پس ساخت SQL امنه
+#[derive(Debug, Clone, Default)]
+pub struct UrlFilter {
+    /// جستجو در عنوان یا آدرس مقصد
+    pub query: Option<String>,
+    pub status: Option<UrlStatusFilter>,
+    pub sort_by: UrlSortField,
+    pub order: SortOrder,
+}
+
+impl UrlFilter {
+    /// ساخت فیلتر از پارامترهای query string
+    ///
+    /// ترتیب پیش‌فرض نزولی هست (جدیدترین/پرکلیک‌ترین اول)
+    ///
+    /// # Errors
+    /// `BadRequest` اگه مقدار `status`، `sort_by` یا `order` نامعتبر باشه
+    pub fn from_params(params: &SearchParams) -> crate::error::Result<Self> {
+        let query = params.query
+            .as_deref()
+            .map(str::trim)
+            .filter(|q| !q.is_empty())
+            .map(ToString::to_string);
+        
+        let status = match params.status.as_deref() {
+            None | Some("all") => None,
+            Some("active") => Some(UrlStatusFilter::Active),
+            Some("expired") => Some(UrlStatusFilter::Expired),
+            Some(other) => return Err(AppError::BadRequest(
+                format!("Invalid status '{}', expected active, expired or all", other)
+            )),
+        };
+        
+        let sort_by = match params.sort_by.as_deref() {
+            None | Some("created_at") => UrlSortField::CreatedAt,
+            Some("clicks") => UrlSortField::Clicks,
+            Some(other) => return Err(AppError::BadRequest(
+                format!("Invalid sort_by '{}', expected created_at or clicks", other)
+            )),
+        };
+        
+        let order = match params.order.as_deref() {
+            None | Some("desc") => SortOrder::Desc,
+            Some("asc") => SortOrder::Asc,
+            Some(other) => return Err(AppError::BadRequest(
+                format!("Invalid order '{}', expected asc or desc", other)
+            )),
+        };
+        
+        Ok(Self { query, status, sort_by, order })
+    }
+}
+
 // =====================================
 // URL Builder (Builder Pattern)
 // =====================================
@@ -189,7 +450,11 @@ pub struct UrlBuilder {
     short_code: Option<String>,
     title: Option<String>,
     user_id: Option<String>,
+    starts_at: Option<DateTime<Utc>>,
     expires_at: Option<DateTime<Utc>>,
+    redirect_type: RedirectType,
+    password_hash: Option<String>,
+    max_clicks: Option<i64>,
 }
 
 impl UrlBuilder {
@@ -223,6 +488,13 @@ impl UrlBuilder {
         self
     }
     
+    /// تنظیم زمان شروع (لینک قبلش باز نمیشه)
+    #[must_use]
+    pub fn starts_at(mut self, starts_at: DateTime<Utc>) -> Self {
+        self.starts_at = Some(starts_at);
+        self
+    }
+    
     /// تنظیم تاریخ انقضا
     #[must_use]
     pub fn expires_at(mut self, expires_at: DateTime<Utc>) -> Self {
@@ -238,16 +510,46 @@ impl UrlBuilder {
         self
     }
     
+    /// تنظیم status code ریدایرکت
+    #[must_use]
+    pub fn redirect_type(mut self, redirect_type: RedirectType) -> Self {
+        self.redirect_type = redirect_type;
+        self
+    }
+    
+    /// تنظیم هش رمز لینک (خروجی `hash_password`)
+    #[must_use]
+    pub fn password_hash(mut self, password_hash: impl Into<String>) -> Self {
+        self.password_hash = Some(password_hash.into());
+        self
+    }
+    
+    /// تنظیم سقف کلیک (1 = یک‌بار مصرف)
+    #[must_use]
+    pub fn max_clicks(mut self, max_clicks: u32) -> Self {
+        self.max_clicks = Some(i64::from(max_clicks));
+        self
+    }
+    
     /// ساخت CreateUrl
     ///
     /// # Errors
-    /// خطا برمیگردونه اگه URL اصلی تنظیم نشده باشه
+    /// خطا برمیگردونه اگه URL اصلی تنظیم نشده باشه یا زمان شروع بعد از انقضا باشه
     pub fn build(self) -> crate::error::Result<CreateUrl> {
         let original_url = self.original_url
             .ok_or_else(|| crate::error::AppError::BadRequest(
                 "Original URL is required".to_string()
             ))?;
         
+        // لینکی که قبل از شروع منقضی بشه هیچوقت باز نمیشه
+        if let (Some(starts_at), Some(expires_at)) = (self.starts_at, self.expires_at) {
+            if starts_at >= expires_at {
+                return Err(crate::error::AppError::BadRequest(
+                    "starts_at must be before the expiration time".to_string()
+                ));
+            }
+        }
+        
         // اگه کد سفارشی نداریم، یکی تولید میکنیم
         let short_code = self.short_code
             .unwrap_or_else(crate::utils::generate_short_code);
@@ -258,7 +560,11 @@ impl UrlBuilder {
             original_url,
             title: self.title,
             user_id: self.user_id,
+            starts_at: self.starts_at,
             expires_at: self.expires_at,
+            redirect_type: self.redirect_type,
+            password_hash: self.password_hash,
+            max_clicks: self.max_clicks,
         })
     }
 }
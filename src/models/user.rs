@@ -26,6 +26,11 @@ pub struct User {
     
     pub name: Option<String>,
     pub is_active: bool,
+    pub role: UserRole,
+    
+    /// زمان آخرین تغییر رمز عبور - توکن‌های قبل از این زمان باطل هستن
+    pub password_changed_at: Option<DateTime<Utc>>,
+    
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -40,17 +45,58 @@ impl User {
     /// # Errors
     /// خطا برمیگردونه اگه verification fail بشه
     pub fn verify_password(&self, password: &str) -> crate::error::Result<bool> {
-        use argon2::{Argon2, PasswordHash, PasswordVerifier};
-        
-        let parsed_hash = PasswordHash::new(&self.password_hash)
-            .map_err(|e| crate::error::AppError::Internal(e.to_string()))?;
-        
-        Ok(Argon2::default()
-            .verify_password(password.as_bytes(), &parsed_hash)
-            .is_ok())
+        verify_password(&self.password_hash, password)
+    }
+    
+    /// آیا توکنی که در `issued_at` صادر شده بعد از تغییر رمز هنوز معتبره؟
+    ///
+    /// مقایسه با دقت میکروثانیه هست، پس توکنی که در همون ثانیه ولی قبل از
+    /// تغییر رمز صادر شده رد میشه
+    #[must_use]
+    pub fn accepts_token_issued_at(&self, issued_at: f64) -> bool {
+        let issued_at_micros = (issued_at * 1_000_000.0).round() as i64;
+        self.password_changed_at
+            .is_none_or(|changed_at| issued_at_micros >= changed_at.timestamp_micros())
     }
 }
 
+/// هش کردن رمز عبور با Argon2
+///
+/// # مفاهیم:
+/// - Salt تصادفی برای هر هش
+/// - خروجی در فرمت PHC (شامل الگوریتم، پارامترها و salt)
+///
+/// # Errors
+/// خطا برمیگردونه اگه hashing fail بشه
+pub fn hash_password(password: &str) -> crate::error::Result<String> {
+    use argon2::{
+        password_hash::{rand_core::OsRng, SaltString},
+        Argon2, PasswordHasher,
+    };
+    
+    let salt = SaltString::generate(&mut OsRng);
+    
+    Ok(Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| crate::error::AppError::Internal(e.to_string()))?
+        .to_string())
+}
+
+/// بررسی رمز در برابر هشی که `hash_password` ساخته
+///
+/// # Errors
+/// خطا برمیگردونه اگه هش ذخیره شده خراب باشه
+pub fn verify_password(password_hash: &str, password: &str) -> crate::error::Result<bool> {
+    use argon2::{Argon2, PasswordHash, PasswordVerifier};
+    
+    let parsed_hash = PasswordHash::new(password_hash)
+        .map_err(|e| crate::error::AppError::Internal(e.to_string()))?;
+    
+    Ok(Argon2::default()
+        .verify_password(password.as_bytes(), &parsed_hash)
+        .is_ok())
+}
+
 /// تبدیل User به UserResponse
 ///
 /// # مفاهیم:
@@ -63,6 +109,7 @@ impl From<User> for UserResponse {
             email: user.email,
             name: user.name,
             is_active: user.is_active,
+            role: user.role,
             created_at: user.created_at,
         }
     }
@@ -76,11 +123,38 @@ impl From<&User> for UserResponse {
             email: user.email.clone(),
             name: user.name.clone(),
             is_active: user.is_active,
+            role: user.role,
             created_at: user.created_at,
         }
     }
 }
 
+// =====================================
+// User Role
+// =====================================
+/// نقش کاربر
+///
+/// # مفاهیم:
+/// - `sqlx::Type`: ذخیره enum به صورت TEXT در دیتابیس
+/// - `type_name = "text"`: در PostgreSQL ستون TEXT هست نه enum نوع‌دار
+/// - کاربرهای جدید همیشه `User` هستن؛ ادمین‌ها نقش رو تغییر میدن
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum UserRole {
+    #[default]
+    User,
+    Admin,
+}
+
+impl UserRole {
+    /// آیا این نقش دسترسی ادمین داره؟
+    #[must_use]
+    pub fn is_admin(&self) -> bool {
+        matches!(self, Self::Admin)
+    }
+}
+
 // =====================================
 // Create User DTO
 // =====================================
@@ -107,18 +181,7 @@ impl CreateUser {
         password: &str,
         name: Option<String>,
     ) -> crate::error::Result<Self> {
-        use argon2::{
-            password_hash::{rand_core::OsRng, SaltString},
-            Argon2, PasswordHasher,
-        };
-        
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        
-        let password_hash = argon2
-            .hash_password(password.as_bytes(), &salt)
-            .map_err(|e| crate::error::AppError::Internal(e.to_string()))?
-            .to_string();
+        let password_hash = hash_password(password)?;
         
         Ok(Self {
             id: nanoid::nanoid!(21),
@@ -156,10 +219,24 @@ pub struct LoginRequest {
 }
 
 /// درخواست بروزرسانی پروفایل
-#[derive(Debug, Clone, Deserialize, Validate)]
+///
+/// فیلدهای `None` دست نمیخورن
+#[derive(Debug, Clone, Default, Deserialize, Validate)]
 pub struct UpdateProfileRequest {
     #[validate(length(min = 2, max = 100, message = "Name must be 2-100 characters"))]
     pub name: Option<String>,
+    
+    #[validate(email(message = "Invalid email format"))]
+    pub email: Option<String>,
+}
+
+/// درخواست ادمین برای تغییر وضعیت یا نقش یک کاربر
+///
+/// فیلدهای `None` دست نمیخورن
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AdminUpdateUserRequest {
+    pub is_active: Option<bool>,
+    pub role: Option<UserRole>,
 }
 
 /// درخواست تغییر رمز عبور
@@ -184,6 +261,7 @@ pub struct UserResponse {
     pub email: String,
     pub name: Option<String>,
     pub is_active: bool,
+    pub role: UserRole,
     pub created_at: DateTime<Utc>,
 }
 
@@ -191,8 +269,14 @@ pub struct UserResponse {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoginResponse {
     pub user: UserResponse,
+    
+    /// Access token کوتاه‌مدت (JWT)
     pub token: String,
     pub expires_at: DateTime<Utc>,
+    
+    /// Refresh token طولانی‌مدت برای `POST /api/auth/refresh`
+    pub refresh_token: String,
+    pub refresh_expires_at: DateTime<Utc>,
 }
 
 /// پاسخ ثبت‌نام موفق
@@ -223,8 +307,8 @@ pub struct Claims {
     /// زمان انقضا (Unix timestamp)
     pub exp: i64,
     
-    /// زمان صدور
-    pub iat: i64,
+    /// زمان صدور (Unix timestamp با کسر ثانیه تا با تغییر رمز دقیق مقایسه بشه)
+    pub iat: f64,
 }
 
 impl Claims {
@@ -238,7 +322,7 @@ impl Claims {
             sub: user_id.to_string(),
             email: email.to_string(),
             exp: exp.timestamp(),
-            iat: now.timestamp(),
+            iat: now.timestamp_micros() as f64 / 1_000_000.0,
         }
     }
     
@@ -249,3 +333,42 @@ impl Claims {
     }
 }
 
+// =====================================
+// User Filter
+// =====================================
+/// فیلتر اعتبارسنجی شده برای جستجوی کاربران (پنل ادمین)
+#[derive(Debug, Clone, Default)]
+pub struct UserFilter {
+    /// جستجو در ایمیل یا نام
+    pub query: Option<String>,
+    
+    /// `Some(true)` فقط فعال‌ها، `Some(false)` فقط غیرفعال‌ها
+    pub is_active: Option<bool>,
+}
+
+impl UserFilter {
+    /// ساخت فیلتر از پارامترهای query string
+    ///
+    /// # Errors
+    /// `BadRequest` اگه مقدار `status` نامعتبر باشه
+    pub fn from_params(params: &super::SearchParams) -> crate::error::Result<Self> {
+        let query = params.query
+            .as_deref()
+            .map(str::trim)
+            .filter(|q| !q.is_empty())
+            .map(ToString::to_string);
+        
+        let is_active = match params.status.as_deref() {
+            None | Some("all") => None,
+            Some("active") => Some(true),
+            Some("inactive") => Some(false),
+            Some(other) => return Err(crate::error::AppError::BadRequest(
+                format!("Invalid status '{}', expected active, inactive or all", other)
+            )),
+        };
+        
+        Ok(Self { query, is_active })
+    }
+}
+
+
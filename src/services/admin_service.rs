@@ -0,0 +1,256 @@
+//! # سرویس ادمین (Admin Service)
+//!
+//! عملیات مدیریتی روی همه کاربران و لینک‌ها
+//!
+//! ## مفاهیم:
+//! - بررسی نقش ادمین در extractor `AdminUser` انجام میشه، نه اینجا
+//! - این سرویس محدودیت مالکیت نداره
+
+use std::sync::Arc;
+use tracing::{info, instrument};
+
+use crate::{
+    config::Config,
+    database::{UrlStore, UserStore},
+    error::{AppError, OptionExt, Result},
+    models::{
+        AdminUpdateUserRequest, PaginatedResult, Pagination, SearchParams, UrlFilter,
+        UrlResponse, UserFilter, UserResponse,
+    },
+};
+
+use super::{RedirectCache, Service};
+
+// =====================================
+// Admin Service
+// =====================================
+/// سرویس ادمین
+///
+/// # مسئولیت‌ها:
+/// - جستجو و مدیریت کاربران (غیرفعال کردن، تغییر نقش)
+/// - لیست و حذف هر لینکی
+#[derive(Debug, Clone)]
+pub struct AdminService {
+    user_repo: Arc<dyn UserStore>,
+    url_repo: Arc<dyn UrlStore>,
+    redirect_cache: Arc<RedirectCache>,
+    config: Arc<Config>,
+}
+
+impl Service for AdminService {}
+
+impl AdminService {
+    /// ساخت سرویس جدید
+    #[must_use]
+    pub fn new(
+        user_repo: Arc<dyn UserStore>,
+        url_repo: Arc<dyn UrlStore>,
+        redirect_cache: Arc<RedirectCache>,
+        config: Arc<Config>,
+    ) -> Self {
+        Self { user_repo, url_repo, redirect_cache, config }
+    }
+    
+    /// آیا این کاربر ادمین فعال هست؟
+    pub async fn is_admin(&self, user_id: &str) -> Result<bool> {
+        let user = self.user_repo.find_by_id(user_id).await?;
+        
+        Ok(user.is_some_and(|u| u.is_active && u.role.is_admin()))
+    }
+    
+    /// جستجو و صفحه‌بندی کاربران
+    pub async fn list_users(
+        &self,
+        search: &SearchParams,
+        pagination: &Pagination,
+    ) -> Result<PaginatedResult<UserResponse>> {
+        let filter = UserFilter::from_params(search)?;
+        let pagination = pagination.normalized();
+        
+        let (users, total) = self.user_repo.search(&filter, &pagination).await?;
+        let users: Vec<UserResponse> = users.into_iter().map(Into::into).collect();
+        
+        Ok(PaginatedResult::new(users, &pagination, total))
+    }
+    
+    /// تغییر وضعیت یا نقش یک کاربر
+    ///
+    /// # Errors
+    /// - `NotFound`: کاربر پیدا نشد
+    /// - `BadRequest`: ادمین نمیتونه خودش رو غیرفعال کنه یا نقش خودش رو برداره
+    #[instrument(skip(self, request))]
+    pub async fn update_user(
+        &self,
+        admin_id: &str,
+        user_id: &str,
+        request: AdminUpdateUserRequest,
+    ) -> Result<UserResponse> {
+        let mut user = self.user_repo
+            .find_by_id(user_id)
+            .await?
+            .ok_or_not_found(format!("User '{}' not found", user_id))?;
+        
+        if admin_id == user_id
+            && (request.is_active == Some(false)
+                || request.role.is_some_and(|role| !role.is_admin()))
+        {
+            return Err(AppError::BadRequest(
+                "Admins cannot deactivate or demote themselves".to_string()
+            ));
+        }
+        
+        if let Some(is_active) = request.is_active {
+            user.is_active = is_active;
+        }
+        
+        if let Some(role) = request.role {
+            user.role = role;
+        }
+        
+        let user = self.user_repo.update(&user).await?;
+        
+        info!(
+            admin_id = %admin_id,
+            user_id = %user_id,
+            is_active = user.is_active,
+            role = ?user.role,
+            "Admin updated user"
+        );
+        
+        Ok(user.into())
+    }
+    
+    /// جستجو، فیلتر و صفحه‌بندی همه URL‌ها
+    pub async fn list_urls(
+        &self,
+        search: &SearchParams,
+        pagination: &Pagination,
+    ) -> Result<PaginatedResult<UrlResponse>> {
+        let filter = UrlFilter::from_params(search)?;
+        let pagination = pagination.normalized();
+        
+        let (urls, total) = self.url_repo.search(None, &filter, &pagination).await?;
+        
+        let responses: Vec<UrlResponse> = urls
+            .iter()
+            .map(|url| UrlResponse::from_url(url, &self.config.base_url))
+            .collect();
+        
+        Ok(PaginatedResult::new(responses, &pagination, total))
+    }
+    
+    /// حذف هر URL بدون بررسی مالکیت
+    #[instrument(skip(self))]
+    pub async fn delete_url(&self, admin_id: &str, short_code: &str) -> Result<()> {
+        let url = self.url_repo
+            .find_by_short_code(short_code)
+            .await?
+            .ok_or_else(|| AppError::url_not_found(short_code))?;
+        
+        self.url_repo.delete(&url.id).await?;
+        self.redirect_cache.invalidate(short_code);
+        
+        info!(admin_id = %admin_id, short_code = %short_code, "Admin deleted URL");
+        Ok(())
+    }
+}
+
+// =====================================
+// Tests
+// =====================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{Database, UrlRepository, UserRepository};
+    use crate::models::{CreateUrl, CreateUser, RedirectType, UserRole};
+    
+    async fn test_service() -> (AdminService, Database) {
+        let db = Database::ephemeral().await.unwrap();
+        let service = AdminService::new(
+            Arc::new(UserRepository::new(db.clone())),
+            Arc::new(UrlRepository::new(db.clone())),
+            Arc::new(RedirectCache::disabled()),
+            Arc::new(Config::default()),
+        );
+        (service, db)
+    }
+    
+    async fn create_user(db: &Database, email: &str, name: &str) -> String {
+        UserRepository::new(db.clone())
+            .create(&CreateUser::new(email, "password123", Some(name.to_string())).unwrap())
+            .await
+            .unwrap()
+            .id
+    }
+    
+    #[tokio::test]
+    async fn test_list_and_deactivate_users() {
+        let (service, db) = test_service().await;
+        let admin = create_user(&db, "admin@example.com", "Admin").await;
+        let alice = create_user(&db, "alice@example.com", "Alice").await;
+        create_user(&db, "bob@example.com", "Bob").await;
+        
+        service.update_user(&admin, &admin, AdminUpdateUserRequest {
+            role: Some(UserRole::Admin),
+            ..Default::default()
+        }).await.unwrap();
+        assert!(service.is_admin(&admin).await.unwrap());
+        assert!(!service.is_admin(&alice).await.unwrap());
+        
+        let search = SearchParams { query: Some("ALICE".to_string()), ..Default::default() };
+        let result = service.list_users(&search, &Pagination::default()).await.unwrap();
+        assert_eq!(result.pagination.total_items, 1);
+        assert_eq!(result.data[0].id, alice);
+        
+        let deactivated = service.update_user(&admin, &alice, AdminUpdateUserRequest {
+            is_active: Some(false),
+            ..Default::default()
+        }).await.unwrap();
+        assert!(!deactivated.is_active);
+        
+        let search = SearchParams { status: Some("inactive".to_string()), ..Default::default() };
+        let result = service.list_users(&search, &Pagination::default()).await.unwrap();
+        assert_eq!(result.pagination.total_items, 1);
+        
+        // ادمین نمیتونه خودش رو غیرفعال کنه
+        let result = service.update_user(&admin, &admin, AdminUpdateUserRequest {
+            is_active: Some(false),
+            ..Default::default()
+        }).await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+    
+    #[tokio::test]
+    async fn test_list_and_delete_any_url() {
+        let (service, db) = test_service().await;
+        let alice = create_user(&db, "alice@example.com", "Alice").await;
+        let bob = create_user(&db, "bob@example.com", "Bob").await;
+        
+        for (code, owner) in [("alice1", Some(alice)), ("bob1", Some(bob)), ("anon1", None)] {
+            service.url_repo.create(&CreateUrl {
+                id: nanoid::nanoid!(21),
+                short_code: code.to_string(),
+                original_url: format!("https://example.com/{}", code),
+                title: None,
+                user_id: owner,
+                expires_at: None,
+                redirect_type: RedirectType::default(),
+                password_hash: None,
+                max_clicks: None,
+                starts_at: None,
+            }).await.unwrap();
+        }
+        
+        let all = service.list_urls(&SearchParams::default(), &Pagination::default()).await.unwrap();
+        assert_eq!(all.pagination.total_items, 3);
+        
+        service.delete_url("admin", "bob1").await.unwrap();
+        let all = service.list_urls(&SearchParams::default(), &Pagination::default()).await.unwrap();
+        assert_eq!(all.pagination.total_items, 2);
+        
+        assert!(matches!(
+            service.delete_url("admin", "bob1").await,
+            Err(AppError::NotFound(_))
+        ));
+    }
+}
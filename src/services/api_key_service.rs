@@ -0,0 +1,272 @@
+//! # سرویس API Key
+//!
+//! ساخت، لیست، ابطال و اعتبارسنجی کلیدهای دسترسی برنامه‌ای
+//!
+//! ## مفاهیم Rust:
+//! - Secret handling: کلید خام فقط یک بار برمیگرده
+//! - Hash lookup: پیدا کردن کلید با هش SHA-256
+//! - Owner cache: نگاشت هش کلید به صاحبش برای rate limiter، بدون query تکراری
+
+use std::{
+    num::NonZeroUsize,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use lru::LruCache;
+use tokio::time::Instant;
+use tracing::{info, instrument};
+use validator::Validate;
+
+use crate::{
+    database::ApiKeyStore,
+    error::{AppError, Result},
+    models::{
+        hash_api_key, ApiKey, ApiKeyResponse, CreateApiKey, CreateApiKeyRequest,
+        CreatedApiKeyResponse,
+    },
+};
+
+use super::Service;
+
+/// حداقل فاصله بین دو بار ذخیره `last_used_at` (ثانیه)
+///
+/// جلوی یه write به ازای هر درخواست رو میگیره
+const LAST_USED_RESOLUTION_SECS: i64 = 60;
+
+/// حداکثر تعداد کلید‌هایی که صاحبشون در حافظه میمونه
+const OWNER_CACHE_CAPACITY: usize = 10_000;
+
+/// عمر هر ورودی cache صاحب کلید
+///
+/// کلید باطل شده روی instance‌های دیگه حداکثر تا این مدت هنوز bucket کاربر رو میگیره
+/// (احراز هویت همیشه با دیتابیس چک میشه)
+const OWNER_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// صاحب یک کلید در cache
+#[derive(Debug, Clone)]
+struct CachedOwner {
+    user_id: String,
+    key_id: String,
+    cached_at: Instant,
+}
+
+// =====================================
+// API Key Service
+// =====================================
+/// سرویس API key
+///
+/// # مسئولیت‌ها:
+/// - ساخت کلید با scope‌های مشخص
+/// - لیست و ابطال کلیدهای کاربر
+/// - احراز هویت درخواست‌ها با کلید
+#[derive(Debug, Clone)]
+pub struct ApiKeyService {
+    repo: Arc<dyn ApiKeyStore>,
+    /// هش کلید => صاحبش؛ بین clone‌ها مشترکه
+    owners: Arc<Mutex<LruCache<String, CachedOwner>>>,
+}
+
+impl Service for ApiKeyService {}
+
+impl ApiKeyService {
+    /// ساخت سرویس جدید
+    #[must_use]
+    pub fn new(repo: Arc<dyn ApiKeyStore>) -> Self {
+        let capacity = NonZeroUsize::new(OWNER_CACHE_CAPACITY).unwrap_or(NonZeroUsize::MIN);
+        
+        Self {
+            repo,
+            owners: Arc::new(Mutex::new(LruCache::new(capacity))),
+        }
+    }
+    
+    /// ساخت API key جدید برای کاربر
+    #[instrument(skip(self, request), fields(name = %request.name))]
+    pub async fn create_key(
+        &self,
+        user_id: &str,
+        request: CreateApiKeyRequest,
+    ) -> Result<CreatedApiKeyResponse> {
+        request.validate()?;
+        
+        let (create, key) = CreateApiKey::generate(user_id, request.name, &request.scopes);
+        let api_key = self.repo.create(&create).await?;
+        
+        info!(user_id = %user_id, key_id = %api_key.id, "API key created");
+        
+        Ok(CreatedApiKeyResponse {
+            api_key: api_key.into(),
+            key,
+        })
+    }
+    
+    /// لیست کلیدهای فعال کاربر
+    pub async fn list_keys(&self, user_id: &str) -> Result<Vec<ApiKeyResponse>> {
+        let keys = self.repo.find_active_by_user(user_id).await?;
+        
+        Ok(keys.into_iter().map(Into::into).collect())
+    }
+    
+    /// ابطال یک کلید
+    #[instrument(skip(self))]
+    pub async fn revoke_key(&self, user_id: &str, key_id: &str) -> Result<()> {
+        if !self.repo.revoke(key_id, user_id).await? {
+            return Err(AppError::NotFound(format!("API key '{}' not found", key_id)));
+        }
+        
+        let mut owners = self.owners.lock().unwrap_or_else(PoisonError::into_inner);
+        let revoked: Vec<String> = owners
+            .iter()
+            .filter(|(_, owner)| owner.key_id == key_id)
+            .map(|(hash, _)| hash.clone())
+            .collect();
+        for hash in revoked {
+            owners.pop(&hash);
+        }
+        drop(owners);
+        
+        info!(user_id = %user_id, key_id = %key_id, "API key revoked");
+        Ok(())
+    }
+    
+    /// احراز هویت با API key
+    ///
+    /// # مفاهیم:
+    /// - کلید باطل شده یا متعلق به کاربر غیرفعال => 401
+    /// - `last_used_at` حداکثر هر دقیقه یک بار بروز میشه
+    pub async fn authenticate(&self, key: &str) -> Result<ApiKey> {
+        let api_key = self.repo
+            .find_active_by_hash(&hash_api_key(key))
+            .await?
+            .ok_or_else(|| AppError::Unauthorized("Invalid API key".to_string()))?;
+        
+        let stale = api_key.last_used_at.is_none_or(|last| {
+            (chrono::Utc::now() - last).num_seconds() >= LAST_USED_RESOLUTION_SECS
+        });
+        if stale {
+            self.repo.touch(&api_key.id).await?;
+        }
+        
+        self.remember_owner(&hash_api_key(key), &api_key);
+        Ok(api_key)
+    }
+    
+    /// صاحب کلید از cache، بدون دیتابیس
+    ///
+    /// `None` یعنی کلید هنوز دیده نشده یا ورودی‌اش منقضی شده
+    #[must_use]
+    pub fn cached_owner(&self, key: &str) -> Option<String> {
+        let mut owners = self.owners.lock().unwrap_or_else(PoisonError::into_inner);
+        let hash = hash_api_key(key);
+        
+        match owners.get(&hash) {
+            Some(owner) if owner.cached_at.elapsed() < OWNER_CACHE_TTL => Some(owner.user_id.clone()),
+            Some(_) => {
+                owners.pop(&hash);
+                None
+            }
+            None => None,
+        }
+    }
+    
+    /// پیدا کردن صاحب کلید (اول cache، بعد دیتابیس)
+    ///
+    /// برخلاف `authenticate` خطای 401 نمیده و `last_used_at` رو تغییر نمیده؛
+    /// کلید نامعتبر یا باطل شده `None` برمیگردونه
+    pub async fn resolve_owner(&self, key: &str) -> Result<Option<String>> {
+        if let Some(user_id) = self.cached_owner(key) {
+            return Ok(Some(user_id));
+        }
+        
+        let hash = hash_api_key(key);
+        let Some(api_key) = self.repo.find_active_by_hash(&hash).await? else {
+            return Ok(None);
+        };
+        
+        self.remember_owner(&hash, &api_key);
+        Ok(Some(api_key.user_id))
+    }
+    
+    fn remember_owner(&self, hash: &str, api_key: &ApiKey) {
+        self.owners
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .put(hash.to_string(), CachedOwner {
+                user_id: api_key.user_id.clone(),
+                key_id: api_key.id.clone(),
+                cached_at: Instant::now(),
+            });
+    }
+}
+
+// =====================================
+// Tests
+// =====================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{ApiKeyRepository, Database, UserRepository};
+    use crate::models::{ApiScope, CreateUser};
+    
+    async fn test_service() -> (ApiKeyService, String) {
+        let db = Database::ephemeral().await.unwrap();
+        let user_id = UserRepository::new(db.clone())
+            .create(&CreateUser::new("ci@example.com", "password123", None).unwrap())
+            .await
+            .unwrap()
+            .id;
+        
+        (ApiKeyService::new(Arc::new(ApiKeyRepository::new(db))), user_id)
+    }
+    
+    #[tokio::test]
+    async fn test_api_key_lifecycle() {
+        let (service, user_id) = test_service().await;
+        
+        let created = service.create_key(&user_id, CreateApiKeyRequest {
+            name: "ci".to_string(),
+            scopes: vec![ApiScope::UrlsWrite, ApiScope::UrlsRead, ApiScope::UrlsWrite],
+        }).await.unwrap();
+        
+        assert!(created.key.starts_with(crate::models::API_KEY_PREFIX));
+        assert!(created.key.starts_with(&created.api_key.prefix));
+        assert_eq!(created.api_key.scopes, vec![ApiScope::UrlsRead, ApiScope::UrlsWrite]);
+        
+        // احراز هویت و ثبت آخرین استفاده
+        let key = service.authenticate(&created.key).await.unwrap();
+        assert_eq!(key.user_id, user_id);
+        let listed = service.list_keys(&user_id).await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert!(listed[0].last_used_at.is_some());
+        
+        assert!(service.authenticate("usk_wrong").await.is_err());
+        assert_eq!(service.cached_owner(&created.key), Some(user_id.clone()));
+        assert_eq!(service.resolve_owner("usk_wrong").await.unwrap(), None);
+        
+        // فقط مالک میتونه باطل کنه
+        assert!(service.revoke_key("someone-else", &created.api_key.id).await.is_err());
+        service.revoke_key(&user_id, &created.api_key.id).await.unwrap();
+        
+        // ابطال ورودی cache رو هم پاک میکنه
+        assert_eq!(service.cached_owner(&created.key), None);
+        assert_eq!(service.resolve_owner(&created.key).await.unwrap(), None);
+        assert!(matches!(
+            service.authenticate(&created.key).await,
+            Err(AppError::Unauthorized(_))
+        ));
+        assert!(service.list_keys(&user_id).await.unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_api_key_requires_scope() {
+        let (service, user_id) = test_service().await;
+        
+        let result = service.create_key(&user_id, CreateApiKeyRequest {
+            name: "empty".to_string(),
+            scopes: vec![],
+        }).await;
+        
+        assert!(matches!(result, Err(AppError::Validation(_))));
+    }
+}
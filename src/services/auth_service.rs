@@ -15,11 +15,11 @@ use validator::Validate;
 
 use crate::{
     config::Config,
-    database::UserRepository,
+    database::{RefreshTokenStore, UserStore},
     error::{AppError, Result, OptionExt},
     models::{
-        Claims, CreateUser, LoginRequest, LoginResponse,
-        RegisterRequest, RegisterResponse, User, UserResponse,
+        hash_refresh_token, ChangePasswordRequest, Claims, CreateRefreshToken, CreateUser,
+        LoginRequest, LoginResponse, RefreshTokenRequest, RegisterRequest, RegisterResponse, UpdateProfileRequest, User, UserResponse,
     },
 };
 
@@ -34,10 +34,12 @@ use super::Service;
 /// - ثبت‌نام کاربر
 /// - ورود و صدور توکن
 /// - اعتبارسنجی توکن
+/// - rotation و ابطال refresh token‌ها
 /// - مدیریت رمز عبور
 #[derive(Debug, Clone)]
 pub struct AuthService {
-    repo: UserRepository,
+    repo: Arc<dyn UserStore>,
+    refresh_repo: Arc<dyn RefreshTokenStore>,
     config: Arc<Config>,
 }
 
@@ -46,8 +48,12 @@ impl Service for AuthService {}
 impl AuthService {
     /// ساخت سرویس جدید
     #[must_use]
-    pub fn new(repo: UserRepository, config: Arc<Config>) -> Self {
-        Self { repo, config }
+    pub fn new(
+        repo: Arc<dyn UserStore>,
+        refresh_repo: Arc<dyn RefreshTokenStore>,
+        config: Arc<Config>,
+    ) -> Self {
+        Self { repo, refresh_repo, config }
     }
     
     /// ثبت‌نام کاربر جدید
@@ -122,17 +128,9 @@ impl AuthService {
         }
         
         // Step 5: صدور توکن
-        let token = self.generate_token(&user)?;
-        let expires_at = Utc::now() 
-            + chrono::Duration::hours(self.config.jwt_expiration_hours as i64);
-        
         info!(user_id = %user.id, "User logged in");
         
-        Ok(LoginResponse {
-            user: user.into(),
-            token,
-            expires_at,
-        })
+        self.start_session(user).await
     }
     
     /// اعتبارسنجی توکن JWT
@@ -162,16 +160,82 @@ impl AuthService {
         Ok(token_data.claims)
     }
     
+    /// احراز هویت کامل یک توکن
+    ///
+    /// # مفاهیم:
+    /// - علاوه بر امضا و انقضا، وضعیت فعلی کاربر هم چک میشه
+    /// - توکن‌های صادر شده قبل از تغییر رمز عبور رد میشن
+    pub async fn authenticate(&self, token: &str) -> Result<Claims> {
+        let claims = self.verify_token(token)?;
+        
+        let user = self.repo
+            .find_by_id(&claims.sub)
+            .await?
+            .ok_or_else(|| AppError::Unauthorized("Invalid token".to_string()))?;
+        
+        if !user.is_active {
+            return Err(AppError::Forbidden(
+                "Account is deactivated".to_string()
+            ));
+        }
+        
+        if !user.accepts_token_issued_at(claims.iat) {
+            return Err(AppError::Unauthorized("Token has been revoked".to_string()));
+        }
+        
+        Ok(claims)
+    }
+    
     /// گرفتن کاربر با ID
     pub async fn get_user(&self, user_id: &str) -> Result<UserResponse> {
         let user = self.repo
-            .find_by_id(&user_id.to_string())
+            .find_by_id(user_id)
             .await?
             .ok_or_not_found(format!("User '{}' not found", user_id))?;
         
         Ok(user.into())
     }
     
+    /// بروزرسانی پروفایل کاربر
+    ///
+    /// # مفاهیم:
+    /// - فقط فیلدهای ارسال شده تغییر میکنن
+    /// - ایمیل جدید نباید مال کاربر دیگه‌ای باشه
+    #[instrument(skip(self, request))]
+    pub async fn update_profile(
+        &self,
+        user_id: &str,
+        request: UpdateProfileRequest,
+    ) -> Result<UserResponse> {
+        request.validate()?;
+        
+        let mut user = self.repo
+            .find_by_id(user_id)
+            .await?
+            .ok_or_not_found(format!("User '{}' not found", user_id))?;
+        
+        if let Some(name) = request.name {
+            user.name = Some(name);
+        }
+        
+        if let Some(email) = request.email {
+            if email != user.email {
+                if self.repo.email_exists(&email).await? {
+                    return Err(AppError::Conflict(
+                        "Email already registered".to_string()
+                    ));
+                }
+                user.email = email;
+            }
+        }
+        
+        let user = self.repo.update(&user).await?;
+        
+        info!(user_id = %user_id, "Profile updated");
+        
+        Ok(user.into())
+    }
+    
     /// تولید توکن JWT
     ///
     /// # مفاهیم:
@@ -193,64 +257,167 @@ impl AuthService {
         Ok(token)
     }
     
-    /// Refresh توکن
-    ///
-    /// توکن جدید صادر میکنه اگه توکن قبلی هنوز معتبر باشه
-    pub async fn refresh_token(&self, token: &str) -> Result<LoginResponse> {
-        // اعتبارسنجی توکن فعلی
-        let claims = self.verify_token(token)?;
-        
-        // گرفتن کاربر
-        let user = self.repo
-            .find_by_id(&claims.sub)
-            .await?
-            .ok_or_not_found("User not found")?;
+    /// مدت اعتبار refresh token
+    fn refresh_ttl(&self) -> chrono::Duration {
+        chrono::Duration::days(self.config.refresh_token_expiration_days as i64)
+    }
+    
+    /// شروع یه نشست جدید: refresh token با family جدید + access token
+    async fn start_session(&self, user: User) -> Result<LoginResponse> {
+        let (refresh, refresh_token) = CreateRefreshToken::generate(&user.id, None, self.refresh_ttl());
+        self.refresh_repo.create(&refresh).await?;
         
-        // صدور توکن جدید
-        let new_token = self.generate_token(&user)?;
+        self.login_response(user, refresh_token, refresh.expires_at)
+    }
+    
+    /// ساخت پاسخ ورود (access token جدید + refresh token + اطلاعات کاربر)
+    fn login_response(
+        &self,
+        user: User,
+        refresh_token: String,
+        refresh_expires_at: chrono::DateTime<Utc>,
+    ) -> Result<LoginResponse> {
+        let token = self.generate_token(&user)?;
         let expires_at = Utc::now() 
             + chrono::Duration::hours(self.config.jwt_expiration_hours as i64);
         
         Ok(LoginResponse {
             user: user.into(),
-            token: new_token,
+            token,
             expires_at,
+            refresh_token,
+            refresh_expires_at,
         })
     }
     
+    /// گرفتن access token جدید با refresh token
+    ///
+    /// # مفاهیم:
+    /// - Rotation: refresh token فعلی باطل میشه و یه توکن جدید برمیگرده
+    /// - Reuse detection: اگه یه توکن rotate شده دوباره بیاد، احتمالا دزدیده شده؛
+    ///   کل family (نشست) باطل میشه و کاربر باید دوباره وارد بشه
+    #[instrument(skip(self, request))]
+    pub async fn refresh_token(&self, request: RefreshTokenRequest) -> Result<LoginResponse> {
+        request.validate()?;
+        
+        let invalid = || AppError::Unauthorized("Invalid refresh token".to_string());
+        
+        let stored = self.refresh_repo
+            .find_by_hash(&hash_refresh_token(&request.refresh_token))
+            .await?
+            .ok_or_else(invalid)?;
+        
+        if stored.is_revoked() {
+            if stored.replaced_by.is_some() {
+                warn!(
+                    user_id = %stored.user_id,
+                    family_id = %stored.family_id,
+                    "Refresh token reuse detected, revoking session"
+                );
+                self.refresh_repo.revoke_family(&stored.family_id).await?;
+            }
+            return Err(invalid());
+        }
+        
+        if stored.is_expired() {
+            return Err(AppError::Unauthorized("Refresh token expired".to_string()));
+        }
+        
+        let user = self.repo
+            .find_by_id(&stored.user_id)
+            .await?
+            .ok_or_else(invalid)?;
+        
+        if !user.is_active {
+            return Err(AppError::Forbidden(
+                "Account is deactivated".to_string()
+            ));
+        }
+        
+        // Rotation
+        let (next, refresh_token) = CreateRefreshToken::generate(
+            &user.id,
+            Some(stored.family_id.clone()),
+            self.refresh_ttl(),
+        );
+        
+        if !self.refresh_repo.rotate(&stored.id, &next).await? {
+            // یه درخواست همزمان همین توکن رو زودتر مصرف کرده
+            warn!(user_id = %user.id, family_id = %stored.family_id, "Concurrent refresh token reuse");
+            self.refresh_repo.revoke_family(&stored.family_id).await?;
+            return Err(invalid());
+        }
+        
+        self.login_response(user, refresh_token, next.expires_at)
+    }
+    
+    /// خروج: باطل کردن نشستی که refresh token بهش تعلق داره
+    ///
+    /// برای توکن ناشناخته هم موفق برمیگرده (idempotent)
+    #[instrument(skip(self, request))]
+    pub async fn logout(&self, request: RefreshTokenRequest) -> Result<()> {
+        request.validate()?;
+        
+        if let Some(stored) = self.refresh_repo
+            .find_by_hash(&hash_refresh_token(&request.refresh_token))
+            .await?
+        {
+            self.refresh_repo.revoke_family(&stored.family_id).await?;
+            info!(user_id = %stored.user_id, "User logged out");
+        }
+        
+        Ok(())
+    }
+    
     /// تغییر رمز عبور
-    #[instrument(skip(self, current_password, new_password))]
+    ///
+    /// # مفاهیم:
+    /// - رمز فعلی باید درست باشه
+    /// - بعد از تغییر، همه access و refresh token‌های قبلی باطل میشن
+    /// - یه توکن جدید برمیگرده تا کاربر لاگین بمونه
+    #[instrument(skip(self, request))]
     pub async fn change_password(
         &self,
         user_id: &str,
-        current_password: &str,
-        new_password: &str,
-    ) -> Result<()> {
-        // اعتبارسنجی طول رمز جدید
-        if new_password.len() < 8 {
-            return Err(AppError::BadRequest(
-                "Password must be at least 8 characters".to_string()
-            ));
-        }
+        request: ChangePasswordRequest,
+    ) -> Result<LoginResponse> {
+        // اعتبارسنجی
+        request.validate()?;
         
         // گرفتن کاربر
         let user = self.repo
-            .find_by_id(&user_id.to_string())
+            .find_by_id(user_id)
             .await?
             .ok_or_not_found("User not found")?;
         
         // بررسی رمز فعلی
-        if !user.verify_password(current_password)? {
+        if !user.verify_password(&request.current_password)? {
+            warn!(user_id = %user_id, "Failed password change attempt");
             return Err(AppError::Unauthorized(
                 "Current password is incorrect".to_string()
             ));
         }
         
-        // TODO: بروزرسانی رمز در دیتابیس
-        // این بخش نیاز به متد update در repository داره
+        if request.current_password == request.new_password {
+            return Err(AppError::BadRequest(
+                "New password must be different from the current password".to_string()
+            ));
+        }
+        
+        // ذخیره هش جدید
+        self.repo.update_password(user_id, &request.new_password).await?;
+        
+        let user = self.repo
+            .find_by_id(&user.id)
+            .await?
+            .ok_or_not_found("User not found")?;
+        
+        // نشست‌های قبلی هم دیگه نباید refresh بشن
+        self.refresh_repo.revoke_all_for_user(user_id).await?;
         
         info!(user_id = %user_id, "Password changed");
-        Ok(())
+        
+        self.start_session(user).await
     }
 }
 
@@ -282,6 +449,35 @@ pub fn extract_token_from_header(header_value: &str) -> Option<&str> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::database::MemoryStore;
+    
+    /// ساخت سرویس روی store در حافظه
+    fn test_service() -> AuthService {
+        let store = MemoryStore::new();
+        AuthService::new(
+            Arc::new(store.clone()),
+            Arc::new(store),
+            Arc::new(Config::default()),
+        )
+    }
+    
+    /// ثبت‌نام و ورود یه کاربر تستی
+    async fn login(service: &AuthService, email: &str, password: &str) -> LoginResponse {
+        service.register(RegisterRequest {
+            email: email.to_string(),
+            password: password.to_string(),
+            name: None,
+        }).await.unwrap();
+        
+        service.login(LoginRequest {
+            email: email.to_string(),
+            password: password.to_string(),
+        }).await.unwrap()
+    }
+    
+    fn refresh_request(token: &str) -> RefreshTokenRequest {
+        RefreshTokenRequest { refresh_token: token.to_string() }
+    }
     
     #[test]
     fn test_extract_token() {
@@ -312,9 +508,151 @@ mod tests {
             sub: "user1".to_string(),
             email: "test@example.com".to_string(),
             exp: Utc::now().timestamp() - 3600, // 1 ساعت قبل
-            iat: Utc::now().timestamp() - 7200, // 2 ساعت قبل
+            iat: (Utc::now().timestamp() - 7200) as f64, // 2 ساعت قبل
         };
         assert!(expired_claims.is_expired());
     }
+    
+    #[tokio::test]
+    async fn test_change_password_revokes_old_tokens() {
+        let service = test_service();
+        let session = login(&service, "pw@example.com", "oldpassword").await;
+        let user = session.user;
+        
+        // توکنی که چند ثانیه قبل از تغییر رمز صادر شده
+        let mut claims = Claims::new(&user.id, &user.email, 1);
+        claims.iat -= 10.0;
+        let old_token = encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(service.config.jwt_secret.as_bytes()),
+        ).unwrap();
+        assert!(service.authenticate(&old_token).await.is_ok());
+        
+        // رمز فعلی اشتباه
+        let wrong = service.change_password(&user.id, ChangePasswordRequest {
+            current_password: "wrongpassword".to_string(),
+            new_password: "newpassword".to_string(),
+        }).await;
+        assert!(matches!(wrong, Err(AppError::Unauthorized(_))));
+        
+        let response = service.change_password(&user.id, ChangePasswordRequest {
+            current_password: "oldpassword".to_string(),
+            new_password: "newpassword".to_string(),
+        }).await.unwrap();
+        
+        // توکن قدیمی باطل شده، توکن جدید کار میکنه
+        assert!(matches!(
+            service.authenticate(&old_token).await,
+            Err(AppError::Unauthorized(_))
+        ));
+        assert!(service.authenticate(&response.token).await.is_ok());
+        
+        // توکنی که در همون ثانیه ولی قبل از تغییر رمز صادر شده هم باطله
+        let changed_at = service.repo.find_by_id(&user.id).await.unwrap().unwrap()
+            .password_changed_at
+            .unwrap();
+        let mut claims = Claims::new(&user.id, &user.email, 1);
+        claims.iat = (changed_at.timestamp_micros() - 1) as f64 / 1_000_000.0;
+        let same_second_token = encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(service.config.jwt_secret.as_bytes()),
+        ).unwrap();
+        assert!(matches!(
+            service.authenticate(&same_second_token).await,
+            Err(AppError::Unauthorized(_))
+        ));
+        
+        // refresh token نشست قبلی هم باطل شده
+        assert!(service.refresh_token(refresh_request(&session.refresh_token)).await.is_err());
+        assert!(service.refresh_token(refresh_request(&response.refresh_token)).await.is_ok());
+        
+        // رمز جدید ذخیره شده
+        let login = |password: &str| LoginRequest {
+            email: "pw@example.com".to_string(),
+            password: password.to_string(),
+        };
+        assert!(service.login(login("oldpassword")).await.is_err());
+        assert!(service.login(login("newpassword")).await.is_ok());
+    }
+    
+    #[tokio::test]
+    async fn test_update_profile() {
+        let service = test_service();
+        
+        let register = |email: &str| RegisterRequest {
+            email: email.to_string(),
+            password: "password123".to_string(),
+            name: Some("Typo Nmae".to_string()),
+        };
+        let user = service.register(register("me@example.com")).await.unwrap().user;
+        service.register(register("taken@example.com")).await.unwrap();
+        
+        // فقط نام تغییر میکنه
+        let updated = service.update_profile(&user.id, UpdateProfileRequest {
+            name: Some("Fixed Name".to_string()),
+            ..Default::default()
+        }).await.unwrap();
+        assert_eq!(updated.name.as_deref(), Some("Fixed Name"));
+        assert_eq!(updated.email, "me@example.com");
+        
+        // ایمیل تکراری
+        let conflict = service.update_profile(&user.id, UpdateProfileRequest {
+            email: Some("taken@example.com".to_string()),
+            ..Default::default()
+        }).await;
+        assert!(matches!(conflict, Err(AppError::Conflict(_))));
+        
+        // ایمیل فعلی خودش مشکلی نداره
+        let same = service.update_profile(&user.id, UpdateProfileRequest {
+            email: Some("me@example.com".to_string()),
+            ..Default::default()
+        }).await;
+        assert!(same.is_ok());
+        
+        let updated = service.update_profile(&user.id, UpdateProfileRequest {
+            email: Some("new@example.com".to_string()),
+            ..Default::default()
+        }).await.unwrap();
+        assert_eq!(updated.email, "new@example.com");
+        assert_eq!(updated.name.as_deref(), Some("Fixed Name"));
+    }
+    
+    #[tokio::test]
+    async fn test_refresh_token_rotation_and_reuse() {
+        let service = test_service();
+        let session = login(&service, "rt@example.com", "password123").await;
+        
+        // rotation: توکن جدید، متفاوت با قبلی
+        let rotated = service.refresh_token(refresh_request(&session.refresh_token)).await.unwrap();
+        assert_ne!(rotated.refresh_token, session.refresh_token);
+        assert!(service.authenticate(&rotated.token).await.is_ok());
+        
+        // استفاده دوباره از توکن قدیمی => کل نشست باطل میشه
+        let reuse = service.refresh_token(refresh_request(&session.refresh_token)).await;
+        assert!(matches!(reuse, Err(AppError::Unauthorized(_))));
+        assert!(service.refresh_token(refresh_request(&rotated.refresh_token)).await.is_err());
+        
+        // توکن ناشناخته
+        assert!(service.refresh_token(refresh_request("not-a-token")).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_logout_revokes_session_only() {
+        let service = test_service();
+        let first = login(&service, "lo@example.com", "password123").await;
+        let second = service.login(LoginRequest {
+            email: "lo@example.com".to_string(),
+            password: "password123".to_string(),
+        }).await.unwrap();
+        
+        service.logout(refresh_request(&first.refresh_token)).await.unwrap();
+        
+        assert!(service.refresh_token(refresh_request(&first.refresh_token)).await.is_err());
+        assert!(service.refresh_token(refresh_request(&second.refresh_token)).await.is_ok());
+        
+        // idempotent
+        assert!(service.logout(refresh_request(&first.refresh_token)).await.is_ok());
+    }
 }
-
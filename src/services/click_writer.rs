@@ -0,0 +1,378 @@
+//! # ثبت دسته‌ای کلیک‌ها
+//!
+//! هر redirect یک کلیک تولید میکنه. به جای یک task و چند query برای هر
+//! کلیک، کلیک‌ها وارد یک صف (`mpsc`) میشن و یک task جمع‌کننده اون‌ها رو
+//! در یک تراکنش مینویسه.
+//!
+//! ## رفتار:
+//! - **Coalescing**: شمارنده هر URL با یک `UPDATE` و مجموع کلیک‌ها زیاد میشه
+//! - **Flush**: هر `CLICK_FLUSH_INTERVAL_MS` یا وقتی `CLICK_BATCH_SIZE` کلیک جمع بشه
+//! - **Backpressure**: صف ظرفیت محدود داره (`CLICK_QUEUE_CAPACITY`)؛ وقتی پره
+//!   redirect منتظر میمونه و `queue_full` زیاد میشه
+//! - **Shutdown**: `flush` همه کلیک‌های قبل از خودش رو مینویسه
+//!
+//! ## مفاهیم Rust:
+//! - **Channels**: `mpsc::Sender` قابل clone بین همه handler‌ها، یک `Receiver`
+//! - **`tokio::select!`**: منتظر پیام یا tick، هر کدوم زودتر برسه
+//! - **`oneshot`**: جواب دادن به درخواست flush
+
+use std::{
+    mem,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use tokio::{
+    sync::{mpsc, oneshot},
+    time::{self, MissedTickBehavior},
+};
+use tracing::{debug, warn};
+
+use crate::{
+    config::Config,
+    database::ClickStore,
+    models::{ClickBatch, ClickWriterStats, CreateClickEvent},
+};
+
+/// پیام‌های صف
+#[derive(Debug)]
+enum Message {
+    /// `increment: false` یعنی شمارنده کلیک قبلا زیاد شده و فقط رویداد نوشته میشه
+    Click {
+        increment: bool,
+        event: CreateClickEvent,
+    },
+    /// نوشتن همه کلیک‌های قبلی و خبر دادن بعد از اتمام
+    Flush(oneshot::Sender<()>),
+}
+
+/// شمارنده‌های مشترک بین writer و task جمع‌کننده
+#[derive(Debug, Default)]
+struct Metrics {
+    buffered: AtomicU64,
+    enqueued: AtomicU64,
+    recorded: AtomicU64,
+    failed: AtomicU64,
+    batches: AtomicU64,
+    queue_full: AtomicU64,
+}
+
+// =====================================
+// Click Writer
+// =====================================
+/// صف ثبت کلیک با flush دسته‌ای
+///
+/// # مثال
+/// ```rust,ignore
+/// let writer = ClickWriter::spawn(click_store, &config);
+/// writer.record(CreateClickEvent::new(&url_id, info)).await;
+///
+/// // موقع shutdown
+/// writer.flush().await;
+/// ```
+#[derive(Debug)]
+pub struct ClickWriter {
+    sender: mpsc::Sender<Message>,
+    metrics: Arc<Metrics>,
+}
+
+impl ClickWriter {
+    /// ساخت writer و اجرای task جمع‌کننده
+    ///
+    /// task تا وقتی همه `ClickWriter`‌ها drop بشن اجرا میمونه و قبل از
+    /// تموم شدن کلیک‌های باقیمونده رو مینویسه.
+    ///
+    /// # Panics
+    /// اگه خارج از runtime tokio صدا زده بشه
+    #[must_use]
+    pub fn spawn(store: Arc<dyn ClickStore>, config: &Config) -> Self {
+        let (sender, receiver) = mpsc::channel(config.click_queue_capacity.max(1));
+        let metrics = Arc::new(Metrics::default());
+        
+        tokio::spawn(run(
+            receiver,
+            store,
+            config.click_batch_size.max(1),
+            Duration::from_millis(config.click_flush_interval_ms.max(1)),
+            metrics.clone(),
+        ));
+        
+        Self { sender, metrics }
+    }
+    
+    /// اضافه کردن یک کلیک به صف
+    ///
+    /// معمولا فوراً برمیگرده؛ فقط وقتی صف پره منتظر جا میمونه.
+    pub async fn record(&self, event: CreateClickEvent) {
+        self.enqueue(Message::Click { increment: true, event }).await;
+    }
+    
+    /// اضافه کردن رویداد کلیکی که شمارنده‌اش قبلا (به صورت اتمی) زیاد شده
+    ///
+    /// برای لینک‌های با `max_clicks` که چک سقف نمیتونه منتظر flush بمونه.
+    pub async fn record_event(&self, event: CreateClickEvent) {
+        self.enqueue(Message::Click { increment: false, event }).await;
+    }
+    
+    async fn enqueue(&self, message: Message) {
+        let sent = match self.sender.try_send(message) {
+            Ok(()) => Ok(()),
+            Err(mpsc::error::TrySendError::Full(message)) => {
+                self.metrics.queue_full.fetch_add(1, Ordering::Relaxed);
+                self.sender.send(message).await.map_err(|_| ())
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => Err(()),
+        };
+        
+        match sent {
+            Ok(()) => {
+                self.metrics.enqueued.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(()) => {
+                self.metrics.failed.fetch_add(1, Ordering::Relaxed);
+                warn!("Click writer is not running, click dropped");
+            }
+        }
+    }
+    
+    /// نوشتن همه کلیک‌هایی که تا الان وارد صف شدن
+    ///
+    /// بعد از این writer هنوز قابل استفاده هست.
+    pub async fn flush(&self) {
+        let (done, wait) = oneshot::channel();
+        
+        if self.sender.send(Message::Flush(done)).await.is_ok() {
+            // اگه task متوقف شده باشه، `wait` با خطا برمیگرده
+            let _ = wait.await;
+        }
+    }
+    
+    /// شمارنده‌ها و وضعیت فعلی صف
+    #[must_use]
+    pub fn stats(&self) -> ClickWriterStats {
+        let queue_capacity = self.sender.max_capacity();
+        
+        ClickWriterStats {
+            queue_capacity,
+            queued: queue_capacity - self.sender.capacity(),
+            buffered: self.metrics.buffered.load(Ordering::Relaxed),
+            enqueued: self.metrics.enqueued.load(Ordering::Relaxed),
+            recorded: self.metrics.recorded.load(Ordering::Relaxed),
+            failed: self.metrics.failed.load(Ordering::Relaxed),
+            batches: self.metrics.batches.load(Ordering::Relaxed),
+            queue_full: self.metrics.queue_full.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// حلقه task جمع‌کننده
+///
+/// flush داخل همین حلقه انجام میشه؛ تا تموم نشه پیام جدیدی خونده نمیشه
+/// و صف پر میشه (همون backpressure).
+async fn run(
+    mut receiver: mpsc::Receiver<Message>,
+    store: Arc<dyn ClickStore>,
+    batch_size: usize,
+    interval: Duration,
+    metrics: Arc<Metrics>,
+) {
+    let mut batch = ClickBatch::default();
+    let mut ticker = time::interval(interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    
+    loop {
+        tokio::select! {
+            message = receiver.recv() => match message {
+                Some(Message::Click { increment, event }) => {
+                    if increment {
+                        batch.push(event);
+                    } else {
+                        batch.push_event(event);
+                    }
+                    metrics.buffered.store(batch.len() as u64, Ordering::Relaxed);
+                    
+                    if batch.len() >= batch_size {
+                        flush(store.as_ref(), &mut batch, &metrics).await;
+                    }
+                }
+                Some(Message::Flush(done)) => {
+                    flush(store.as_ref(), &mut batch, &metrics).await;
+                    let _ = done.send(());
+                }
+                // همه sender‌ها drop شدن
+                None => {
+                    flush(store.as_ref(), &mut batch, &metrics).await;
+                    break;
+                }
+            },
+            _ = ticker.tick() => flush(store.as_ref(), &mut batch, &metrics).await,
+        }
+    }
+}
+
+/// نوشتن batch فعلی در یک تراکنش
+///
+/// در صورت خطا batch دور ریخته میشه (تلاش دوباره ممکنه همون خطا رو تکرار کنه
+/// و صف رو قفل کنه)؛ تعدادش در `failed` ثبت میشه.
+async fn flush(store: &dyn ClickStore, batch: &mut ClickBatch, metrics: &Metrics) {
+    if batch.is_empty() {
+        return;
+    }
+    
+    let pending = mem::take(batch);
+    metrics.buffered.store(0, Ordering::Relaxed);
+    
+    match store.record_batch(&pending).await {
+        Ok(recorded) => {
+            metrics.recorded.fetch_add(recorded, Ordering::Relaxed);
+            metrics.batches.fetch_add(1, Ordering::Relaxed);
+            debug!(clicks = pending.len(), urls = pending.increments.len(), "Flushed click batch");
+        }
+        Err(e) => {
+            metrics.failed.fetch_add(pending.len() as u64, Ordering::Relaxed);
+            warn!(error = %e, clicks = pending.len(), "Failed to flush click batch");
+        }
+    }
+}
+
+// =====================================
+// Tests
+// =====================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::ConfigBuilder,
+        database::{MemoryStore, UrlStore},
+        models::{ClickInfo, CreateUrl, RedirectType, Url},
+    };
+    
+    async fn create_url(store: &MemoryStore, code: &str) -> Url {
+        UrlStore::create(store, &CreateUrl {
+            id: nanoid::nanoid!(21),
+            short_code: code.to_string(),
+            original_url: format!("https://example.com/{}", code),
+            title: None,
+            user_id: None,
+            expires_at: None,
+            redirect_type: RedirectType::default(),
+            password_hash: None,
+            max_clicks: None,
+            starts_at: None,
+        })
+        .await
+        .unwrap()
+    }
+    
+    async fn clicks(store: &MemoryStore, code: &str) -> i64 {
+        store.find_by_short_code(code).await.unwrap().unwrap().clicks
+    }
+    
+    #[tokio::test]
+    async fn test_flush_coalesces_per_url() {
+        let store = MemoryStore::new();
+        let a = create_url(&store, "a").await;
+        let b = create_url(&store, "b").await;
+        let writer = ClickWriter::spawn(Arc::new(store.clone()), &Config::default());
+        
+        for url in [&a, &a, &a, &b] {
+            writer.record(CreateClickEvent::new(&url.id, ClickInfo::default())).await;
+        }
+        
+        // هنوز به interval یا batch_size نرسیده
+        assert_eq!(clicks(&store, "a").await, 0);
+        
+        writer.flush().await;
+        
+        assert_eq!(clicks(&store, "a").await, 3);
+        assert_eq!(clicks(&store, "b").await, 1);
+        assert_eq!(store.count_by_url(&a.id).await.unwrap(), 3);
+        
+        let stats = writer.stats();
+        assert_eq!((stats.enqueued, stats.recorded, stats.batches), (4, 4, 1));
+        assert_eq!((stats.queued, stats.buffered, stats.failed), (0, 0, 0));
+    }
+    
+    #[tokio::test]
+    async fn test_batch_size_triggers_flush() {
+        let store = MemoryStore::new();
+        let url = create_url(&store, "code").await;
+        let config = ConfigBuilder::new().click_writer(100, 2, 60_000).build();
+        let writer = ClickWriter::spawn(Arc::new(store.clone()), &config);
+        
+        for _ in 0..5 {
+            writer.record(CreateClickEvent::new(&url.id, ClickInfo::default())).await;
+        }
+        
+        // دو batch کامل بدون flush دستی نوشته میشن
+        for _ in 0..50 {
+            if writer.stats().batches == 2 {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(writer.stats().batches, 2);
+        assert_eq!(clicks(&store, "code").await, 4);
+        
+        writer.flush().await;
+        assert_eq!(clicks(&store, "code").await, 5);
+    }
+    
+    #[tokio::test(start_paused = true)]
+    async fn test_interval_triggers_flush() {
+        let store = MemoryStore::new();
+        let url = create_url(&store, "code").await;
+        let config = ConfigBuilder::new().click_writer(100, 100, 1000).build();
+        let writer = ClickWriter::spawn(Arc::new(store.clone()), &config);
+        
+        writer.record(CreateClickEvent::new(&url.id, ClickInfo::default())).await;
+        time::sleep(Duration::from_millis(1500)).await;
+        
+        assert_eq!(clicks(&store, "code").await, 1);
+        assert_eq!(writer.stats().batches, 1);
+    }
+    
+    #[tokio::test]
+    async fn test_clicks_for_deleted_url_do_not_fail_batch() {
+        let store = MemoryStore::new();
+        let kept = create_url(&store, "kept").await;
+        let gone = create_url(&store, "gone").await;
+        let writer = ClickWriter::spawn(Arc::new(store.clone()), &Config::default());
+        
+        writer.record(CreateClickEvent::new(&kept.id, ClickInfo::default())).await;
+        writer.record(CreateClickEvent::new(&gone.id, ClickInfo::default())).await;
+        UrlStore::delete(&store, &gone.id).await.unwrap();
+        
+        // کد آزاد شده دوباره استفاده میشه؛ کلیک لینک قبلی بهش نمیرسه
+        create_url(&store, "gone").await;
+        writer.flush().await;
+        
+        assert_eq!(clicks(&store, "kept").await, 1);
+        assert_eq!(clicks(&store, "gone").await, 0);
+        let stats = writer.stats();
+        assert_eq!((stats.recorded, stats.failed), (1, 0));
+    }
+    
+    #[tokio::test]
+    async fn test_full_queue_is_counted() {
+        let store = MemoryStore::new();
+        let url = create_url(&store, "code").await;
+        let config = ConfigBuilder::new().click_writer(1, 100, 60_000).build();
+        let writer = ClickWriter::spawn(Arc::new(store.clone()), &config);
+        
+        // روی runtime تک thread، task جمع‌کننده بین دو record فرصت اجرا نداره
+        // مگه record منتظر بمونه
+        for _ in 0..3 {
+            writer.record(CreateClickEvent::new(&url.id, ClickInfo::default())).await;
+        }
+        writer.flush().await;
+        
+        let stats = writer.stats();
+        assert!(stats.queue_full >= 1);
+        assert_eq!(stats.enqueued, 3);
+        assert_eq!(clicks(&store, "code").await, 3);
+    }
+}
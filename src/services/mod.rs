@@ -12,7 +12,7 @@
 //! ├─────────────────┤
 //! │ Repository Layer│  <-- Data access
 //! ├─────────────────┤
-//! │    Database     │  <-- SQLite
+//! │    Database     │  <-- SQLite / PostgreSQL
 //! └─────────────────┘
 //! ```
 //!
@@ -24,14 +24,30 @@
 
 mod url_service;
 mod auth_service;
+mod api_key_service;
+mod admin_service;
+mod stats_service;
+mod redirect_cache;
+mod click_writer;
 
 pub use url_service::*;
 pub use auth_service::*;
+pub use api_key_service::*;
+pub use admin_service::*;
+pub use stats_service::*;
+pub use redirect_cache::*;
+pub use click_writer::*;
 
 use std::sync::Arc;
+use std::time::Instant;
 use crate::{
-    config::Config,
-    database::{Database, UrlRepository, UserRepository},
+    api::{FailureLimiter, RateLimiterState},
+    config::{Config, TrustedProxies},
+    database::{
+        ApiKeyRepository, ApiKeyStore, ClickRepository, ClickStore, Database, MemoryStore,
+        RefreshTokenRepository, RefreshTokenStore, UrlRepository, UrlStore, UserRepository,
+        UserStore,
+    },
 };
 
 // =====================================
@@ -58,38 +74,140 @@ pub struct AppState {
     
     /// سرویس احراز هویت
     pub auth_service: Arc<AuthService>,
+    
+    /// سرویس API key
+    pub api_key_service: Arc<ApiKeyService>,
+    
+    /// سرویس ادمین
+    pub admin_service: Arc<AdminService>,
+    
+    /// سرویس آمار
+    pub stats_service: Arc<StatsService>,
+    
+    /// Rate limiter مشترک برای API
+    pub rate_limiter: RateLimiterState,
+    
+    /// سقف رمز‌های اشتباه برای هر لینک (کلید: `link:<code>`)
+    pub unlock_limiter: FailureLimiter,
+    
+    /// پروکسی‌هایی که header‌های forwarding ازشون قبول میشه
+    pub trusted_proxies: Arc<TrustedProxies>,
+    
+    /// اتصال دیتابیس (برای health check)؛ `None` یعنی ذخیره‌سازی در حافظه
+    pub db: Option<Database>,
+    
+    /// زمان شروع برنامه (برای uptime)
+    pub started_at: Instant,
 }
 
 impl AppState {
-    /// ساخت AppState جدید
+    /// ساخت AppState جدید روی دیتابیس
     ///
     /// # مفاهیم:
     /// - Factory method: ساخت object پیچیده
     /// - Dependency Injection: همه وابستگی‌ها تزریق میشن
+    ///
+    /// # Panics
+    /// اگه خارج از runtime tokio صدا زده بشه (task ثبت کلیک‌ها spawn میشه)
     #[must_use]
     pub fn new(db: Database, config: Config) -> Self {
-        // ساخت repositories
-        let url_repo = UrlRepository::new(db.clone());
-        let user_repo = UserRepository::new(db);
+        Self::with_stores(Stores::sql(&db), Some(db), config)
+    }
+    
+    /// ساخت AppState بدون دیتابیس، با همه داده‌ها در حافظه
+    ///
+    /// برای نسخه دمو یا تست: هیچ فایلی ساخته نمیشه و داده‌ها با
+    /// بسته شدن برنامه از بین میرن (`DATABASE_URL=memory:`)
+    ///
+    /// # Panics
+    /// اگه خارج از runtime tokio صدا زده بشه (task ثبت کلیک‌ها spawn میشه)
+    #[must_use]
+    pub fn in_memory(config: Config) -> Self {
+        Self::with_stores(Stores::memory(&MemoryStore::new()), None, config)
+    }
+    
+    fn with_stores(stores: Stores, db: Option<Database>, config: Config) -> Self {
+        let started_at = Instant::now();
         
         // ساخت config به صورت Arc
         let config = Arc::new(config);
         
+        // cache ریدایرکت بین سرویس‌هایی که لینک تغییر میدن مشترکه
+        let redirect_cache = Arc::new(RedirectCache::from_config(&config));
+        
+        let click_writer = Arc::new(ClickWriter::spawn(stores.clicks.clone(), &config));
+        
         // ساخت services
         let url_service = Arc::new(UrlService::new(
-            url_repo,
+            stores.urls.clone(),
+            stores.clicks,
+            redirect_cache.clone(),
+            click_writer.clone(),
             config.clone(),
         ));
         
         let auth_service = Arc::new(AuthService::new(
-            user_repo,
+            stores.users.clone(),
+            stores.refresh_tokens,
             config.clone(),
         ));
         
+        let api_key_service = Arc::new(ApiKeyService::new(stores.api_keys));
+        
+        let admin_service = Arc::new(AdminService::new(
+            stores.users.clone(),
+            stores.urls.clone(),
+            redirect_cache.clone(),
+            config.clone(),
+        ));
+        
+        let stats_service = Arc::new(StatsService::new(
+            stores.urls,
+            stores.users,
+            redirect_cache,
+            click_writer,
+            config.clone(),
+        ));
+        
+        let rate_limiter = RateLimiterState::from_config(&config);
+        let unlock_limiter = FailureLimiter::per_minute(
+            config.unlock_attempts_per_minute,
+            config.unlock_attempts_per_minute,
+        );
+        
+        // `Config::validate` ورودی‌ها رو قبلا چک کرده؛ اگه نکرده باشه
+        // هیچ پروکسی‌ای مورد اعتماد نیست (حالت امن)
+        let trusted_proxies = Arc::new(TrustedProxies::from_config(&config).unwrap_or_default());
+        
         Self {
             config,
             url_service,
             auth_service,
+            api_key_service,
+            admin_service,
+            stats_service,
+            rate_limiter,
+            unlock_limiter,
+            trusted_proxies,
+            db,
+            started_at,
+        }
+    }
+    
+    /// مدت زمان اجرای برنامه (ثانیه)
+    #[must_use]
+    pub fn uptime_seconds(&self) -> u64 {
+        self.started_at.elapsed().as_secs()
+    }
+    
+    /// آزاد کردن منابع موقع خاموش شدن برنامه
+    ///
+    /// اول ثبت‌های کلیک در پس‌زمینه flush میشن، بعد pool دیتابیس بسته میشه.
+    /// باید بعد از بسته شدن سرور HTTP صدا زده بشه.
+    pub async fn shutdown(&self) {
+        self.url_service.drain_background_writes().await;
+        if let Some(db) = &self.db {
+            db.close().await;
         }
     }
     
@@ -100,6 +218,42 @@ impl AppState {
     }
 }
 
+// =====================================
+// Stores
+// =====================================
+/// storage‌هایی که سرویس‌ها روشون ساخته میشن
+struct Stores {
+    urls: Arc<dyn UrlStore>,
+    clicks: Arc<dyn ClickStore>,
+    users: Arc<dyn UserStore>,
+    refresh_tokens: Arc<dyn RefreshTokenStore>,
+    api_keys: Arc<dyn ApiKeyStore>,
+}
+
+impl Stores {
+    /// repository‌های SQL روی یک دیتابیس
+    fn sql(db: &Database) -> Self {
+        Self {
+            urls: Arc::new(UrlRepository::new(db.clone())),
+            clicks: Arc::new(ClickRepository::new(db.clone())),
+            users: Arc::new(UserRepository::new(db.clone())),
+            refresh_tokens: Arc::new(RefreshTokenRepository::new(db.clone())),
+            api_keys: Arc::new(ApiKeyRepository::new(db.clone())),
+        }
+    }
+    
+    /// همه storage‌ها روی یک `MemoryStore` مشترک
+    fn memory(store: &MemoryStore) -> Self {
+        Self {
+            urls: Arc::new(store.clone()),
+            clicks: Arc::new(store.clone()),
+            users: Arc::new(store.clone()),
+            refresh_tokens: Arc::new(store.clone()),
+            api_keys: Arc::new(store.clone()),
+        }
+    }
+}
+
 // =====================================
 // Service Trait (اختیاری)
 // =====================================
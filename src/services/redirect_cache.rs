@@ -0,0 +1,411 @@
+//! # Cache ریدایرکت
+//!
+//! بیشتر ترافیک روی چند لینک پرطرفدار میاد. این cache مقصد هر short_code
+//! رو در حافظه نگه میداره تا `GET /:code` برای لینک‌های داغ به دیتابیس نره.
+//!
+//! ## رفتار:
+//! - **LRU**: وقتی پر بشه، ورودی‌ای که از همه دیرتر استفاده شده بیرون میره
+//! - **TTL**: هر ورودی بعد از `REDIRECT_CACHE_TTL_SECS` دوباره از دیتابیس خونده میشه
+//! - **Negative caching**: کد ناموجود هم cache میشه تا اسکن کدهای تصادفی
+//!   دیتابیس رو درگیر نکنه
+//! - **Invalidation**: ساخت، ویرایش و حذف لینک ورودی رو پاک میکنه
+//!
+//! cache مال یک instance هست؛ در استقرار چند instance ای، تغییرات روی
+//! instance‌های دیگه حداکثر بعد از TTL دیده میشن.
+//!
+//! ## مفاهیم Rust:
+//! - **Interior Mutability**: `Mutex` دور `LruCache` (حتی `get` ترتیب رو عوض میکنه)
+//! - **Atomics**: شمارنده‌های hit/miss بدون lock
+//! - lock هیچوقت روی `.await` نگه داشته نمیشه
+
+use std::{
+    future::Future,
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, MutexGuard, PoisonError,
+    },
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use lru::LruCache;
+use tokio::time::Instant;
+
+use crate::{
+    config::Config,
+    error::Result,
+    models::{RedirectCacheStats, RedirectType, Url},
+};
+
+// =====================================
+// Cached Redirect
+// =====================================
+/// اطلاعات لازم برای redirect یک short_code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedRedirect {
+    /// شناسه URL برای ثبت کلیک
+    pub url_id: String,
+    pub original_url: String,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub redirect_type: RedirectType,
+    /// هش رمز لینک؛ بدون رمز درست مقصد فاش نمیشه
+    pub password_hash: Option<String>,
+    /// سقف کلیک؛ شمارنده در cache نیست و چکش با دیتابیس انجام میشه
+    pub max_clicks: Option<i64>,
+}
+
+impl CachedRedirect {
+    /// آیا لینک منقضی شده؟ (مثل `Url::is_expired`)
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|exp| exp < Utc::now())
+    }
+    
+    /// آیا برای redirect رمز لازمه؟
+    #[must_use]
+    pub fn is_password_protected(&self) -> bool {
+        self.password_hash.is_some()
+    }
+}
+
+impl From<&Url> for CachedRedirect {
+    fn from(url: &Url) -> Self {
+        Self {
+            url_id: url.id.clone(),
+            original_url: url.original_url.clone(),
+            starts_at: url.starts_at,
+            expires_at: url.expires_at,
+            redirect_type: url.redirect_type,
+            password_hash: url.password_hash.clone(),
+            max_clicks: url.max_clicks,
+        }
+    }
+}
+
+/// یک ورودی cache - `None` یعنی کد وجود نداره
+#[derive(Debug)]
+struct Entry {
+    target: Option<CachedRedirect>,
+    cached_at: Instant,
+}
+
+#[derive(Debug)]
+struct Inner {
+    entries: LruCache<String, Entry>,
+    /// با هر invalidation زیاد میشه؛ load‌ای که قبلش شروع شده نتیجه‌اش رو ذخیره نمیکنه
+    generation: u64,
+}
+
+// =====================================
+// Redirect Cache
+// =====================================
+/// cache با ظرفیت محدود از short_code به مقصد
+///
+/// # مثال
+/// ```rust,ignore
+/// let target = cache
+///     .get_or_load(code, || repo.find_by_short_code(code))
+///     .await?;
+/// ```
+#[derive(Debug)]
+pub struct RedirectCache {
+    /// `None` یعنی cache غیرفعاله (ظرفیت صفر)
+    inner: Option<Mutex<Inner>>,
+    capacity: usize,
+    ttl: Duration,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl RedirectCache {
+    /// ساخت cache جدید
+    ///
+    /// ظرفیت صفر یعنی cache غیرفعاله و همه درخواست‌ها مستقیم load میشن
+    #[must_use]
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        let inner = NonZeroUsize::new(capacity).map(|capacity| {
+            Mutex::new(Inner {
+                entries: LruCache::new(capacity),
+                generation: 0,
+            })
+        });
+        
+        Self {
+            inner,
+            capacity,
+            ttl,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+    
+    /// ساخت cache از تنظیمات (`REDIRECT_CACHE_CAPACITY`، `REDIRECT_CACHE_TTL_SECS`)
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(
+            config.redirect_cache_capacity,
+            Duration::from_secs(config.redirect_cache_ttl_secs),
+        )
+    }
+    
+    /// cache غیرفعال
+    #[must_use]
+    pub fn disabled() -> Self {
+        Self::new(0, Duration::ZERO)
+    }
+    
+    /// گرفتن مقصد از cache، یا load و ذخیره اون
+    ///
+    /// # مفاهیم:
+    /// - خطای `load` cache نمیشه و مستقیم برمیگرده
+    /// - اگه وسط load یه invalidation اتفاق بیفته، نتیجه (که ممکنه کهنه باشه)
+    ///   فقط برگردونده میشه و ذخیره نمیشه
+    ///
+    /// # Returns
+    /// `None` اگه کد وجود نداشته باشه
+    pub async fn get_or_load<F, Fut>(&self, short_code: &str, load: F) -> Result<Option<CachedRedirect>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Option<Url>>>,
+    {
+        let Some(inner) = &self.inner else {
+            return Ok(load().await?.as_ref().map(CachedRedirect::from));
+        };
+        
+        let generation = {
+            let mut inner = lock(inner);
+            
+            let cached = inner.entries.get(short_code).map(|entry| {
+                (entry.cached_at.elapsed() < self.ttl).then(|| entry.target.clone())
+            });
+            
+            match cached {
+                Some(Some(target)) => {
+                    self.hits.fetch_add(1, Ordering::Relaxed);
+                    return Ok(target);
+                }
+                // منقضی شده
+                Some(None) => {
+                    inner.entries.pop(short_code);
+                }
+                None => {}
+            }
+            
+            inner.generation
+        };
+        
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let target = load().await?.as_ref().map(CachedRedirect::from);
+        
+        let mut inner = lock(inner);
+        if inner.generation == generation {
+            inner.entries.put(short_code.to_string(), Entry {
+                target: target.clone(),
+                cached_at: Instant::now(),
+            });
+        }
+        
+        Ok(target)
+    }
+    
+    /// حذف ورودی یک کد (بعد از ساخت، ویرایش یا حذف لینک)
+    pub fn invalidate(&self, short_code: &str) {
+        if let Some(inner) = &self.inner {
+            let mut inner = lock(inner);
+            inner.entries.pop(short_code);
+            inner.generation += 1;
+        }
+    }
+    
+    /// حذف ورودی‌های چند URL با شناسه (برای حذف دسته‌ای)
+    pub fn invalidate_url_ids(&self, url_ids: &[String]) {
+        if url_ids.is_empty() {
+            return;
+        }
+        
+        if let Some(inner) = &self.inner {
+            let mut inner = lock(inner);
+            
+            let codes: Vec<String> = inner
+                .entries
+                .iter()
+                .filter(|(_, entry)| {
+                    entry.target.as_ref().is_some_and(|target| url_ids.contains(&target.url_id))
+                })
+                .map(|(code, _)| code.clone())
+                .collect();
+            
+            for code in codes {
+                inner.entries.pop(&code);
+            }
+            inner.generation += 1;
+        }
+    }
+    
+    /// شمارنده‌ها و وضعیت فعلی cache
+    #[must_use]
+    pub fn stats(&self) -> RedirectCacheStats {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let total = hits + misses;
+        
+        RedirectCacheStats {
+            enabled: self.inner.is_some(),
+            capacity: self.capacity,
+            entries: self.inner.as_ref().map_or(0, |inner| lock(inner).entries.len()),
+            hits,
+            misses,
+            hit_ratio: if total == 0 { 0.0 } else { hits as f64 / total as f64 },
+        }
+    }
+}
+
+/// گرفتن lock - داده‌ها همیشه سازگار هستن، پس poison نادیده گرفته میشه
+fn lock(inner: &Mutex<Inner>) -> MutexGuard<'_, Inner> {
+    inner.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+// =====================================
+// Tests
+// =====================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    
+    fn url(code: &str) -> Url {
+        let now = Utc::now();
+        Url {
+            id: format!("id-{}", code),
+            short_code: code.to_string(),
+            original_url: format!("https://example.com/{}", code),
+            title: None,
+            clicks: 0,
+            user_id: None,
+            expires_at: None,
+            redirect_type: RedirectType::default(),
+            password_hash: None,
+            max_clicks: None,
+            starts_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+    
+    /// load که تعداد فراخوانی‌ها رو میشمره
+    async fn load(cache: &RedirectCache, code: &str, exists: bool, loads: &AtomicUsize) -> Option<CachedRedirect> {
+        cache
+            .get_or_load(code, || async {
+                loads.fetch_add(1, Ordering::SeqCst);
+                Ok(exists.then(|| url(code)))
+            })
+            .await
+            .unwrap()
+    }
+    
+    #[tokio::test]
+    async fn test_hits_and_negative_caching() {
+        let cache = RedirectCache::new(10, Duration::from_secs(60));
+        let loads = AtomicUsize::new(0);
+        
+        for _ in 0..3 {
+            let target = load(&cache, "hot", true, &loads).await.unwrap();
+            assert_eq!(target.original_url, "https://example.com/hot");
+        }
+        
+        // کد ناموجود هم فقط یک بار load میشه
+        for _ in 0..2 {
+            assert!(load(&cache, "nope", false, &loads).await.is_none());
+        }
+        
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+        
+        let stats = cache.stats();
+        assert!(stats.enabled);
+        assert_eq!((stats.hits, stats.misses, stats.entries), (3, 2, 2));
+        assert!((stats.hit_ratio - 0.6).abs() < f64::EPSILON);
+    }
+    
+    #[tokio::test(start_paused = true)]
+    async fn test_entries_expire_after_ttl() {
+        let cache = RedirectCache::new(10, Duration::from_secs(60));
+        let loads = AtomicUsize::new(0);
+        
+        load(&cache, "code", true, &loads).await;
+        tokio::time::advance(Duration::from_secs(59)).await;
+        load(&cache, "code", true, &loads).await;
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+        
+        tokio::time::advance(Duration::from_secs(2)).await;
+        load(&cache, "code", true, &loads).await;
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+    }
+    
+    #[tokio::test]
+    async fn test_invalidation() {
+        let cache = RedirectCache::new(10, Duration::from_secs(60));
+        let loads = AtomicUsize::new(0);
+        
+        // ورودی منفی بعد از ساخت لینک پاک میشه
+        assert!(load(&cache, "new", false, &loads).await.is_none());
+        cache.invalidate("new");
+        assert!(load(&cache, "new", true, &loads).await.is_some());
+        
+        load(&cache, "other", true, &loads).await;
+        cache.invalidate_url_ids(&["id-new".to_string()]);
+        assert_eq!(cache.stats().entries, 1);
+        
+        load(&cache, "new", true, &loads).await;
+        load(&cache, "other", true, &loads).await;
+        assert_eq!(loads.load(Ordering::SeqCst), 4);
+    }
+    
+    #[tokio::test]
+    async fn test_invalidation_during_load_is_not_overwritten() {
+        let cache = RedirectCache::new(10, Duration::from_secs(60));
+        
+        // ویرایش همزمان با load: نتیجه قدیمی نباید cache بشه
+        let stale = cache
+            .get_or_load("race", || async {
+                cache.invalidate("race");
+                Ok(Some(url("race")))
+            })
+            .await
+            .unwrap();
+        assert!(stale.is_some());
+        assert_eq!(cache.stats().entries, 0);
+    }
+    
+    #[tokio::test]
+    async fn test_least_recently_used_is_evicted() {
+        let cache = RedirectCache::new(2, Duration::from_secs(60));
+        let loads = AtomicUsize::new(0);
+        
+        load(&cache, "a", true, &loads).await;
+        load(&cache, "b", true, &loads).await;
+        load(&cache, "a", true, &loads).await;
+        // "b" کمتر استفاده شده و بیرون میره
+        load(&cache, "c", true, &loads).await;
+        
+        load(&cache, "a", true, &loads).await;
+        assert_eq!(loads.load(Ordering::SeqCst), 3);
+        load(&cache, "b", true, &loads).await;
+        assert_eq!(loads.load(Ordering::SeqCst), 4);
+    }
+    
+    #[tokio::test]
+    async fn test_disabled_cache_always_loads() {
+        let cache = RedirectCache::disabled();
+        let loads = AtomicUsize::new(0);
+        
+        load(&cache, "code", true, &loads).await;
+        load(&cache, "code", true, &loads).await;
+        
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+        assert!(!cache.stats().enabled);
+        assert_eq!(cache.stats().hits, 0);
+    }
+}
+
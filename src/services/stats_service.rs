@@ -0,0 +1,176 @@
+//! # سرویس آمار (Stats Service)
+//!
+//! ترکیب آمار لینک‌ها و کاربران برای داشبورد ادمین
+//!
+//! ## مفاهیم:
+//! - چند query مستقل روی repository‌های مختلف
+//! - پر کردن روزهای خالی در سری زمانی
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use chrono::{Duration, Utc};
+
+use crate::{
+    config::Config,
+    database::{UrlStore, UserStore},
+    error::Result,
+    models::{DailyCount, SystemStats, UrlResponse},
+};
+
+use super::{ClickWriter, RedirectCache, Service};
+
+/// تعداد روزهای نمودار لینک‌های ساخته شده
+const CREATED_PER_DAY_WINDOW: i64 = 30;
+
+/// تعداد لینک‌های جدول پرکلیک‌ترین‌ها
+const TOP_URLS_LIMIT: u32 = 10;
+
+// =====================================
+// Stats Service
+// =====================================
+/// سرویس آمار کل سیستم
+#[derive(Debug, Clone)]
+pub struct StatsService {
+    url_repo: Arc<dyn UrlStore>,
+    user_repo: Arc<dyn UserStore>,
+    redirect_cache: Arc<RedirectCache>,
+    click_writer: Arc<ClickWriter>,
+    config: Arc<Config>,
+}
+
+impl Service for StatsService {}
+
+impl StatsService {
+    /// ساخت سرویس جدید
+    #[must_use]
+    pub fn new(
+        url_repo: Arc<dyn UrlStore>,
+        user_repo: Arc<dyn UserStore>,
+        redirect_cache: Arc<RedirectCache>,
+        click_writer: Arc<ClickWriter>,
+        config: Arc<Config>,
+    ) -> Self {
+        Self { url_repo, user_repo, redirect_cache, click_writer, config }
+    }
+    
+    /// آمار کل سیستم
+    ///
+    /// # مفاهیم:
+    /// - `tokio::try_join!`: اجرای همزمان query‌ها
+    pub async fn get_system_stats(&self) -> Result<SystemStats> {
+        let today = Utc::now().date_naive();
+        let window_start = today - Duration::days(CREATED_PER_DAY_WINDOW - 1);
+        let since = window_start.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+        
+        let (url_stats, (active_urls, expired_urls), total_users, active_users, created, top) = tokio::try_join!(
+            self.url_repo.get_stats(),
+            self.url_repo.count_by_status(),
+            self.user_repo.count(),
+            self.user_repo.count_active(),
+            self.url_repo.count_created_per_day(since),
+            self.url_repo.find_top_by_clicks(TOP_URLS_LIMIT),
+        )?;
+        
+        // روزهای بدون لینک هم با صفر نمایش داده میشن
+        let created: HashMap<String, i64> = created
+            .into_iter()
+            .map(|day| (day.date, day.count))
+            .collect();
+        let urls_created_per_day = window_start
+            .iter_days()
+            .take_while(|day| *day <= today)
+            .map(|day| {
+                let date = day.format("%Y-%m-%d").to_string();
+                let count = created.get(&date).copied().unwrap_or(0);
+                DailyCount { date, count }
+            })
+            .collect();
+        
+        let top_urls = top
+            .iter()
+            .map(|url| UrlResponse::from_url(url, &self.config.base_url))
+            .collect();
+        
+        Ok(SystemStats {
+            total_urls: url_stats.total_urls,
+            total_clicks: url_stats.total_clicks,
+            total_users,
+            average_clicks_per_url: url_stats.avg_clicks,
+            active_users,
+            active_urls,
+            expired_urls,
+            urls_created_per_day,
+            top_urls,
+            redirect_cache: self.redirect_cache.stats(),
+            click_writer: self.click_writer.stats(),
+        })
+    }
+}
+
+// =====================================
+// Tests
+// =====================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{ClickRepository, Database, UrlRepository, UserRepository};
+    use crate::models::{CreateUrl, CreateUser, RedirectType};
+    
+    #[tokio::test]
+    async fn test_system_stats() {
+        let db = Database::ephemeral().await.unwrap();
+        let url_repo = UrlRepository::new(db.clone());
+        let user_repo = UserRepository::new(db.clone());
+        let config = Config::default();
+        let service = StatsService::new(
+            Arc::new(url_repo.clone()),
+            Arc::new(user_repo.clone()),
+            Arc::new(RedirectCache::disabled()),
+            Arc::new(ClickWriter::spawn(Arc::new(ClickRepository::new(db.clone())), &config)),
+            Arc::new(config),
+        );
+        
+        user_repo
+            .create(&CreateUser::new("a@example.com", "password123", None).unwrap())
+            .await
+            .unwrap();
+        
+        for (code, clicks, expires_at) in [
+            ("popular", 5, None),
+            ("quiet", 1, None),
+            ("gone", 3, Some(Utc::now() - Duration::hours(1))),
+        ] {
+            url_repo.create(&CreateUrl {
+                id: nanoid::nanoid!(21),
+                short_code: code.to_string(),
+                original_url: format!("https://example.com/{}", code),
+                title: None,
+                user_id: None,
+                expires_at,
+                redirect_type: RedirectType::default(),
+                password_hash: None,
+                max_clicks: None,
+                starts_at: None,
+            }).await.unwrap();
+            for _ in 0..clicks {
+                url_repo.increment_clicks(code).await.unwrap();
+            }
+        }
+        
+        let stats = service.get_system_stats().await.unwrap();
+        
+        assert_eq!(stats.total_urls, 3);
+        assert_eq!(stats.total_clicks, 9);
+        assert_eq!(stats.total_users, 1);
+        assert_eq!(stats.active_users, 1);
+        assert_eq!((stats.active_urls, stats.expired_urls), (2, 1));
+        
+        // 30 روز، همه لینک‌ها امروز ساخته شدن
+        assert_eq!(stats.urls_created_per_day.len(), 30);
+        assert_eq!(stats.urls_created_per_day.last().unwrap().count, 3);
+        assert_eq!(stats.urls_created_per_day.iter().map(|d| d.count).sum::<i64>(), 3);
+        
+        let top: Vec<&str> = stats.top_urls.iter().map(|u| u.short_code.as_str()).collect();
+        assert_eq!(top, vec!["popular", "gone", "quiet"]);
+    }
+}
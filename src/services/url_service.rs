@@ -14,15 +14,33 @@ use validator::Validate;
 
 use crate::{
     config::Config,
-    database::UrlRepository,
+    database::{ClickStore, UrlStore},
     error::{AppError, Result, OptionExt},
     models::{
-        CreateUrl, CreateUrlRequest, Url, UrlBuilder, UrlResponse,
+        hash_password, verify_password, AnalyticsQuery, BatchDeleteRequest, BatchOperationResponse, ClickDimension, ClickInfo, CreateClickEvent,
+        CreateUrlRequest, PaginatedResult, Pagination, SearchParams, UpdateUrlRequest,
+        UrlAnalytics, UrlBuilder, UrlFilter, UrlResponse,
     },
     utils,
 };
 
-use super::Service;
+use super::{CachedRedirect, ClickWriter, RedirectCache, Service};
+
+/// تعداد آیتم‌ها در هر لیست "top" از analytics
+const ANALYTICS_TOP_LIMIT: u32 = 10;
+
+// =====================================
+// Redirect Outcome
+// =====================================
+/// نتیجه درخواست redirect
+#[derive(Debug, Clone)]
+pub enum RedirectOutcome {
+    /// مقصد آزاده و کلیک ثبت شده
+    Redirect(CachedRedirect),
+    
+    /// لینک رمز داره؛ تا وارد شدن رمز درست مقصد فاش نمیشه و کلیکی ثبت نمیشه
+    PasswordRequired,
+}
 
 // =====================================
 // URL Service
@@ -32,11 +50,20 @@ use super::Service;
 /// # مسئولیت‌ها:
 /// - ساخت URL کوتاه
 /// - Redirect و افزایش counter
+/// - ثبت رویداد هر کلیک
 /// - اعتبارسنجی
 /// - مدیریت انقضا
+///
+/// ذخیره‌سازی از طریق trait object‌ها تزریق میشه، پس سرویس با SQL
+/// و `MemoryStore` یکسان کار میکنه.
 #[derive(Debug, Clone)]
 pub struct UrlService {
-    repo: UrlRepository,
+    repo: Arc<dyn UrlStore>,
+    click_repo: Arc<dyn ClickStore>,
+    /// cache مقصد کدهای کوتاه برای redirect (مشترک با `AdminService`)
+    redirect_cache: Arc<RedirectCache>,
+    /// صف ثبت دسته‌ای کلیک‌ها (مشترک با `StatsService`)
+    click_writer: Arc<ClickWriter>,
     config: Arc<Config>,
 }
 
@@ -46,8 +73,20 @@ impl Service for UrlService {}
 impl UrlService {
     /// ساخت سرویس جدید
     #[must_use]
-    pub fn new(repo: UrlRepository, config: Arc<Config>) -> Self {
-        Self { repo, config }
+    pub fn new(
+        repo: Arc<dyn UrlStore>,
+        click_repo: Arc<dyn ClickStore>,
+        redirect_cache: Arc<RedirectCache>,
+        click_writer: Arc<ClickWriter>,
+        config: Arc<Config>,
+    ) -> Self {
+        Self {
+            repo,
+            click_repo,
+            redirect_cache,
+            click_writer,
+            config,
+        }
     }
     
     /// ساخت URL کوتاه جدید
@@ -117,15 +156,35 @@ impl UrlService {
             builder = builder.user_id(user);
         }
         
+        if let Some(starts_at) = request.starts_at {
+            builder = builder.starts_at(starts_at);
+        }
+        
         if let Some(hours) = request.expires_in_hours {
             builder = builder.expires_in_hours(hours);
         }
         
+        if let Some(password) = &request.password {
+            builder = builder.password_hash(hash_password(password)?);
+        }
+        
+        if let Some(max_clicks) = request.max_clicks {
+            builder = builder.max_clicks(max_clicks);
+        }
+        
+        // بدون مقدار صریح، پیش‌فرض سرور در لحظه ساخت ذخیره میشه
+        builder = builder.redirect_type(
+            request.redirect_type.unwrap_or(self.config.default_redirect_type)
+        );
+        
         let create_url = builder.build()?;
         
         // Step 5: ذخیره در دیتابیس
         let url = self.repo.create(&create_url).await?;
         
+        // شاید قبلا "کد ناموجود" cache شده باشه
+        self.redirect_cache.invalidate(&url.short_code);
+        
         info!(short_code = %url.short_code, "Created new short URL");
         
         // Step 6: تبدیل به response
@@ -135,73 +194,260 @@ impl UrlService {
     /// گرفتن URL اصلی برای redirect
     ///
     /// # مفاهیم:
-    /// - Side effect: افزایش counter
-    /// - Expiration check
-    #[instrument(skip(self))]
-    pub async fn get_original_url(&self, short_code: &str) -> Result<String> {
+    /// - مقصد از `RedirectCache` خونده میشه؛ فقط miss به دیتابیس میره
+    /// - Side effect: افزایش counter و ثبت رویداد کلیک (از طریق `ClickWriter`)
+    /// - Expiration و `starts_at` check (روی داده cache شده هم انجام میشه)
+    /// - لینک با `max_clicks`: شمارنده همینجا و به صورت اتمی در دیتابیس چک و زیاد میشه
+    ///
+    /// # Arguments
+    /// * `short_code` - کد کوتاه
+    /// * `click` - اطلاعات کلاینت برای ثبت در `click_events`
+    ///
+    /// # Returns
+    /// مقصد و نوع redirect، یا `PasswordRequired` برای لینک رمزدار
+    ///
+    /// # Errors
+    /// - `NotFound` اگه کد وجود نداشته باشه یا منقضی شده باشه
+    /// - `NotYetAvailable` اگه زمان شروع لینک نرسیده باشه
+    /// - `Gone` اگه سقف کلیک لینک پر شده باشه
+    #[instrument(skip(self, click))]
+    pub async fn get_original_url(&self, short_code: &str, click: ClickInfo) -> Result<RedirectOutcome> {
+        let target = self.resolve(short_code).await?;
+        
+        if target.is_password_protected() {
+            return Ok(RedirectOutcome::PasswordRequired);
+        }
+        
+        self.record_click(short_code, &target, click).await?;
+        
+        Ok(RedirectOutcome::Redirect(target))
+    }
+    
+    /// باز کردن لینک رمزدار
+    ///
+    /// # مفاهیم:
+    /// - رمز با هش Argon2 ذخیره شده مقایسه میشه (مثل ورود کاربر)
+    /// - کلیک فقط بعد از رمز درست ثبت میشه
+    /// - محدودیت تعداد تلاش در لایه HTTP اعمال میشه
+    ///
+    /// # Errors
+    /// - `Unauthorized` اگه رمز اشتباه باشه
+    /// - `Gone` اگه سقف کلیک لینک پر شده باشه
+    #[instrument(skip(self, password, click))]
+    pub async fn unlock_url(
+        &self,
+        short_code: &str,
+        password: &str,
+        click: ClickInfo,
+    ) -> Result<CachedRedirect> {
+        let target = self.resolve(short_code).await?;
+        
+        if let Some(password_hash) = &target.password_hash {
+            if !verify_password(password_hash, password)? {
+                warn!(short_code = %short_code, "Invalid link password");
+                return Err(AppError::Unauthorized("Invalid link password".to_string()));
+            }
+        }
+        
+        self.record_click(short_code, &target, click).await?;
+        
+        Ok(target)
+    }
+    
+    /// پیدا کردن مقصد یک کد و چک کردن اینکه هنوز قابل استفاده هست
+    async fn resolve(&self, short_code: &str) -> Result<CachedRedirect> {
         // پیدا کردن URL
-        let url = self.repo
-            .find_by_short_code(short_code)
+        let target = self.redirect_cache
+            .get_or_load(short_code, || self.repo.find_by_short_code(short_code))
             .await?
             .ok_or_not_found(format!("URL '{}' not found", short_code))?;
         
         // بررسی انقضا
-        if url.is_expired() {
+        if target.is_expired() {
             warn!(short_code = %short_code, "Attempted to access expired URL");
             return Err(AppError::NotFound(
                 "This URL has expired".to_string()
             ));
         }
         
-        // افزایش counter (در پس‌زمینه انجام میشه)
-        // Clone کردن برای انتقال به task
-        let repo = self.repo.clone();
-        let code = short_code.to_string();
+        // بررسی زمان شروع
+        if let Some(starts_at) = target.starts_at.filter(|start| *start > Utc::now()) {
+            warn!(short_code = %short_code, %starts_at, "Attempted to access URL before its start time");
+            return Err(AppError::NotYetAvailable(starts_at));
+        }
         
-        // Spawn یک task برای افزایش counter
-        // این باعث میشه redirect سریع‌تر باشه
-        tokio::spawn(async move {
-            if let Err(e) = repo.increment_clicks(&code).await {
-                warn!(error = %e, "Failed to increment click count");
-            }
-        });
+        Ok(target)
+    }
+    
+    /// افزایش counter و ثبت کلیک به صورت دسته‌ای در پس‌زمینه
+    ///
+    /// redirect منتظر دیتابیس نمیمونه، فقط اگه صف پر باشه. استثنا لینک‌های
+    /// با `max_clicks` هستن: شمارنده با یک UPDATE شرطی زیاد میشه تا redirect‌های
+    /// همزمان از سقف رد نشن، و فقط رویداد کلیک به صف میره.
+    async fn record_click(&self, short_code: &str, target: &CachedRedirect, click: ClickInfo) -> Result<()> {
+        let event = CreateClickEvent::new(&target.url_id, click);
+        
+        if target.max_clicks.is_none() {
+            self.click_writer.record(event).await;
+            return Ok(());
+        }
         
-        Ok(url.original_url)
+        if !self.repo.increment_clicks(short_code).await? {
+            warn!(short_code = %short_code, "Attempted to access exhausted URL");
+            return Err(AppError::Gone(
+                "This URL has reached its click limit".to_string()
+            ));
+        }
+        
+        self.click_writer.record_event(event).await;
+        Ok(())
     }
     
     /// گرفتن اطلاعات کامل URL
+    ///
+    /// مقصد لینک رمزدار فقط به صاحبش نشون داده میشه؛
+    /// وگرنه رمز با یک درخواست info دور زده میشد
     #[instrument(skip(self))]
-    pub async fn get_url_info(&self, short_code: &str) -> Result<UrlResponse> {
+    pub async fn get_url_info(&self, short_code: &str, viewer: Option<&str>) -> Result<UrlResponse> {
         let url = self.repo
             .find_by_short_code(short_code)
             .await?
             .ok_or_not_found(format!("URL '{}' not found", short_code))?;
         
+        if url.is_password_protected() && (viewer.is_none() || url.user_id.as_deref() != viewer) {
+            return Err(AppError::Forbidden(
+                "This URL is password protected".to_string()
+            ));
+        }
+        
         Ok(UrlResponse::from_url(&url, &self.config.base_url))
     }
     
     /// لیست URL‌های یک کاربر
-    pub async fn get_user_urls(&self, user_id: &str) -> Result<Vec<UrlResponse>> {
-        let urls = self.repo.find_by_user(user_id).await?;
+    ///
+    /// # مفاهیم:
+    /// - جستجو در عنوان و مقصد، فیلتر وضعیت، مرتب‌سازی
+    /// - صفحه‌بندی با `PaginatedResult`
+    ///
+    /// # Errors
+    /// `BadRequest` اگه پارامترهای جستجو نامعتبر باشن
+    pub async fn get_user_urls(
+        &self,
+        user_id: &str,
+        search: &SearchParams,
+        pagination: &Pagination,
+    ) -> Result<PaginatedResult<UrlResponse>> {
+        let filter = UrlFilter::from_params(search)?;
+        let pagination = pagination.normalized();
+        
+        let (urls, total) = self.repo
+            .search_by_user(user_id, &filter, &pagination)
+            .await?;
         
         let responses: Vec<UrlResponse> = urls
             .iter()
             .map(|url| UrlResponse::from_url(url, &self.config.base_url))
             .collect();
         
-        Ok(responses)
+        Ok(PaginatedResult::new(responses, &pagination, total))
+    }
+    
+    /// ویرایش URL
+    ///
+    /// # مفاهیم:
+    /// - Partial update: فقط فیلدهای ارسال شده تغییر میکنن
+    /// - مقدار خالی (`title: ""`، `expires_in_hours: 0`، `password: ""`) فیلد رو پاک میکنه
+    /// - `null` صریح برای `password`، `max_clicks` و `starts_at` هم فیلد رو پاک میکنه
+    /// - شمارنده کلیک و short_code حفظ میشن
+    ///
+    /// # Errors
+    /// - `NotFound`: URL پیدا نشد
+    /// - `Forbidden`: کاربر مالک لینک نیست
+    /// - `BadRequest`: URL مقصد نامعتبر یا انقضای قبل از `starts_at`
+    #[instrument(skip(self, request))]
+    pub async fn update_url(
+        &self,
+        short_code: &str,
+        user_id: &str,
+        request: UpdateUrlRequest,
+    ) -> Result<UrlResponse> {
+        request.validate()?;
+        
+        let mut url = self.repo
+            .find_by_short_code(short_code)
+            .await?
+            .ok_or_not_found(format!("URL '{}' not found", short_code))?;
+        
+        // بررسی مالکیت
+        if url.user_id.as_deref() != Some(user_id) {
+            return Err(AppError::Forbidden(
+                "You don't have permission to update this URL".to_string()
+            ));
+        }
+        
+        if let Some(original_url) = request.url {
+            if !utils::is_valid_url(&original_url) {
+                return Err(AppError::BadRequest("Invalid URL format".to_string()));
+            }
+            url.original_url = original_url;
+        }
+        
+        if let Some(title) = request.title {
+            url.title = (!title.is_empty()).then_some(title);
+        }
+        
+        if let Some(hours) = request.expires_in_hours {
+            url.expires_at = (hours > 0).then(|| utils::expires_at_from_hours(hours));
+        }
+        
+        if let Some(starts_at) = request.starts_at {
+            url.starts_at = starts_at;
+        }
+        
+        // همون قانون UrlBuilder: انقضا باید بعد از شروع باشه
+        if let (Some(starts_at), Some(expires_at)) = (url.starts_at, url.expires_at) {
+            if starts_at >= expires_at {
+                return Err(AppError::BadRequest(
+                    "starts_at must be before the expiration time".to_string()
+                ));
+            }
+        }
+        
+        if let Some(redirect_type) = request.redirect_type {
+            url.redirect_type = redirect_type;
+        }
+        
+        if let Some(max_clicks) = request.max_clicks {
+            url.max_clicks = max_clicks.map(i64::from);
+        }
+        
+        // طول رمز در `request.validate()` چک شده
+        if let Some(password) = request.password {
+            url.password_hash = password.as_deref().map(hash_password).transpose()?;
+        }
+        
+        let url = self.repo.update(&url).await?;
+        self.redirect_cache.invalidate(short_code);
+        
+        info!(short_code = %short_code, "Updated URL");
+        
+        Ok(UrlResponse::from_url(&url, &self.config.base_url))
     }
     
     /// حذف URL
     ///
     /// # Arguments
     /// * `short_code` - کد کوتاه
-    /// * `user_id` - شناسه کاربر (برای authorization)
+    /// * `user_id` - شناسه کاربر (فقط مالک میتونه حذف کنه)
+    ///
+    /// # Errors
+    /// - `NotFound`: URL پیدا نشد
+    /// - `Forbidden`: کاربر مالک لینک نیست (لینک‌های ناشناس هم مالکی ندارن)
     #[instrument(skip(self))]
     pub async fn delete_url(
         &self,
         short_code: &str,
-        user_id: Option<&str>,
+        user_id: &str,
     ) -> Result<()> {
         // پیدا کردن URL
         let url = self.repo
@@ -210,21 +456,111 @@ impl UrlService {
             .ok_or_not_found(format!("URL '{}' not found", short_code))?;
         
         // بررسی مالکیت
-        if let Some(uid) = user_id {
-            if url.user_id.as_deref() != Some(uid) {
-                return Err(AppError::Forbidden(
-                    "You don't have permission to delete this URL".to_string()
-                ));
-            }
+        if url.user_id.as_deref() != Some(user_id) {
+            return Err(AppError::Forbidden(
+                "You don't have permission to delete this URL".to_string()
+            ));
         }
         
         // حذف
         self.repo.delete(&url.id).await?;
+        self.redirect_cache.invalidate(short_code);
         
         info!(short_code = %short_code, "Deleted URL");
         Ok(())
     }
     
+    /// گرفتن analytics یک URL
+    ///
+    /// # مفاهیم:
+    /// - Authorization: فقط مالک لینک دسترسی داره
+    /// - محاسبه بازه پیش‌فرض بر اساس interval
+    ///
+    /// # Errors
+    /// - `NotFound`: URL پیدا نشد
+    /// - `Forbidden`: کاربر مالک لینک نیست
+    /// - `BadRequest`: بازه زمانی نامعتبر
+    #[instrument(skip(self, query))]
+    pub async fn get_url_analytics(
+        &self,
+        short_code: &str,
+        user_id: &str,
+        query: AnalyticsQuery,
+    ) -> Result<UrlAnalytics> {
+        let url = self.repo
+            .find_by_short_code(short_code)
+            .await?
+            .ok_or_not_found(format!("URL '{}' not found", short_code))?;
+        
+        // بررسی مالکیت
+        if url.user_id.as_deref() != Some(user_id) {
+            return Err(AppError::Forbidden(
+                "You don't have permission to view analytics for this URL".to_string()
+            ));
+        }
+        
+        // محاسبه بازه
+        let interval = query.interval;
+        let to = query.to.unwrap_or_else(Utc::now);
+        let from = query.from.unwrap_or(to - interval.default_span());
+        
+        if from >= to {
+            return Err(AppError::BadRequest(
+                "'from' must be before 'to'".to_string()
+            ));
+        }
+        
+        let total_clicks = self.click_repo.count_in_range(&url.id, from, to).await?;
+        let timeline = self.click_repo.timeline(&url.id, interval, from, to).await?;
+        let top_referrers = self.click_repo
+            .top_values(&url.id, ClickDimension::Referer, from, to, ANALYTICS_TOP_LIMIT)
+            .await?;
+        let top_user_agents = self.click_repo
+            .top_values(&url.id, ClickDimension::UserAgent, from, to, ANALYTICS_TOP_LIMIT)
+            .await?;
+        let countries = self.click_repo
+            .top_values(&url.id, ClickDimension::Country, from, to, ANALYTICS_TOP_LIMIT)
+            .await?;
+        
+        Ok(UrlAnalytics {
+            short_code: url.short_code,
+            interval,
+            from,
+            to,
+            total_clicks,
+            timeline,
+            top_referrers,
+            top_user_agents,
+            countries,
+        })
+    }
+    
+    /// حذف دسته‌ای URL‌های کاربر
+    ///
+    /// # مفاهیم:
+    /// - همه حذف‌ها در یک تراکنش انجام میشن
+    /// - شناسه‌هایی که پیدا نشن یا مال کاربر نباشن در `failed_ids` برمیگردن
+    #[instrument(skip(self, request), fields(count = request.ids.len()))]
+    pub async fn batch_delete(
+        &self,
+        user_id: &str,
+        request: BatchDeleteRequest,
+    ) -> Result<BatchOperationResponse> {
+        request.validate()?;
+        
+        let deleted = self.repo.delete_many_owned(&request.ids, user_id).await?;
+        self.redirect_cache.invalidate_url_ids(&deleted);
+        
+        let failed_ids: Vec<String> = request.ids
+            .into_iter()
+            .filter(|id| !deleted.contains(id))
+            .collect();
+        
+        info!(deleted = deleted.len(), failed = failed_ids.len(), "Batch deleted URLs");
+        
+        Ok(BatchOperationResponse::new(deleted.len(), failed_ids))
+    }
+    
     /// تولید کد یکتا
     ///
     /// # مفاهیم:
@@ -246,6 +582,20 @@ impl UrlService {
         ))
     }
     
+    /// نوشتن کلیک‌هایی که هنوز در صف `ClickWriter` هستن
+    ///
+    /// موقع shutdown صدا زده میشه تا هیچ کلیکی گم نشه.
+    /// redirect‌های بعد از این هنوز کار میکنن و در flush بعدی نوشته میشن.
+    pub async fn drain_background_writes(&self) {
+        let pending = self.click_writer.stats();
+        let pending = pending.queued as u64 + pending.buffered;
+        if pending > 0 {
+            info!(pending, "Flushing buffered click writes");
+        }
+        
+        self.click_writer.flush().await;
+    }
+    
     /// پاکسازی URL‌های منقضی
     pub async fn cleanup_expired(&self) -> Result<u64> {
         let deleted = self.repo.delete_expired().await?;
@@ -269,9 +619,50 @@ impl UrlService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::database::MemoryStore;
+    use crate::models::RedirectType;
     
     // تست‌های unit برای توابع pure
-    // تست‌های integration با database mock
+    // تست‌های سرویس روی MemoryStore (بدون دیتابیس)
+    
+    /// ساخت سرویس روی store در حافظه
+    fn test_service() -> (UrlService, MemoryStore) {
+        test_service_with(Config::default())
+    }
+    
+    fn test_service_with(config: Config) -> (UrlService, MemoryStore) {
+        let store = MemoryStore::new();
+        let config = Arc::new(config);
+        let service = UrlService::new(
+            Arc::new(store.clone()),
+            Arc::new(store.clone()),
+            Arc::new(RedirectCache::new(100, std::time::Duration::from_secs(60))),
+            Arc::new(ClickWriter::spawn(Arc::new(store.clone()), &config)),
+            config,
+        );
+        (service, store)
+    }
+    
+    fn create_request(url: &str) -> CreateUrlRequest {
+        CreateUrlRequest {
+            url: url.to_string(),
+            custom_code: None,
+            title: None,
+            starts_at: None,
+            expires_in_hours: None,
+            redirect_type: None,
+            password: None,
+            max_clicks: None,
+        }
+    }
+    
+    /// redirect بدون رمز که باید مستقیم به مقصد برسه
+    async fn redirect(service: &UrlService, short_code: &str) -> CachedRedirect {
+        match service.get_original_url(short_code, ClickInfo::default()).await.unwrap() {
+            RedirectOutcome::Redirect(target) => target,
+            RedirectOutcome::PasswordRequired => panic!("unexpected password prompt"),
+        }
+    }
     
     #[test]
     fn test_url_validation() {
@@ -284,5 +675,480 @@ mod tests {
         let code = utils::generate_short_code();
         assert!(utils::is_valid_short_code(&code));
     }
+    
+    #[tokio::test(start_paused = true)]
+    async fn test_redirect_records_click_event() {
+        let (service, store) = test_service();
+        
+        let url = service
+            .create_short_url(create_request("https://example.com"), None)
+            .await
+            .unwrap();
+        
+        let click = ClickInfo {
+            ip_address: Some("203.0.113.7".to_string()),
+            user_agent: Some("test-agent".to_string()),
+            referer: None,
+            country: Some("DE".to_string()),
+        };
+        service.get_original_url(&url.short_code, click).await.unwrap();
+        
+        // ثبت کلیک تا flush بعدی (هر ثانیه) عقب میفته
+        assert_eq!(store.count_by_url(&url.id).await.unwrap(), 0);
+        tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+        assert_eq!(store.count_by_url(&url.id).await.unwrap(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_drain_background_writes_flushes_clicks() {
+        let (service, store) = test_service();
+        
+        let url = service
+            .create_short_url(create_request("https://example.com"), None)
+            .await
+            .unwrap();
+        
+        for _ in 0..5 {
+            service
+                .get_original_url(&url.short_code, ClickInfo::default())
+                .await
+                .unwrap();
+        }
+        
+        // بعد از drain همه کلیک‌ها بدون صبر کردن ثبت شدن
+        service.drain_background_writes().await;
+        
+        assert_eq!(store.count_by_url(&url.id).await.unwrap(), 5);
+        let info = service.get_url_info(&url.short_code, None).await.unwrap();
+        assert_eq!(info.clicks, 5);
+    }
+    
+    #[tokio::test]
+    async fn test_redirect_type_default_and_override() {
+        let config = crate::config::ConfigBuilder::new()
+            .default_redirect_type(RedirectType::PermanentRedirect)
+            .build();
+        let (service, _) = test_service_with(config);
+        let owner = "owner-id".to_string();
+        
+        let url = service
+            .create_short_url(create_request("https://example.com"), Some(owner.clone()))
+            .await
+            .unwrap();
+        assert_eq!(url.redirect_type, RedirectType::PermanentRedirect);
+        
+        let request = CreateUrlRequest {
+            redirect_type: Some(RedirectType::Found),
+            ..create_request("https://example.com/legacy")
+        };
+        let legacy = service.create_short_url(request, None).await.unwrap();
+        let target = redirect(&service, &legacy.short_code).await;
+        assert_eq!(target.redirect_type, RedirectType::Found);
+        
+        // ویرایش نوع، ورودی cache رو هم عوض میکنه
+        let target = redirect(&service, &url.short_code).await;
+        assert_eq!(target.redirect_type, RedirectType::PermanentRedirect);
+        let request = UpdateUrlRequest {
+            redirect_type: Some(RedirectType::MovedPermanently),
+            ..Default::default()
+        };
+        let updated = service.update_url(&url.short_code, &owner, request).await.unwrap();
+        assert_eq!(updated.redirect_type, RedirectType::MovedPermanently);
+        let target = redirect(&service, &url.short_code).await;
+        assert_eq!(target.redirect_type, RedirectType::MovedPermanently);
+    }
+    
+    #[tokio::test]
+    async fn test_password_protected_url() {
+        let (service, store) = test_service();
+        let owner = "owner-id".to_string();
+        
+        let request = CreateUrlRequest {
+            password: Some("s3cret".to_string()),
+            ..create_request("https://example.com/private")
+        };
+        let url = service.create_short_url(request, Some(owner.clone())).await.unwrap();
+        assert!(url.password_protected);
+        
+        // بدون رمز نه مقصد فاش میشه نه کلیک ثبت میشه
+        let outcome = service.get_original_url(&url.short_code, ClickInfo::default()).await.unwrap();
+        assert!(matches!(outcome, RedirectOutcome::PasswordRequired));
+        let result = service.get_url_info(&url.short_code, None).await;
+        assert!(matches!(result, Err(AppError::Forbidden(_))));
+        let result = service.get_url_info(&url.short_code, Some("someone-else")).await;
+        assert!(matches!(result, Err(AppError::Forbidden(_))));
+        assert!(service.get_url_info(&url.short_code, Some(&owner)).await.is_ok());
+        
+        let result = service.unlock_url(&url.short_code, "wrong", ClickInfo::default()).await;
+        assert!(matches!(result, Err(AppError::Unauthorized(_))));
+        let target = service
+            .unlock_url(&url.short_code, "s3cret", ClickInfo::default())
+            .await
+            .unwrap();
+        assert_eq!(target.original_url, "https://example.com/private");
+        
+        service.drain_background_writes().await;
+        assert_eq!(store.count_by_url(&url.id).await.unwrap(), 1);
+        
+        // رمز کوتاه رد میشه و رشته خالی رمز رو برمیداره
+        let request = UpdateUrlRequest {
+            password: Some(Some("abc".to_string())),
+            ..Default::default()
+        };
+        let result = service.update_url(&url.short_code, &owner, request).await;
+        assert!(matches!(result, Err(AppError::Validation(_))));
+        
+        let request: UpdateUrlRequest = serde_json::from_str(r#"{"password": ""}"#).unwrap();
+        assert_eq!(request.password, Some(None));
+        let updated = service.update_url(&url.short_code, &owner, request).await.unwrap();
+        assert!(!updated.password_protected);
+        let target = redirect(&service, &url.short_code).await;
+        assert_eq!(target.original_url, "https://example.com/private");
+    }
+    
+    #[tokio::test]
+    async fn test_scheduled_url_not_available_before_start() {
+        let (service, store) = test_service();
+        let launch = Utc::now() + chrono::Duration::hours(1);
+        
+        let request = CreateUrlRequest {
+            starts_at: Some(launch),
+            ..create_request("https://example.com/campaign")
+        };
+        let url = service.create_short_url(request, None).await.unwrap();
+        assert_eq!(url.starts_at, Some(launch));
+        
+        // قبل از شروع: خطای جدا از 404 و بدون ثبت کلیک
+        let result = service.get_original_url(&url.short_code, ClickInfo::default()).await;
+        assert!(matches!(result, Err(AppError::NotYetAvailable(at)) if at == launch));
+        service.drain_background_writes().await;
+        assert_eq!(store.count_by_url(&url.id).await.unwrap(), 0);
+        
+        let request = CreateUrlRequest {
+            starts_at: Some(Utc::now() - chrono::Duration::minutes(1)),
+            ..create_request("https://example.com/live")
+        };
+        let live = service.create_short_url(request, None).await.unwrap();
+        assert_eq!(redirect(&service, &live.short_code).await.original_url, "https://example.com/live");
+        
+        // شروع بعد از انقضا رد میشه
+        let request = CreateUrlRequest {
+            starts_at: Some(Utc::now() + chrono::Duration::hours(48)),
+            expires_in_hours: Some(24),
+            ..create_request("https://example.com/never")
+        };
+        let result = service.create_short_url(request, None).await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+    
+    #[tokio::test]
+    async fn test_update_starts_at() {
+        let (service, _) = test_service();
+        let owner = "owner-id".to_string();
+        
+        let url = service
+            .create_short_url(create_request("https://example.com/launch"), Some(owner.clone()))
+            .await
+            .unwrap();
+        redirect(&service, &url.short_code).await;
+        
+        // تعویق شروع روی redirect cache شده هم اثر داره
+        let launch = Utc::now() + chrono::Duration::hours(2);
+        let request = UpdateUrlRequest { starts_at: Some(Some(launch)), ..Default::default() };
+        let updated = service.update_url(&url.short_code, &owner, request).await.unwrap();
+        assert_eq!(updated.starts_at, Some(launch));
+        let result = service.get_original_url(&url.short_code, ClickInfo::default()).await;
+        assert!(matches!(result, Err(AppError::NotYetAvailable(at)) if at == launch));
+        
+        // شروع بعد از انقضا رد میشه
+        let request = UpdateUrlRequest { expires_in_hours: Some(1), ..Default::default() };
+        let result = service.update_url(&url.short_code, &owner, request).await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+        
+        let cleared: UpdateUrlRequest = serde_json::from_str(r#"{"starts_at": null}"#).unwrap();
+        let updated = service.update_url(&url.short_code, &owner, cleared).await.unwrap();
+        assert_eq!(updated.starts_at, None);
+        assert_eq!(redirect(&service, &url.short_code).await.original_url, "https://example.com/launch");
+    }
+    
+    #[tokio::test]
+    async fn test_one_time_url_is_atomic() {
+        let (service, store) = test_service();
+        let service = Arc::new(service);
+        
+        let request = CreateUrlRequest {
+            max_clicks: Some(1),
+            ..create_request("https://example.com/reset")
+        };
+        let url = service.create_short_url(request, None).await.unwrap();
+        assert_eq!(url.max_clicks, Some(1));
+        
+        // از چند redirect همزمان فقط یکی موفق میشه
+        let attempts: Vec<_> = (0..10)
+            .map(|_| {
+                let service = service.clone();
+                let code = url.short_code.clone();
+                tokio::spawn(async move {
+                    service.get_original_url(&code, ClickInfo::default()).await
+                })
+            })
+            .collect();
+        
+        let mut redirected = 0;
+        for attempt in attempts {
+            match attempt.await.unwrap() {
+                Ok(RedirectOutcome::Redirect(_)) => redirected += 1,
+                Err(AppError::Gone(_)) => {}
+                other => panic!("unexpected outcome: {:?}", other),
+            }
+        }
+        assert_eq!(redirected, 1);
+        
+        service.drain_background_writes().await;
+        let info = service.get_url_info(&url.short_code, None).await.unwrap();
+        assert_eq!(info.clicks, 1);
+        assert_eq!(store.count_by_url(&url.id).await.unwrap(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_update_max_clicks() {
+        let (service, _) = test_service();
+        let owner = "owner-id".to_string();
+        
+        let request = CreateUrlRequest {
+            max_clicks: Some(1),
+            ..create_request("https://example.com/once")
+        };
+        let url = service.create_short_url(request, Some(owner.clone())).await.unwrap();
+        assert!(service.get_original_url(&url.short_code, ClickInfo::default()).await.is_ok());
+        assert!(matches!(
+            service.get_original_url(&url.short_code, ClickInfo::default()).await,
+            Err(AppError::Gone(_))
+        ));
+        
+        let zero: UpdateUrlRequest = serde_json::from_str(r#"{"max_clicks": 0}"#).unwrap();
+        let result = service.update_url(&url.short_code, &owner, zero).await;
+        assert!(matches!(result, Err(AppError::Validation(_))));
+        
+        // فیلد ارسال نشده سقف رو نگه میداره، `null` حذفش میکنه
+        let untouched: UpdateUrlRequest = serde_json::from_str(r#"{"title": "Once"}"#).unwrap();
+        let updated = service.update_url(&url.short_code, &owner, untouched).await.unwrap();
+        assert_eq!(updated.max_clicks, Some(1));
+        
+        let cleared: UpdateUrlRequest = serde_json::from_str(r#"{"max_clicks": null}"#).unwrap();
+        let updated = service.update_url(&url.short_code, &owner, cleared).await.unwrap();
+        assert_eq!(updated.max_clicks, None);
+        assert!(service.get_original_url(&url.short_code, ClickInfo::default()).await.is_ok());
+        
+        let raised = UpdateUrlRequest { max_clicks: Some(Some(5)), ..Default::default() };
+        let updated = service.update_url(&url.short_code, &owner, raised).await.unwrap();
+        assert_eq!(updated.max_clicks, Some(5));
+    }
+    
+    #[tokio::test]
+    async fn test_redirect_cache_invalidated_on_update_and_delete() {
+        let (service, _) = test_service();
+        let owner = "owner-id".to_string();
+        
+        let url = service
+            .create_short_url(create_request("https://example.com/old"), Some(owner.clone()))
+            .await
+            .unwrap();
+        
+        for _ in 0..3 {
+            let target = redirect(&service, &url.short_code).await;
+            assert_eq!(target.original_url, "https://example.com/old");
+        }
+        let stats = service.redirect_cache.stats();
+        assert_eq!((stats.hits, stats.misses), (2, 1));
+        
+        let request = UpdateUrlRequest {
+            url: Some("https://example.com/new".to_string()),
+            ..Default::default()
+        };
+        service.update_url(&url.short_code, &owner, request).await.unwrap();
+        let target = redirect(&service, &url.short_code).await;
+        assert_eq!(target.original_url, "https://example.com/new");
+        
+        service.delete_url(&url.short_code, &owner).await.unwrap();
+        let result = service.get_original_url(&url.short_code, ClickInfo::default()).await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+        
+        // ورودی منفی با ساخت دوباره همون کد پاک میشه
+        let request = CreateUrlRequest {
+            custom_code: Some(url.short_code.clone()),
+            ..create_request("https://example.com/again")
+        };
+        service.create_short_url(request, None).await.unwrap();
+        let target = redirect(&service, &url.short_code).await;
+        assert_eq!(target.original_url, "https://example.com/again");
+    }
+    
+    #[tokio::test]
+    async fn test_url_analytics_owner_only() {
+        use crate::models::AnalyticsInterval;
+        
+        let (service, store) = test_service();
+        let owner = "owner-id".to_string();
+        
+        let url = service
+            .create_short_url(create_request("https://example.com"), Some(owner.clone()))
+            .await
+            .unwrap();
+        
+        for (referer, country) in [("https://a.example", "DE"), ("https://a.example", "FR"), ("https://b.example", "DE")] {
+            let click = ClickInfo {
+                referer: Some(referer.to_string()),
+                country: Some(country.to_string()),
+                ..Default::default()
+            };
+            ClickStore::create(&store, &CreateClickEvent::new(&url.id, click)).await.unwrap();
+        }
+        
+        let analytics = service
+            .get_url_analytics(&url.short_code, &owner, AnalyticsQuery::default())
+            .await
+            .unwrap();
+        
+        assert_eq!(analytics.interval, AnalyticsInterval::Day);
+        assert_eq!(analytics.total_clicks, 3);
+        assert_eq!(analytics.timeline.len(), 1);
+        assert_eq!(analytics.timeline[0].clicks, 3);
+        assert_eq!(analytics.top_referrers[0].value, "https://a.example");
+        assert_eq!(analytics.top_referrers[0].clicks, 2);
+        assert_eq!(analytics.countries[0].value, "DE");
+        assert_eq!(analytics.countries[0].clicks, 2);
+        
+        let other = service
+            .get_url_analytics(&url.short_code, "someone-else", AnalyticsQuery::default())
+            .await;
+        assert!(matches!(other, Err(AppError::Forbidden(_))));
+    }
+    
+    #[tokio::test]
+    async fn test_update_url_keeps_clicks() {
+        let (service, _) = test_service();
+        let owner = "owner-id".to_string();
+        
+        let url = service
+            .create_short_url(create_request("https://exmaple.com/typo"), Some(owner.clone()))
+            .await
+            .unwrap();
+        service.repo.increment_clicks(&url.short_code).await.unwrap();
+        
+        let request = UpdateUrlRequest {
+            url: Some("https://example.com/fixed".to_string()),
+            title: Some("Fixed".to_string()),
+            ..Default::default()
+        };
+        let updated = service.update_url(&url.short_code, &owner, request).await.unwrap();
+        
+        assert_eq!(updated.short_code, url.short_code);
+        assert_eq!(updated.original_url, "https://example.com/fixed");
+        assert_eq!(updated.title.as_deref(), Some("Fixed"));
+        assert_eq!(updated.clicks, 1);
+        
+        // رشته خالی و صفر، عنوان و انقضا رو پاک میکنن
+        let request = UpdateUrlRequest {
+            expires_in_hours: Some(24),
+            ..Default::default()
+        };
+        let updated = service.update_url(&url.short_code, &owner, request).await.unwrap();
+        assert!(updated.expires_at.is_some());
+        
+        let request = UpdateUrlRequest {
+            title: Some(String::new()),
+            expires_in_hours: Some(0),
+            ..Default::default()
+        };
+        let updated = service.update_url(&url.short_code, &owner, request).await.unwrap();
+        assert_eq!(updated.title, None);
+        assert_eq!(updated.expires_at, None);
+        
+        let other = service
+            .update_url(&url.short_code, "someone-else", UpdateUrlRequest::default())
+            .await;
+        assert!(matches!(other, Err(AppError::Forbidden(_))));
+    }
+    
+    #[tokio::test]
+    async fn test_batch_delete_reports_failures() {
+        let (service, _) = test_service();
+        let owner = "owner-id".to_string();
+        let other = "other-id".to_string();
+        
+        let mine = service
+            .create_short_url(create_request("https://example.com/1"), Some(owner.clone()))
+            .await
+            .unwrap();
+        let theirs = service
+            .create_short_url(create_request("https://example.com/2"), Some(other))
+            .await
+            .unwrap();
+        
+        let request = BatchDeleteRequest {
+            ids: vec![mine.id.clone(), theirs.id.clone(), "missing".to_string()],
+        };
+        let response = service.batch_delete(&owner, request).await.unwrap();
+        
+        assert_eq!(response.success_count, 1);
+        assert_eq!(response.failed_ids, vec![theirs.id.clone(), "missing".to_string()]);
+        assert!(service.get_url_info(&mine.short_code, None).await.is_err());
+        assert!(service.get_url_info(&theirs.short_code, None).await.is_ok());
+    }
+    
+    #[tokio::test]
+    async fn test_user_urls_search_sort_paginate() {
+        let (service, _) = test_service();
+        let owner = "owner-id".to_string();
+        
+        for (i, path) in ["docs/a", "docs/b", "blog/c"].iter().enumerate() {
+            let url = service
+                .create_short_url(
+                    create_request(&format!("https://example.com/{}", path)),
+                    Some(owner.clone()),
+                )
+                .await
+                .unwrap();
+            for _ in 0..i {
+                service.repo.increment_clicks(&url.short_code).await.unwrap();
+            }
+        }
+        
+        // جستجو + مرتب‌سازی بر اساس کلیک
+        let search = SearchParams {
+            query: Some("docs".to_string()),
+            sort_by: Some("clicks".to_string()),
+            ..Default::default()
+        };
+        let page = service
+            .get_user_urls(&owner, &search, &Pagination { page: 1, per_page: 1 })
+            .await
+            .unwrap();
+        
+        assert_eq!(page.pagination.total_items, 2);
+        assert_eq!(page.pagination.total_pages, 2);
+        assert!(page.pagination.has_next);
+        assert_eq!(page.data.len(), 1);
+        assert_eq!(page.data[0].original_url, "https://example.com/docs/b");
+        
+        // فیلتر وضعیت
+        let expired = SearchParams {
+            status: Some("expired".to_string()),
+            ..Default::default()
+        };
+        let page = service
+            .get_user_urls(&owner, &expired, &Pagination::default())
+            .await
+            .unwrap();
+        assert_eq!(page.pagination.total_items, 0);
+        
+        // پارامتر نامعتبر
+        let invalid = SearchParams {
+            sort_by: Some("title; DROP TABLE urls".to_string()),
+            ..Default::default()
+        };
+        let result = service.get_user_urls(&owner, &invalid, &Pagination::default()).await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
 }
-
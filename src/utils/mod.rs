@@ -112,9 +112,10 @@ pub fn encode_id_to_short_code(id: u64) -> String {
 /// دیکود کردن short code به ID
 ///
 /// # Errors
-/// خطا برمیگردونه اگه فرمت نامعتبر باشه
+/// خطا برمیگردونه اگه فرمت نامعتبر باشه یا عدد در `u64` جا نشه
 pub fn decode_short_code_to_id(code: &str) -> Result<u64, base62::DecodeError> {
-    base62::decode(code)
+    let id = base62::decode(code)?;
+    u64::try_from(id).map_err(|_| base62::DecodeError::ArithmeticOverflow)
 }
 
 // =====================================
@@ -139,7 +140,7 @@ pub fn is_valid_short_code(code: &str) -> bool {
     let len = code.len();
     
     // چک کردن طول
-    if len < MIN_CUSTOM_CODE_LENGTH || len > MAX_CUSTOM_CODE_LENGTH {
+    if !(MIN_CUSTOM_CODE_LENGTH..=MAX_CUSTOM_CODE_LENGTH).contains(&len) {
         return false;
     }
     
@@ -195,7 +196,7 @@ pub fn normalize_url(url_str: &str) -> Option<String> {
 /// use url_shortener::utils::truncate;
 ///
 /// let text = "Hello, World!";
-/// assert_eq!(truncate(text, Some(5)), "Hello...");
+/// assert_eq!(truncate(text, Some(8)), "Hello...");
 /// ```
 #[must_use]
 pub fn truncate(text: &str, max_len: Option<usize>) -> String {
@@ -355,6 +356,13 @@ mod tests {
         let encoded = encode_id_to_short_code(id);
         let decoded = decode_short_code_to_id(&encoded).unwrap();
         assert_eq!(id, decoded);
+        
+        // بزرگتر از u64::MAX بریده نمیشه
+        let too_big = base62::encode(u128::from(u64::MAX) + 1);
+        assert_eq!(
+            decode_short_code_to_id(&too_big),
+            Err(base62::DecodeError::ArithmeticOverflow)
+        );
     }
     
     #[test]
@@ -17,8 +17,6 @@
 //! cargo test url_               # تست‌هایی که با url_ شروع میشن
 //! ```
 
-use std::time::Duration;
-
 // =====================================
 // تست‌های Utils
 // =====================================
@@ -273,6 +271,17 @@ mod model_tests {
         assert_eq!(large.limit(), 100);  // حداکثر 100
     }
     
+    /// تست محدود کردن پارامترهای صفحه‌بندی
+    #[test]
+    fn test_pagination_normalized() {
+        let raw = Pagination { page: 0, per_page: 500 };
+        let normalized = raw.normalized();
+        
+        assert_eq!(normalized.page, 1);
+        assert_eq!(normalized.per_page, 100);
+        assert_eq!(Pagination { page: 1, per_page: 0 }.normalized().per_page, 1);
+    }
+    
     /// تست PaginationInfo
     #[test]
     fn test_pagination_info() {
@@ -296,29 +305,528 @@ mod model_tests {
             sub: "user1".to_string(),
             email: "test@test.com".to_string(),
             exp: Utc::now().timestamp() - 3600,
-            iat: Utc::now().timestamp() - 7200,
+            iat: (Utc::now().timestamp() - 7200) as f64,
         };
         assert!(expired.is_expired());
     }
+    
+    /// تست scope‌های API key
+    #[test]
+    fn test_api_key_scopes() {
+        use url_shortener::api::Credential;
+        use url_shortener::models::ApiScope;
+        
+        let scopes: Vec<ApiScope> = serde_json::from_str(r#"["urls:read", "analytics:read"]"#).unwrap();
+        let credential = Credential::ApiKey { key_id: "k1".to_string(), scopes };
+        
+        assert!(credential.require(ApiScope::UrlsRead).is_ok());
+        assert!(credential.require(ApiScope::UrlsWrite).is_err());
+        assert!(credential.require_session().is_err());
+        
+        // نشست JWT همه دسترسی‌ها رو داره
+        assert!(Credential::Session.require(ApiScope::UrlsWrite).is_ok());
+        assert!(Credential::Session.require_session().is_ok());
+        
+        assert!(serde_json::from_str::<ApiScope>(r#""admin""#).is_err());
+    }
+    
+    /// نوع redirect در JSON همون status code هست
+    #[test]
+    fn test_redirect_type_json() {
+        use url_shortener::models::RedirectType;
+        
+        let parsed: RedirectType = serde_json::from_str("301").unwrap();
+        assert_eq!(parsed, RedirectType::MovedPermanently);
+        assert_eq!(serde_json::to_string(&RedirectType::PermanentRedirect).unwrap(), "308");
+        
+        assert!(serde_json::from_str::<RedirectType>("303").is_err());
+        assert!(serde_json::from_str::<RedirectType>(r#""permanent""#).is_err());
+    }
+    
+    /// رمز لینک طول محدود داره
+    #[test]
+    fn test_link_password_validation() {
+        use url_shortener::models::CreateUrlRequest;
+        use validator::Validate;
+        
+        let request = |password: &str| CreateUrlRequest {
+            url: "https://example.com".to_string(),
+            custom_code: None,
+            title: None,
+            starts_at: None,
+            expires_in_hours: None,
+            redirect_type: None,
+            password: Some(password.to_string()),
+            max_clicks: None,
+        };
+        
+        assert!(request("s3cret").validate().is_ok());
+        assert!(request("abc").validate().is_err());
+        assert!(request(&"x".repeat(129)).validate().is_err());
+    }
 }
 
 // =====================================
 // تست‌های Async (با Database)
 // =====================================
+/// تست‌های repository روی backend فعال
+///
+/// # اجرا:
+/// ```bash
+/// cargo test --test integration_tests                     # SQLite in-memory
+/// TEST_DATABASE_URL=postgres://localhost/urls_test \
+///     cargo test --no-default-features --features postgres  # PostgreSQL
+/// ```
 #[cfg(test)]
 mod async_tests {
-    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+    use url_shortener::{
+        database::{ClickRepository, Database, Repository, UrlRepository, UserRepository},
+        models::{
+            AnalyticsInterval, ClickBatch, ClickInfo, CreateClickEvent, CreateUser, Pagination,
+            RedirectType, UrlBuilder, UrlFilter, UserRole,
+        },
+    };
     
     /// تست اتصال به دیتابیس
     /// 
     /// # مفاهیم:
     /// - `#[tokio::test]`: تست async
-    /// - در production از mock استفاده کنید
+    /// - `Database::ephemeral()`: دیتابیس ایزوله برای هر تست
     #[tokio::test]
-    #[ignore]  // نیاز به دیتابیس واقعی داره
     async fn test_database_connection() {
-        // این تست نیاز به setup دیتابیس داره
-        // در CI/CD معمولا از testcontainers استفاده میشه
+        let db = Database::ephemeral().await.unwrap();
+        
+        assert!(db.health_check().await.is_ok());
+        assert_eq!(db.pending_migrations(std::time::Duration::from_secs(1)).await.unwrap(), 0);
+    }
+    
+    /// ساخت، خوندن، شمارش کلیک و آمار URL‌ها
+    #[tokio::test]
+    async fn test_url_repository_roundtrip() {
+        let db = Database::ephemeral().await.unwrap();
+        let repo = UrlRepository::new(db.clone());
+        let users = UserRepository::new(db);
+        
+        let owner = users
+            .create(&CreateUser::new("owner@example.com", "password123", None).unwrap())
+            .await
+            .unwrap();
+        
+        let create = UrlBuilder::new("https://example.com/docs")
+            .custom_code("docs")
+            .title("Example Docs")
+            .user_id(&owner.id)
+            .build()
+            .unwrap();
+        let url = repo.create(&create).await.unwrap();
+        assert_eq!(url.short_code, "docs");
+        assert!(repo.exists("docs").await.unwrap());
+        
+        assert!(repo.increment_clicks("docs").await.unwrap());
+        assert!(repo.increment_clicks("docs").await.unwrap());
+        assert!(!repo.increment_clicks("missing").await.unwrap());
+        let mut url = repo.find_by_short_code("docs").await.unwrap().unwrap();
+        assert_eq!(url.clicks, 2);
+        assert_eq!(url.redirect_type, RedirectType::TemporaryRedirect);
+        
+        assert!(!url.is_password_protected());
+        
+        url.redirect_type = RedirectType::MovedPermanently;
+        url.password_hash = Some("argon2-hash".to_string());
+        url.max_clicks = Some(5);
+        url.starts_at = Utc.with_ymd_and_hms(2030, 1, 1, 9, 0, 0).single();
+        let url = repo.update(&url).await.unwrap();
+        assert_eq!(url.redirect_type, RedirectType::MovedPermanently);
+        assert_eq!(url.password_hash.as_deref(), Some("argon2-hash"));
+        assert_eq!(url.max_clicks, Some(5));
+        assert_eq!(url.starts_at, Utc.with_ymd_and_hms(2030, 1, 1, 9, 0, 0).single());
+        
+        // جستجو روی هر دو backend به حروف بزرگ و کوچک حساس نیست
+        let filter = UrlFilter {
+            query: Some("EXAMPLE docs".to_string()),
+            ..UrlFilter::default()
+        };
+        let (found, total) = repo
+            .search_by_user(&owner.id, &filter, &Pagination::default())
+            .await
+            .unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(found[0].id, url.id);
+        
+        let stats = repo.get_stats().await.unwrap();
+        assert_eq!(stats.total_urls, 1);
+        assert_eq!(stats.total_clicks, 2);
+        assert!((stats.avg_clicks - 2.0).abs() < f64::EPSILON);
+        
+        assert_eq!(repo.count_by_status().await.unwrap(), (1, 0));
+    }
+    
+    /// سقف کلیک داخل همون UPDATE چک میشه
+    #[tokio::test]
+    async fn test_max_clicks_limits_increment() {
+        let db = Database::ephemeral().await.unwrap();
+        let repo = UrlRepository::new(db);
+        
+        let create = UrlBuilder::new("https://example.com/once")
+            .custom_code("twice")
+            .max_clicks(2)
+            .build()
+            .unwrap();
+        repo.create(&create).await.unwrap();
+        
+        assert!(repo.increment_clicks("twice").await.unwrap());
+        assert!(repo.increment_clicks("twice").await.unwrap());
+        assert!(!repo.increment_clicks("twice").await.unwrap());
+        
+        let url = repo.find_by_short_code("twice").await.unwrap().unwrap();
+        assert_eq!((url.clicks, url.max_clicks), (2, Some(2)));
+        assert!(url.is_exhausted());
+    }
+    
+    /// زمان شروع لینک ذخیره و خونده میشه
+    #[tokio::test]
+    async fn test_starts_at_roundtrip() {
+        let db = Database::ephemeral().await.unwrap();
+        let repo = UrlRepository::new(db);
+        let launch = Utc::now() + Duration::days(7);
+        
+        let create = UrlBuilder::new("https://example.com/launch")
+            .custom_code("launch")
+            .starts_at(launch)
+            .build()
+            .unwrap();
+        repo.create(&create).await.unwrap();
+        
+        let url = repo.find_by_short_code("launch").await.unwrap().unwrap();
+        let stored = url.starts_at.unwrap();
+        assert!((stored - launch).num_milliseconds().abs() < 1);
+        assert!(url.is_not_started());
+        
+        // شروع بعد از انقضا معنی نداره
+        let result = UrlBuilder::new("https://example.com/launch")
+            .starts_at(launch)
+            .expires_at(launch - Duration::days(1))
+            .build();
+        assert!(result.is_err());
+    }
+    
+    /// نقش کاربر به صورت TEXT ذخیره و خونده میشه
+    #[tokio::test]
+    async fn test_user_role_roundtrip() {
+        let db = Database::ephemeral().await.unwrap();
+        let users = UserRepository::new(db);
+        
+        let mut user = users
+            .create(&CreateUser::new("admin@example.com", "password123", None).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(user.role, UserRole::User);
+        assert!(users.email_exists("admin@example.com").await.unwrap());
+        
+        user.role = UserRole::Admin;
+        users.update(&user).await.unwrap();
+        
+        let stored = users.find_by_id(&user.id).await.unwrap().unwrap();
+        assert_eq!(stored.role, UserRole::Admin);
+        assert_eq!(users.count_active().await.unwrap(), 1);
+    }
+    
+    /// کلید bucket‌های سری زمانی روی هر دو backend یکسان هستن
+    #[tokio::test]
+    async fn test_click_timeline_buckets() {
+        let db = Database::ephemeral().await.unwrap();
+        let urls = UrlRepository::new(db.clone());
+        let clicks = ClickRepository::new(db);
+        
+        let now = Utc::now();
+        let url = urls
+            .create(&UrlBuilder::new("https://example.com").build().unwrap())
+            .await
+            .unwrap();
+        for _ in 0..3 {
+            clicks.create(&CreateClickEvent::new(&url.id, ClickInfo::default())).await.unwrap();
+        }
+        
+        let from = now - Duration::days(1);
+        let to = now + Duration::minutes(1);
+        
+        let days = clicks.timeline(&url.id, AnalyticsInterval::Day, from, to).await.unwrap();
+        assert_eq!(days.len(), 1);
+        assert_eq!(days[0].bucket, now.format("%Y-%m-%d").to_string());
+        assert_eq!(days[0].clicks, 3);
+        
+        let hours = clicks.timeline(&url.id, AnalyticsInterval::Hour, from, to).await.unwrap();
+        assert_eq!(hours[0].bucket, now.format("%Y-%m-%dT%H:00:00Z").to_string());
+        
+        let per_day = urls.count_created_per_day(from).await.unwrap();
+        assert_eq!(per_day[0].date, now.format("%Y-%m-%d").to_string());
+        assert_eq!(per_day[0].count, 1);
+    }
+    
+    /// batch کلیک‌ها در یک تراکنش نوشته میشه و لینک حذف شده اون رو fail نمیکنه
+    #[tokio::test]
+    async fn test_click_batch_is_recorded_atomically() {
+        let db = Database::ephemeral().await.unwrap();
+        let urls = UrlRepository::new(db.clone());
+        let clicks = ClickRepository::new(db);
+        
+        let kept = urls
+            .create(&UrlBuilder::new("https://example.com/kept").build().unwrap())
+            .await
+            .unwrap();
+        let gone = urls
+            .create(&UrlBuilder::new("https://example.com/gone").build().unwrap())
+            .await
+            .unwrap();
+        
+        let mut batch = ClickBatch::default();
+        for url in [&kept, &kept, &gone] {
+            batch.push(CreateClickEvent::new(&url.id, ClickInfo::default()));
+        }
+        urls.delete(&gone.id).await.unwrap();
+        
+        // کد حذف شده دوباره استفاده میشه؛ کلیک‌های لینک قبلی بهش نمیرسن
+        let reused = urls
+            .create(&UrlBuilder::new("https://example.com/reused").custom_code(&gone.short_code).build().unwrap())
+            .await
+            .unwrap();
+        
+        assert_eq!(clicks.record_batch(&batch).await.unwrap(), 2);
+        
+        let stored = urls.find_by_short_code(&reused.short_code).await.unwrap().unwrap();
+        assert_eq!(stored.clicks, 0);
+        
+        let stored = urls.find_by_short_code(&kept.short_code).await.unwrap().unwrap();
+        assert_eq!(stored.clicks, 2);
+        assert_eq!(clicks.count_by_url(&kept.id).await.unwrap(), 2);
+    }
+}
+
+// =====================================
+// تست‌های Router
+// =====================================
+/// کل router بدون سرور و سوکت (بیشتر روی `AppState::in_memory`)
+///
+/// # مفاهیم:
+/// - `tower::ServiceExt::oneshot`: فرستادن یک request مستقیم به router
+#[cfg(test)]
+mod router_tests {
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+        Router,
+    };
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+    use url_shortener::{api::create_router_with_state, config::Config, services::AppState};
+    
+    async fn send(app: &Router, request: Request<Body>) -> (StatusCode, Value) {
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json = serde_json::from_slice(&body).unwrap_or(Value::Null);
+        (status, json)
+    }
+    
+    fn post_json(uri: &str, token: Option<&str>, body: &Value) -> Request<Body> {
+        let mut request = Request::post(uri).header(header::CONTENT_TYPE, "application/json");
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        request.body(Body::from(body.to_string())).unwrap()
+    }
+    
+    #[tokio::test]
+    async fn test_in_memory_app_serves_requests() {
+        let app = create_router_with_state(AppState::in_memory(Config::default()));
+        
+        let ready = Request::get("/health/ready").body(Body::empty()).unwrap();
+        let (status, body) = send(&app, ready).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "ready");
+        
+        let credentials = json!({ "email": "demo@example.com", "password": "password123" });
+        let (status, _) = send(&app, post_json("/api/auth/register", None, &credentials)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        
+        let (status, body) = send(&app, post_json("/api/auth/login", None, &credentials)).await;
+        assert_eq!(status, StatusCode::OK);
+        let token = body["data"]["token"].as_str().unwrap().to_string();
+        
+        let link = json!({ "url": "https://example.com/demo", "custom_code": "demo1" });
+        let (status, _) = send(&app, post_json("/api/urls", Some(&token), &link)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        
+        let redirect = app
+            .clone()
+            .oneshot(Request::get("/demo1").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert!(redirect.status().is_redirection());
+        assert_eq!(redirect.headers()[header::LOCATION], "https://example.com/demo");
+        
+        let mine = Request::get("/api/me/urls")
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+        let (status, body) = send(&app, mine).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["data"][0]["short_code"], "demo1");
+    }
+    
+    #[tokio::test]
+    async fn test_url_info_requires_read_scope() {
+        let app = create_router_with_state(AppState::in_memory(Config::default()));
+        
+        let credentials = json!({ "email": "scope@example.com", "password": "password123" });
+        send(&app, post_json("/api/auth/register", None, &credentials)).await;
+        let (_, body) = send(&app, post_json("/api/auth/login", None, &credentials)).await;
+        let token = body["data"]["token"].as_str().unwrap().to_string();
+        
+        let link = json!({ "url": "https://example.com/scoped", "custom_code": "scope1" });
+        send(&app, post_json("/api/urls", Some(&token), &link)).await;
+        
+        let mut keys = Vec::new();
+        for scope in ["urls:write", "urls:read"] {
+            let request = json!({ "name": scope, "scopes": [scope] });
+            let (status, body) = send(&app, post_json("/api/me/api-keys", Some(&token), &request)).await;
+            assert_eq!(status, StatusCode::CREATED);
+            keys.push(body["data"]["key"].as_str().unwrap().to_string());
+        }
+        
+        let info = |key: &str| {
+            Request::get("/api/urls/scope1")
+                .header("X-API-Key", key)
+                .body(Body::empty())
+                .unwrap()
+        };
+        assert_eq!(send(&app, info(&keys[0])).await.0, StatusCode::FORBIDDEN);
+        
+        let (status, body) = send(&app, info(&keys[1])).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["short_code"], "scope1");
+    }
+    
+    #[tokio::test]
+    async fn test_delete_requires_owner_credentials() {
+        let app = create_router_with_state(AppState::in_memory(Config::default()));
+        
+        let mut tokens = Vec::new();
+        for email in ["owner@example.com", "other@example.com"] {
+            let credentials = json!({ "email": email, "password": "password123" });
+            send(&app, post_json("/api/auth/register", None, &credentials)).await;
+            let (_, body) = send(&app, post_json("/api/auth/login", None, &credentials)).await;
+            tokens.push(body["data"]["token"].as_str().unwrap().to_string());
+        }
+        
+        let link = json!({ "url": "https://example.com/owned", "custom_code": "own1" });
+        send(&app, post_json("/api/urls", Some(&tokens[0]), &link)).await;
+        
+        let delete = |header: Option<(&'static str, String)>| {
+            let mut request = Request::delete("/api/urls/own1");
+            if let Some((name, value)) = header {
+                request = request.header(name, value);
+            }
+            request.body(Body::empty()).unwrap()
+        };
+        let bearer = |token: &str| Some(("Authorization", format!("Bearer {}", token)));
+        
+        // بدون اعتبارنامه، با اعتبارنامه خراب و با کاربر دیگه حذف نمیشه
+        assert_eq!(send(&app, delete(None)).await.0, StatusCode::UNAUTHORIZED);
+        assert_eq!(send(&app, delete(bearer("not-a-jwt"))).await.0, StatusCode::UNAUTHORIZED);
+        let fake_key = Some(("X-API-Key", "usk_fake".to_string()));
+        assert_eq!(send(&app, delete(fake_key)).await.0, StatusCode::UNAUTHORIZED);
+        assert_eq!(send(&app, delete(bearer(&tokens[1]))).await.0, StatusCode::FORBIDDEN);
+        
+        // اعتبارنامه خراب روی endpoint‌های OptionalAuth هم ناشناس حساب نمیشه
+        let info = Request::get("/api/urls/own1")
+            .header("Authorization", "Bearer not-a-jwt")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(send(&app, info).await.0, StatusCode::UNAUTHORIZED);
+        
+        assert_eq!(send(&app, delete(bearer(&tokens[0]))).await.0, StatusCode::NO_CONTENT);
+        let info = Request::get("/api/urls/own1").body(Body::empty()).unwrap();
+        assert_eq!(send(&app, info).await.0, StatusCode::NOT_FOUND);
+    }
+    
+    #[tokio::test]
+    async fn test_update_validates_link_password() {
+        let app = create_router_with_state(AppState::in_memory(Config::default()));
+        
+        let credentials = json!({ "email": "lock@example.com", "password": "password123" });
+        send(&app, post_json("/api/auth/register", None, &credentials)).await;
+        let (_, body) = send(&app, post_json("/api/auth/login", None, &credentials)).await;
+        let token = body["data"]["token"].as_str().unwrap().to_string();
+        
+        let link = json!({ "url": "https://example.com/locked", "custom_code": "lock1", "password": "s3cret" });
+        send(&app, post_json("/api/urls", Some(&token), &link)).await;
+        
+        let patch = |body: Value| {
+            Request::patch("/api/urls/lock1")
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        
+        let (status, body) = send(&app, patch(json!({ "password": "abc" }))).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(body["message"].as_str().unwrap().contains("4-128"));
+        
+        let (status, body) = send(&app, patch(json!({ "password": "" }))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["password_protected"], false);
+    }
+    
+    #[tokio::test]
+    async fn test_unlock_limit_counts_only_wrong_passwords() {
+        let config = Config { unlock_attempts_per_minute: 2, ..Config::default() };
+        let app = create_router_with_state(AppState::in_memory(config));
+        
+        let credentials = json!({ "email": "unlock@example.com", "password": "password123" });
+        send(&app, post_json("/api/auth/register", None, &credentials)).await;
+        let (_, body) = send(&app, post_json("/api/auth/login", None, &credentials)).await;
+        let token = body["data"]["token"].as_str().unwrap().to_string();
+        
+        let link = json!({ "url": "https://example.com/secret", "custom_code": "sec1", "password": "s3cret" });
+        send(&app, post_json("/api/urls", Some(&token), &link)).await;
+        
+        let unlock = |password: &str| {
+            Request::post("/sec1")
+                .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(Body::from(format!("password={}", password)))
+                .unwrap()
+        };
+        
+        // رمز درست از سقف کم نمیکنه
+        for _ in 0..4 {
+            assert_eq!(send(&app, unlock("s3cret")).await.0, StatusCode::SEE_OTHER);
+        }
+        
+        assert_eq!(send(&app, unlock("wrong")).await.0, StatusCode::UNAUTHORIZED);
+        assert_eq!(send(&app, unlock("wrong")).await.0, StatusCode::UNAUTHORIZED);
+        
+        // بعد از سقف رمز‌های اشتباه، لینک حتی برای رمز درست قفله
+        assert_eq!(send(&app, unlock("s3cret")).await.0, StatusCode::TOO_MANY_REQUESTS);
+    }
+    
+    #[tokio::test]
+    async fn test_health_reports_unreachable_database() {
+        let db = url_shortener::database::Database::ephemeral().await.unwrap();
+        db.close().await;
+        let app = create_router_with_state(AppState::new(db, Config::default()));
+        
+        let (status, body) = send(&app, Request::get("/health").body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["status"], "degraded");
+        
+        let (status, body) = send(&app, Request::get("/health/ready").body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["database"]["reachable"], false);
+        assert!(body["database"]["error"].is_string());
+        assert!(body["database"].get("migration_error").is_none());
     }
 }
 